@@ -0,0 +1,117 @@
+use rcann::activation::ActivationFn;
+use rcann::loss::LossFn;
+use rcann::net::initializer::RandomNetInitializer;
+use rcann::net::layer::{
+    ConcreteLayerParams, Conv2dLayerParams, DenseLayerParams, DropoutLayerParams, Pool2dLayerParams,
+};
+use rcann::net::{NetBuilder, TrainConfig, Trainer};
+use rcann::optimizer::OptimizerFn;
+use rcann::scoring::MulticlassScorer;
+use rcann::tensor::{Dim3, TensorBase};
+use rcann_examples::util::{load_mnist_data, MnistData};
+use rcann_opencl::backend::{GemmTuner, OpenCLBackend, OpenCLBackendOptions};
+use rcann_opencl::util::{self, VecWidth};
+
+const MAX_BATCH_SIZE: usize = 64;
+/// the fastest matrix multiplication kernel for the device, which is only searched for on the first run
+const GEMM_CONFIG_PATH: &str = "rcann-examples/data/gemm_config.txt";
+
+pub fn main() {
+    let MnistData {
+        train_images,
+        train_labels,
+        test_images,
+        test_labels,
+    } = load_mnist_data::<f32>(60_000, 10_000);
+
+    let device = util::get_default_device().unwrap();
+    let gemm = GemmTuner::default()
+        .load_or_tune::<f32, _>(&device, GEMM_CONFIG_PATH)
+        .unwrap();
+    let options = OpenCLBackendOptions {
+        gemm: Some(gemm),
+        ..OpenCLBackendOptions::from_env()
+    };
+    let backend = OpenCLBackend::from_device_with_options(device, MAX_BATCH_SIZE, VecWidth::SIXTEEN, options).unwrap();
+    //let backend = CpuBackend::<f32>::new(MAX_BATCH_SIZE);
+
+    let conv1 = Conv2dLayerParams {
+        input_dims: Dim3(1, 28, 28),
+        filters: 6,
+        kernel_size: 5,
+        stride: 1,
+        padding: 2,
+        activation_fn: ActivationFn::ReLU { leak: 0.01 },
+        use_bias: true,
+    };
+    let pool1 = Pool2dLayerParams::new(conv1.output_dims(), 2);
+    let conv2 = Conv2dLayerParams {
+        input_dims: pool1.output_dims(),
+        filters: 16,
+        kernel_size: 5,
+        stride: 1,
+        padding: 0,
+        activation_fn: ActivationFn::ReLU { leak: 0.01 },
+        use_bias: true,
+    };
+    let pool2 = Pool2dLayerParams::new(conv2.output_dims(), 2);
+
+    let mut net = NetBuilder::new(backend, 784)
+        .with_initializer(RandomNetInitializer::seed_from_u64(0xf1234567))
+        .with_layer(conv1)
+        .with_layer(ConcreteLayerParams::MaxPool2d(pool1))
+        .with_layer(conv2)
+        .with_layer(ConcreteLayerParams::MaxPool2d(pool2))
+        .with_layer(DenseLayerParams {
+            size: 120,
+            activation_fn: ActivationFn::ReLU { leak: 0.01 },
+            use_bias: true,
+        })
+        .with_layer(DropoutLayerParams::new(0.25))
+        .with_layer(DenseLayerParams {
+            size: 84,
+            activation_fn: ActivationFn::ReLU { leak: 0.01 },
+            use_bias: true,
+        })
+        .with_layer(DenseLayerParams {
+            size: 10,
+            activation_fn: ActivationFn::Identity,
+            use_bias: true,
+        })
+        .build()
+        .unwrap();
+
+    let max_epochs = 5;
+    let config = TrainConfig {
+        loss: LossFn::SoftmaxCrossEntropy,
+        optimizer: OptimizerFn::adam(0.001),
+        batch_size: Some(MAX_BATCH_SIZE),
+        num_epochs: max_epochs,
+        shuffle: true,
+        seed: 0xf666,
+        checkpoint: None,
+    };
+    let mut trainer = Trainer::new(config).with_scorer(MulticlassScorer::for_net(&net));
+    let history = trainer
+        .train(&mut net, train_images.view(), train_labels.view())
+        .expect("failed to write checkpoint");
+
+    for stats in history.epochs.iter() {
+        println!(
+            "epoch {}: loss={:.5} metrics={:?} ({:.2} sec)",
+            stats.epoch,
+            stats.loss,
+            stats.metrics,
+            stats.duration.as_secs_f32()
+        );
+    }
+    println!(
+        "Training time for {max_epochs} epochs and batch size {MAX_BATCH_SIZE}: {} sec",
+        history.total_duration().as_secs_f32()
+    );
+
+    let mut scorer = MulticlassScorer::for_net(&net);
+    net.evaluate(test_images.view(), test_labels.view(), &mut scorer).unwrap();
+
+    scorer.print_report(net.backend());
+}
@@ -1,17 +1,18 @@
-use rand::rngs::StdRng;
-use rand::SeedableRng;
 use rcann::activation::ActivationFn;
+use rcann::loss::LossFn;
 use rcann::net::initializer::RandomNetInitializer;
 use rcann::net::layer::DenseLayerParams;
-use rcann::net::NetBuilder;
+use rcann::net::{NetBuilder, TrainConfig, Trainer};
+use rcann::optimizer::OptimizerFn;
 use rcann::scoring::MulticlassScorer;
 use rcann::tensor::TensorBase;
 use rcann_examples::util::{load_mnist_data, MnistData};
-use rcann_opencl::backend::OpenCLBackend;
-use std::time::Instant;
-use rcann_opencl::util::VecWidth;
+use rcann_opencl::backend::{GemmTuner, OpenCLBackend, OpenCLBackendOptions};
+use rcann_opencl::util::{self, VecWidth};
 
 const MAX_BATCH_SIZE: usize = 64;
+/// the fastest matrix multiplication kernel for the device, which is only searched for on the first run
+const GEMM_CONFIG_PATH: &str = "rcann-examples/data/gemm_config.txt";
 
 pub fn main() {
     let MnistData {
@@ -21,9 +22,15 @@ pub fn main() {
         test_labels,
     } = load_mnist_data::<f32>(60_000, 10_000);
 
-    let mut shuffle_rng = StdRng::seed_from_u64(0xf666);
-
-    let backend = OpenCLBackend::from_default_device(MAX_BATCH_SIZE, VecWidth::SIXTEEN).unwrap();
+    let device = util::get_default_device().unwrap();
+    let gemm = GemmTuner::default()
+        .load_or_tune::<f32, _>(&device, GEMM_CONFIG_PATH)
+        .unwrap();
+    let options = OpenCLBackendOptions {
+        gemm: Some(gemm),
+        ..OpenCLBackendOptions::from_env()
+    };
+    let backend = OpenCLBackend::from_device_with_options(device, MAX_BATCH_SIZE, VecWidth::SIXTEEN, options).unwrap();
     //let backend = CpuBackend::<f32>::new(MAX_BATCH_SIZE);
 
     let mut net = NetBuilder::new(backend, 784)
@@ -31,14 +38,17 @@ pub fn main() {
         .with_layer(DenseLayerParams {
             size: 128,
             activation_fn: ActivationFn::Sigmoid,
+            use_bias: true,
         })
         .with_layer(DenseLayerParams {
             size: 32,
             activation_fn: ActivationFn::Sigmoid,
+            use_bias: true,
         })
         .with_layer(DenseLayerParams {
             size: 10,
             activation_fn: ActivationFn::Sigmoid,
+            use_bias: true,
         })
         .build()
         .unwrap();
@@ -46,18 +56,36 @@ pub fn main() {
     //println!("{:#?}", net);
 
     let max_epochs = 100;
-    let start = Instant::now();
-
-    net.train(&mut shuffle_rng, train_images.view(), train_labels.view(), max_epochs);
+    let config = TrainConfig {
+        loss: LossFn::MSE,
+        optimizer: OptimizerFn::sgd(0.1, 0.1),
+        batch_size: Some(MAX_BATCH_SIZE),
+        num_epochs: max_epochs,
+        shuffle: true,
+        seed: 0xf666,
+        checkpoint: None,
+    };
+    let mut trainer = Trainer::new(config).with_scorer(MulticlassScorer::for_net(&net));
+    let history = trainer
+        .train(&mut net, train_images.view(), train_labels.view())
+        .expect("failed to write checkpoint");
 
-    let elapsed = start.elapsed();
+    for stats in history.epochs.iter() {
+        println!(
+            "epoch {}: loss={:.5} metrics={:?} ({:.2} sec)",
+            stats.epoch,
+            stats.loss,
+            stats.metrics,
+            stats.duration.as_secs_f32()
+        );
+    }
     println!(
         "Training time for {max_epochs} epochs and batch size {MAX_BATCH_SIZE}: {} sec",
-        elapsed.as_secs_f32()
+        history.total_duration().as_secs_f32()
     );
 
     let mut scorer = MulticlassScorer::for_net(&net);
-    net.evaluate(test_images.view(), test_labels.view(), &mut scorer);
+    net.evaluate(test_images.view(), test_labels.view(), &mut scorer).unwrap();
 
     scorer.print_report(net.backend());
 }
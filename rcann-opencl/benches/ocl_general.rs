@@ -2,11 +2,12 @@ use bencher::{benchmark_group, benchmark_main, Bencher};
 use rcann::backend::{BackendOther, TensorOps};
 use rcann::util::bench::*;
 use rcann_opencl::backend::OpenCLBackend;
+use rcann_opencl::util::VecWidth;
 
 macro_rules! impl_add_assign_bench {
     ($name:ident, $ty:ty, $factory:ident, $size:expr, $alpha:literal, $beta:literal) => {
         fn $name(bench: &mut Bencher) {
-            let backend = OpenCLBackend::from_default_device(0).unwrap();
+            let backend = OpenCLBackend::from_default_device(0, VecWidth::SIXTEEN).unwrap();
             let [a, b, _] = $factory($size);
             let ocl_a = backend.new_tensor_from_native(a);
             let mut ocl_b = backend.new_tensor_from_native(b);
@@ -5,11 +5,13 @@ use bencher::Bencher;
 use rcann::backend::{MatrixMultiplication, TensorOps};
 use rcann::util::bench::*;
 use rcann_opencl::backend::OpenCLBackend;
+use rcann_opencl::util::VecWidth;
+use rcann_opencl::util::bench::TransposeGemm;
 
 macro_rules! impl_bench {
     ($name:ident, $ty:ty, $factory:ident, $size:expr, $alpha:literal, $ta:literal, $tb:literal, $beta:literal) => {
         fn $name(bench: &mut Bencher) {
-            let backend = OpenCLBackend::from_default_device(0).unwrap();
+            let backend = OpenCLBackend::from_default_device(0, VecWidth::SIXTEEN).unwrap();
             let [a, b, c] = $factory($size);
             let ocl_a = backend.new_tensor_from_native(a);
             let ocl_b = backend.new_tensor_from_native(b);
@@ -25,23 +27,80 @@ macro_rules! impl_bench {
     };
 }
 
+/// the same product as [impl_bench], transposing the operands into temporaries before a plain GeMM
+macro_rules! impl_transpose_gemm_bench {
+    ($name:ident, $factory:ident, $size:expr, $alpha:literal, $ta:literal, $tb:literal) => {
+        fn $name(bench: &mut Bencher) {
+            let backend = OpenCLBackend::from_default_device(0, VecWidth::SIXTEEN).unwrap();
+            let transpose_gemm = TransposeGemm::new(&backend).unwrap();
+            let [a, b, c] = $factory($size);
+            let ocl_a = backend.new_tensor_from_native(a);
+            let ocl_b = backend.new_tensor_from_native(b);
+            let mut ocl_c = backend.new_tensor_from_native(c);
+            ocl_a.sync();
+            ocl_b.sync();
+            ocl_c.sync();
+            bench.iter(|| {
+                transpose_gemm
+                    .matmul(&backend, $alpha, &ocl_a, $ta, &ocl_b, $tb, 0.0, &mut ocl_c)
+                    .unwrap();
+                ocl_c.sync();
+            })
+        }
+    };
+}
+
 impl_bench!(ocl_f32_lg, f32, get_square_matrices, SIZE_LG, 1.0, false, false, 0.0);
 impl_bench!(ocl_f32_md, f32, get_square_matrices, SIZE_MD, 1.0, false, false, 0.0);
 impl_bench!(ocl_f32_sm, f32, get_square_matrices, SIZE_SM, 1.0, false, false, 0.0);
-benchmark_group!(ocl_f32, ocl_f32_lg /*ocl_f32_md, ocl_f32_sm*/,);
+benchmark_group!(ocl_f32, ocl_f32_lg, ocl_f32_md, ocl_f32_sm);
 
 impl_bench!(ocl_f32_lg_t_a, f32, get_square_matrices, SIZE_LG, 1.0, true, false, 0.0);
 impl_bench!(ocl_f32_md_t_a, f32, get_square_matrices, SIZE_MD, 1.0, true, false, 0.0);
 impl_bench!(ocl_f32_sm_t_a, f32, get_square_matrices, SIZE_SM, 1.0, true, false, 0.0);
-benchmark_group!(ocl_f32_t_a, ocl_f32_lg_t_a, ocl_f32_md_t_a, ocl_f32_sm_t_a);
+impl_transpose_gemm_bench!(ocl_f32_lg_t_a_explicit, get_square_matrices, SIZE_LG, 1.0, true, false);
+impl_transpose_gemm_bench!(ocl_f32_md_t_a_explicit, get_square_matrices, SIZE_MD, 1.0, true, false);
+impl_transpose_gemm_bench!(ocl_f32_sm_t_a_explicit, get_square_matrices, SIZE_SM, 1.0, true, false);
+benchmark_group!(
+    ocl_f32_t_a,
+    ocl_f32_lg_t_a,
+    ocl_f32_lg_t_a_explicit,
+    ocl_f32_md_t_a,
+    ocl_f32_md_t_a_explicit,
+    ocl_f32_sm_t_a,
+    ocl_f32_sm_t_a_explicit
+);
 
 impl_bench!(ocl_f32_lg_t_b, f32, get_square_matrices, SIZE_LG, 1.0, false, true, 0.0);
 impl_bench!(ocl_f32_md_t_b, f32, get_square_matrices, SIZE_MD, 1.0, false, true, 0.0);
 impl_bench!(ocl_f32_sm_t_b, f32, get_square_matrices, SIZE_SM, 1.0, false, true, 0.0);
-benchmark_group!(ocl_f32_t_b, ocl_f32_lg_t_b, ocl_f32_md_t_b, ocl_f32_sm_t_b);
+impl_transpose_gemm_bench!(ocl_f32_lg_t_b_explicit, get_square_matrices, SIZE_LG, 1.0, false, true);
+impl_transpose_gemm_bench!(ocl_f32_md_t_b_explicit, get_square_matrices, SIZE_MD, 1.0, false, true);
+impl_transpose_gemm_bench!(ocl_f32_sm_t_b_explicit, get_square_matrices, SIZE_SM, 1.0, false, true);
+benchmark_group!(
+    ocl_f32_t_b,
+    ocl_f32_lg_t_b,
+    ocl_f32_lg_t_b_explicit,
+    ocl_f32_md_t_b,
+    ocl_f32_md_t_b_explicit,
+    ocl_f32_sm_t_b,
+    ocl_f32_sm_t_b_explicit
+);
 
-benchmark_main!(
-    ocl_f32,
-    //ocl_f32_t_a,
-    //ocl_f32_t_b
+impl_bench!(ocl_f32_lg_t_ab, f32, get_square_matrices, SIZE_LG, 1.0, true, true, 0.0);
+impl_bench!(ocl_f32_md_t_ab, f32, get_square_matrices, SIZE_MD, 1.0, true, true, 0.0);
+impl_bench!(ocl_f32_sm_t_ab, f32, get_square_matrices, SIZE_SM, 1.0, true, true, 0.0);
+impl_transpose_gemm_bench!(ocl_f32_lg_t_ab_explicit, get_square_matrices, SIZE_LG, 1.0, true, true);
+impl_transpose_gemm_bench!(ocl_f32_md_t_ab_explicit, get_square_matrices, SIZE_MD, 1.0, true, true);
+impl_transpose_gemm_bench!(ocl_f32_sm_t_ab_explicit, get_square_matrices, SIZE_SM, 1.0, true, true);
+benchmark_group!(
+    ocl_f32_t_ab,
+    ocl_f32_lg_t_ab,
+    ocl_f32_lg_t_ab_explicit,
+    ocl_f32_md_t_ab,
+    ocl_f32_md_t_ab_explicit,
+    ocl_f32_sm_t_ab,
+    ocl_f32_sm_t_ab_explicit
 );
+
+benchmark_main!(ocl_f32, ocl_f32_t_a, ocl_f32_t_b, ocl_f32_t_ab);
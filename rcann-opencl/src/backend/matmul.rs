@@ -1,9 +1,7 @@
 use crate::backend::OpenCLBackend;
-use crate::tensor::{OclFloat, OclTensor};
-use crate::util::panic_on_error;
+use crate::tensor::OclFloat;
 use rcann::backend::MatrixMultiplication;
-use rcann::dtype::DType;
-use rcann::tensor::{Dim2, ITensor};
+use rcann::tensor::Dim2;
 
 impl<F: OclFloat> MatrixMultiplication for OpenCLBackend<F> {
     fn matmul(
@@ -16,35 +14,20 @@ impl<F: OclFloat> MatrixMultiplication for OpenCLBackend<F> {
         beta: F,
         c: &mut Self::Tensor<Dim2>,
     ) {
-        panic_on_error(|| {
-            let a_transpose = if ta {
-                let mut temp = unsafe { OclTensor::uninit(&self.context, a.dims().transposed())? };
-                self.transpose_program.transpose(&self.queue, a, &mut temp);
-                Some(temp)
-            } else {
-                self.zero_pad_program.zero_padding(&self.queue, a);
-                None
-            };
-            let b_transpose = if tb {
-                let mut temp = unsafe { OclTensor::uninit(&self.context, b.dims().transposed())? };
-                self.transpose_program.transpose(&self.queue, b, &mut temp);
-                Some(temp)
-            } else {
-                self.zero_pad_program.zero_padding(&self.queue, b);
-                None
-            };
+        self.record(|| {
+            // the tiles include the padding of the operands, so it must be zero
+            self.zero_pad_program.zero_padding(&self.queue, a)?;
+            self.zero_pad_program.zero_padding(&self.queue, b)?;
             if beta != F::ZERO {
-                self.zero_pad_program.zero_padding(&self.queue, c);
+                self.zero_pad_program.zero_padding(&self.queue, c)?;
+            }
+            let gemm = &self.gemm_program;
+            match (ta, tb) {
+                (false, false) => gemm.gemm(&self.queue, alpha, a, b, beta, c),
+                (true, false) => gemm.gemm_tn(&self.queue, alpha, a, b, beta, c),
+                (false, true) => gemm.gemm_nt(&self.queue, alpha, a, b, beta, c),
+                (true, true) => gemm.gemm_tt(&self.queue, alpha, a, b, beta, c),
             }
-            self.gemm_program.gemm(
-                &self.queue,
-                alpha,
-                a_transpose.as_ref().unwrap_or(a),
-                b_transpose.as_ref().unwrap_or(b),
-                beta,
-                c,
-            );
-            Ok(())
         });
     }
 }
@@ -1,21 +1,52 @@
 mod matmul;
 mod other;
+mod tune;
+
+pub use tune::*;
 
 use crate::kernels::gemm::GeMMProgram;
 use crate::kernels::scoring::ScoringProgram;
-use crate::kernels::transpose::TransposeProgram;
+use crate::error::Error;
 use crate::tensor::{OclFloat, OclTensor};
-use crate::util::{self, ProgramCache, Result, VecWidth};
+use crate::util::{self, DeviceSelector, Profiler, ProgramCache, Result, VecWidth};
 use opencl3::command_queue::CommandQueue;
 use opencl3::context::Context;
 use opencl3::device::Device;
 use rcann::backend::{Backend, TensorOps, TensorTyped};
 use rcann::tensor::{Dims, DimsMore, ITensor, Tensor, TensorBase, TensorBaseMut, TensorView};
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
 use std::fmt::Debug;
 use crate::kernels::BUFFER_BLOCK_SIZE;
 use crate::kernels::general::GeneralProgram;
 use crate::kernels::zero_padding::ZeroPadProgram;
 
+#[derive(Debug, Clone, Default)]
+pub struct OpenCLBackendOptions {
+    /// caches the program binaries in this directory, so later processes using the same device and driver
+    /// don't have to compile them again
+    pub cache_dir: Option<PathBuf>,
+    /// records the execution time of every kernel, see [OpenCLBackend::profiler]. This adds some overhead
+    /// to each kernel, so it should only be enabled to find bottlenecks.
+    pub profiling: bool,
+    /// the configuration of the matrix multiplication kernel, e.g. found by [GemmTuner]. Defaults to tiles
+    /// of 16 with the vector width of the backend.
+    pub gemm: Option<GemmConfig>,
+}
+
+impl OpenCLBackendOptions {
+    /// the default options, with the cache directory given by [util::PROGRAM_CACHE_DIR_ENV_VAR]
+    pub fn from_env() -> Self {
+        OpenCLBackendOptions {
+            cache_dir: std::env::var_os(util::PROGRAM_CACHE_DIR_ENV_VAR)
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from),
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Debug)]
 #[allow(unused)]
 pub struct OpenCLBackend<F: OclFloat> {
@@ -25,39 +56,66 @@ pub struct OpenCLBackend<F: OclFloat> {
     cache: ProgramCache,
     max_batch_size: usize,
     vec_width: VecWidth,
+    gemm_config: GemmConfig,
     gemm_program: GeMMProgram<F>,
-    transpose_program: TransposeProgram<F>,
     zero_pad_program: ZeroPadProgram<F>,
     general_program: GeneralProgram<F>,
     scoring_program: ScoringProgram<F>,
+    profiler: Option<Rc<Profiler>>,
+    /// the first error of an op since the last call to [TensorOps::take_error]
+    error: RefCell<Option<Error>>,
 }
 
 impl<F: OclFloat> OpenCLBackend<F> {
+    /// creates the backend on the device given by [util::DEVICE_ENV_VAR], or the first GPU if it isn't set
     pub fn from_default_device(max_batch_size: usize, vec_width: VecWidth) -> Result<Self> {
         Self::from_device(util::get_default_device()?, max_batch_size, vec_width)
     }
 
+    pub fn from_selector(selector: &DeviceSelector, max_batch_size: usize, vec_width: VecWidth) -> Result<Self> {
+        Self::from_device(selector.select()?, max_batch_size, vec_width)
+    }
+
+    /// creates the backend on the given device, caching program binaries on disk if
+    /// [util::PROGRAM_CACHE_DIR_ENV_VAR] is set
     pub fn from_device(device: Device, max_batch_size: usize, vec_width: VecWidth) -> Result<Self> {
+        Self::from_device_with_options(device, max_batch_size, vec_width, OpenCLBackendOptions::from_env())
+    }
+
+    pub fn from_device_with_options(
+        device: Device,
+        max_batch_size: usize,
+        vec_width: VecWidth,
+        options: OpenCLBackendOptions,
+    ) -> Result<Self> {
         let context = util::get_context(&device)?;
-        let queue = util::create_queue(&context)?;
-        let gemm_program = GeMMProgram::create(&context, vec_width, BUFFER_BLOCK_SIZE)?;
-        let transpose_program = TransposeProgram::create(&context, BUFFER_BLOCK_SIZE)?;
-        let zero_pad_program = ZeroPadProgram::create(&context, BUFFER_BLOCK_SIZE)?;
-        let general_program = GeneralProgram::create(&context, vec_width, BUFFER_BLOCK_SIZE / vec_width as usize)?;
-        let scoring_program = ScoringProgram::create(&context)?;
-        let cache = ProgramCache::new();
+        let profiler = options.profiling.then(|| Rc::new(Profiler::default()));
+        let queue = if profiler.is_some() {
+            util::create_profiling_queue(&context)?
+        } else {
+            util::create_queue(&context)?
+        };
+        let cache = ProgramCache::new(options.cache_dir, profiler.clone());
+        let gemm_config = options.gemm.unwrap_or(GemmConfig::new(BUFFER_BLOCK_SIZE, vec_width));
+        let gemm_program = GeMMProgram::get_or_create(&context, &cache, gemm_config.vec_width, gemm_config.tile_size)?;
+        let zero_pad_program = ZeroPadProgram::get_or_create(&context, &cache, BUFFER_BLOCK_SIZE)?;
+        let general_program =
+            GeneralProgram::get_or_create(&context, &cache, vec_width, BUFFER_BLOCK_SIZE / vec_width as usize)?;
+        let scoring_program = ScoringProgram::get_or_create(&context, &cache)?;
         Ok(OpenCLBackend {
             device,
             context,
             queue,
             max_batch_size,
             vec_width,
+            gemm_config,
             gemm_program,
-            transpose_program,
             zero_pad_program,
             general_program,
             cache,
             scoring_program,
+            profiler,
+            error: RefCell::new(None),
         })
     }
     #[inline]
@@ -72,10 +130,30 @@ impl<F: OclFloat> OpenCLBackend<F> {
     pub fn queue(&self) -> &CommandQueue {
         &self.queue
     }
+    #[inline]
+    pub fn gemm_config(&self) -> GemmConfig {
+        self.gemm_config
+    }
+    /// the kernel times, if the backend was created with [OpenCLBackendOptions::profiling]
+    #[inline]
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_deref()
+    }
+
+    /// runs an op which can't return its error, unless a previous op failed
+    fn record<Op: FnOnce() -> Result<()>>(&self, op: Op) {
+        if self.error.borrow().is_some() {
+            return;
+        }
+        if let Err(err) = op() {
+            self.error.replace(Some(err));
+        }
+    }
 }
 
 impl<F: OclFloat> TensorTyped for OpenCLBackend<F> {
     type Float = F;
+    type Error = Error;
     type Tensor<D: Dims> = OclTensor<F, D>;
     type TensorRef<'a, D: Dims> = &'a OclTensor<F, D>;
     type InputAdaptionBuff<D: Dims> = OclTensor<F, D>;
@@ -83,60 +161,70 @@ impl<F: OclFloat> TensorTyped for OpenCLBackend<F> {
 }
 
 impl<F: OclFloat> TensorOps for OpenCLBackend<F> {
-    fn new_tensor_exact<D: Dims>(&self, dim: D) -> Self::Tensor<D> {
-        OclTensor::zeroed(&self.context, &self.queue, dim).unwrap()
+    fn try_new_tensor_exact<D: Dims>(&self, dim: D) -> Result<Self::Tensor<D>> {
+        OclTensor::zeroed(&self.context, &self.queue, dim)
     }
 
-    fn new_tensor_batch_sized<D: DimsMore>(&self, inner_dims: D) -> Self::Tensor<D::More> {
-        OclTensor::zeroed(&self.context, &self.queue, inner_dims.insert_major(self.max_batch_size)).unwrap()
+    fn try_new_tensor_batch_sized<D: DimsMore>(&self, inner_dims: D) -> Result<Self::Tensor<D::More>> {
+        OclTensor::zeroed(&self.context, &self.queue, inner_dims.insert_major(self.max_batch_size))
     }
 
     fn resize_tensor<D: Dims>(&self, tensor: &mut Self::Tensor<D>, dims: D) {
         tensor.resize_within_capacity(dims)
     }
 
-    fn write_tensor<T, D>(&self, tensor: &mut Self::Tensor<D>, native_src: &T)
+    fn try_write_tensor<T, D>(&self, tensor: &mut Self::Tensor<D>, native_src: &T) -> Result<()>
     where
         T: TensorBase<Self::Float, D>,
         D: Dims,
     {
-        tensor.write_sync(&self.queue, native_src).unwrap();
+        tensor.write_sync(&self.queue, native_src)
     }
 
-    fn read_tensor<T, D>(&self, tensor: &Self::Tensor<D>, native_dst: &mut T)
+    fn try_read_tensor<T, D>(&self, tensor: &Self::Tensor<D>, native_dst: &mut T) -> Result<()>
     where
         T: TensorBaseMut<Self::Float, D>,
         D: Dims,
     {
-        tensor.read_sync(&self.queue, native_dst).unwrap();
+        // the tensor is invalid if any op writing it failed
+        self.take_error()?;
+        tensor.read_sync(&self.queue, native_dst)
+    }
+
+    fn take_error(&self) -> Result<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
     }
 
-    fn new_input_adaption_buff<D: DimsMore>(&self, inner_dims: D) -> OclTensor<F, D::More> {
-        OclTensor::zeroed(&self.context, &self.queue, inner_dims.insert_major(self.max_batch_size)).unwrap()
+    fn try_new_input_adaption_buff<D: DimsMore>(&self, inner_dims: D) -> Result<OclTensor<F, D::More>> {
+        OclTensor::zeroed(&self.context, &self.queue, inner_dims.insert_major(self.max_batch_size))
     }
 
     fn new_output_adaption_buff<D: DimsMore>(&self, inner_dims: D) -> Tensor<F, D::More> {
         Tensor::zeroed(inner_dims.insert_major(self.max_batch_size))
     }
 
-    fn adapt_input<'a, D: Dims>(
+    fn try_adapt_input<'a, D: Dims>(
         &self,
         buff: &'a mut OclTensor<F, D>,
         input: TensorView<F, D>,
-    ) -> &'a OclTensor<F, D> {
+    ) -> Result<&'a OclTensor<F, D>> {
         buff.resize_within_capacity(*input.dims());
-        buff.write_sync(&self.queue, &input).unwrap();
-        buff
+        buff.write_sync(&self.queue, &input)?;
+        Ok(buff)
     }
 
-    fn adapt_output<'a, D: Dims>(
+    fn try_adapt_output<'a, D: Dims>(
         &self,
         buff: &'a mut Tensor<F, D>,
         output: &'a OclTensor<F, D>,
-    ) -> &'a Tensor<F, D> {
+    ) -> Result<&'a Tensor<F, D>> {
+        self.take_error()?;
         buff.resize_within_capacity(F::ZERO, *output.dims());
-        output.read_sync(&self.queue, buff).unwrap();
-        buff
+        output.read_sync(&self.queue, buff)?;
+        Ok(buff)
     }
 
     fn debug_tensor<D: Dims>(&self, tensor: &OclTensor<F, D>) {
@@ -1,26 +1,79 @@
 use crate::backend::OpenCLBackend;
 use crate::kernels::softmax::Softmax;
 use crate::wrap_cl_error;
-use rcann::backend::BackendOther;
+use rcann::backend::{BackendOther, DropoutMask, Window2d};
 use rcann::tensor::{Dim1, Dim2, Dims, ITensor};
+use crate::kernels::conv::Conv2dProgram;
+use crate::kernels::cross_entropy::CrossEntropyProgram;
+use crate::kernels::dropout::DropoutProgram;
 use crate::kernels::mse::MSEProgram;
-use crate::tensor::OclFloat;
+use crate::kernels::norm::{BatchNormProgram, LayerNormProgram};
+use crate::kernels::pool::Pool2dProgram;
+use crate::tensor::{OclFloat, OclTensor2};
+use crate::util::Result;
 
 #[allow(unused)]
 impl<F: OclFloat> BackendOther for OpenCLBackend<F> {
     fn column_sum(&self, alpha: Self::Float, a: &Self::Tensor<Dim2>, beta: Self::Float, b: &mut Self::Tensor<Dim1>) {
-        self.general_program.column_sum(&self.queue, alpha, a, beta, b);
+        self.record(|| self.general_program.column_sum(&self.queue, alpha, a, beta, b))
     }
 
     fn add_assign<D>(&self, alpha: Self::Float, a: &Self::Tensor<D>, beta: Self::Float, b: &mut Self::Tensor<D>)
     where
         D: Dims,
     {
-        self.general_program.add_assign(&self.queue, alpha, a, beta, b);
+        self.record(|| self.general_program.add_assign(&self.queue, alpha, a, beta, b))
     }
 
-    fn sigmoid(&self, activation: &Self::Tensor<Dim2>, output: &mut Self::Tensor<Dim2>) {
-        self.general_program.sigmoid(&self.queue, activation, output);
+    fn add_assign_row_broadcast(
+        &self,
+        alpha: Self::Float,
+        a: &Self::Tensor<Dim1>,
+        beta: Self::Float,
+        b: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            self.general_program
+                .add_assign_row_broadcast(&self.queue, alpha, a, beta, b)
+        })
+    }
+
+    fn adaptive_update<D: Dims>(
+        &self,
+        learn_rate: Self::Float,
+        decay: Self::Float,
+        scale: Self::Float,
+        epsilon: Self::Float,
+        grad: &Self::Tensor<D>,
+        accum: &mut Self::Tensor<D>,
+        params: &mut Self::Tensor<D>,
+    ) {
+        self.record(|| {
+            self.general_program
+                .adaptive_update(&self.queue, learn_rate, decay, scale, epsilon, grad, accum, params)
+        })
+    }
+
+    fn adam_update<D: Dims>(
+        &self,
+        step_size: Self::Float,
+        beta1: Self::Float,
+        beta2: Self::Float,
+        epsilon: Self::Float,
+        decay: Self::Float,
+        grad: &Self::Tensor<D>,
+        m: &mut Self::Tensor<D>,
+        v: &mut Self::Tensor<D>,
+        params: &mut Self::Tensor<D>,
+    ) {
+        self.record(|| {
+            self.general_program
+                .adam_update(&self.queue, step_size, beta1, beta2, epsilon, decay, grad, m, v, params)
+        })
+    }
+
+    fn sigmoid(&self, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>) {
+        self.record(|| self.general_program.sigmoid(&self.queue, activation, output))
     }
 
     fn sigmoid_error(
@@ -29,33 +82,131 @@ impl<F: OclFloat> BackendOther for OpenCLBackend<F> {
         out_error: &Self::Tensor<Dim2>,
         result: &mut Self::Tensor<Dim2>,
     ) {
-        self.general_program.sigmoid_error(&self.queue, output, out_error, result)
+        self.record(|| {
+            self.general_program
+                .sigmoid_error(&self.queue, output, out_error, result)
+        })
     }
 
-    fn relu(&self, leak: Self::Float, activation: &Self::Tensor<Dim2>, output: &mut Self::Tensor<Dim2>) {
-        todo!()
+    fn relu(&self, leak: Self::Float, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>) {
+        self.record(|| self.general_program.relu(&self.queue, leak, activation, output))
     }
 
     fn relu_error(
         &self,
         leak: Self::Float,
-        activation: &Self::Tensor<Dim2>,
+        activation: Self::TensorRef<'_, Dim2>,
         out_error: &Self::Tensor<Dim2>,
         result: &mut Self::Tensor<Dim2>,
     ) {
-        todo!()
+        self.record(|| {
+            self.general_program
+                .relu_error(&self.queue, leak, activation, out_error, result)
+        })
     }
 
-    fn softmax(&self, activation: &Self::Tensor<Dim2>, output: &mut Self::Tensor<Dim2>) {
-        Softmax::get_or_create(
-            &self.context,
-            &self.cache,
-            self.vec_width,
-            output.dims().cols(),
-            output.buffer_dims().cols(),
-        )
-        .unwrap()
-        .softmax(&self.queue, activation, output)
+    fn tanh(&self, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>) {
+        self.record(|| self.general_program.tanh_activation(&self.queue, activation, output))
+    }
+
+    fn tanh_error(&self, output: &Self::Tensor<Dim2>, out_error: &Self::Tensor<Dim2>, result: &mut Self::Tensor<Dim2>) {
+        self.record(|| {
+            self.general_program
+                .tanh_activation_error(&self.queue, output, out_error, result)
+        })
+    }
+
+    fn elu(
+        &self,
+        alpha: Self::Float,
+        scale: Self::Float,
+        activation: Self::TensorRef<'_, Dim2>,
+        output: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| self.general_program.elu(&self.queue, alpha, scale, activation, output))
+    }
+
+    fn elu_error(
+        &self,
+        alpha: Self::Float,
+        scale: Self::Float,
+        activation: Self::TensorRef<'_, Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        result: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            self.general_program
+                .elu_error(&self.queue, alpha, scale, activation, out_error, result)
+        })
+    }
+
+    fn gelu(&self, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>) {
+        self.record(|| self.general_program.gelu(&self.queue, activation, output))
+    }
+
+    fn gelu_error(
+        &self,
+        activation: Self::TensorRef<'_, Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        result: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            self.general_program
+                .gelu_error(&self.queue, activation, out_error, result)
+        })
+    }
+
+    fn swish(&self, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>) {
+        self.record(|| self.general_program.swish(&self.queue, activation, output))
+    }
+
+    fn swish_error(
+        &self,
+        activation: Self::TensorRef<'_, Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        result: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            self.general_program
+                .swish_error(&self.queue, activation, out_error, result)
+        })
+    }
+
+    fn softplus(&self, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>) {
+        self.record(|| self.general_program.softplus(&self.queue, activation, output))
+    }
+
+    fn softplus_error(
+        &self,
+        activation: Self::TensorRef<'_, Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        result: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            self.general_program
+                .softplus_error(&self.queue, activation, out_error, result)
+        })
+    }
+
+    fn identity(&self, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>) {
+        self.record(|| self.general_program.identity(&self.queue, activation, output))
+    }
+
+    fn identity_error(&self, out_error: &Self::Tensor<Dim2>, result: &mut Self::Tensor<Dim2>) {
+        self.record(|| self.general_program.identity(&self.queue, out_error, result))
+    }
+
+    fn softmax(&self, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>) {
+        self.record(|| {
+            Softmax::get_or_create(
+                &self.context,
+                &self.cache,
+                self.vec_width,
+                output.dims().cols(),
+                output.buffer_dims().cols(),
+            )?
+            .softmax(&self.queue, activation, output)
+        })
     }
 
     fn softmax_error(
@@ -64,7 +215,16 @@ impl<F: OclFloat> BackendOther for OpenCLBackend<F> {
         out_error: &Self::Tensor<Dim2>,
         result: &mut Self::Tensor<Dim2>,
     ) {
-        todo!()
+        self.record(|| {
+            Softmax::get_or_create(
+                &self.context,
+                &self.cache,
+                self.vec_width,
+                output.dims().cols(),
+                output.buffer_dims().cols(),
+            )?
+            .softmax_error(&self.queue, output, out_error, result)
+        })
     }
 
     fn mean_squared_error(
@@ -74,29 +234,325 @@ impl<F: OclFloat> BackendOther for OpenCLBackend<F> {
         result: &mut Self::Tensor<Dim1>,
         result_deriv: &mut Self::Tensor<Dim2>,
     ) {
-        MSEProgram::get_or_create(
-            &self.context,
-            &self.cache,
-            self.vec_width,
-            output.dims().cols(),
-            output.buffer_dims().cols(),
-        )
-        .unwrap()
-        .mean_squared_error(&self.queue, output, expected, result, result_deriv);
+        self.record(|| {
+            MSEProgram::get_or_create(
+                &self.context,
+                &self.cache,
+                self.vec_width,
+                output.dims().cols(),
+                output.buffer_dims().cols(),
+            )?
+            .mean_squared_error(&self.queue, output, expected, result, result_deriv)
+        })
+    }
+
+    fn categorical_cross_entropy(
+        &self,
+        output: &Self::Tensor<Dim2>,
+        expected: &Self::Tensor<Dim2>,
+        result: &mut Self::Tensor<Dim1>,
+        result_deriv: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            CrossEntropyProgram::get_or_create(
+                &self.context,
+                &self.cache,
+                self.vec_width,
+                output.dims().cols(),
+                output.buffer_dims().cols(),
+            )?
+            .categorical_cross_entropy(&self.queue, output, expected, result, result_deriv)
+        })
+    }
+
+    fn binary_cross_entropy(
+        &self,
+        output: &Self::Tensor<Dim2>,
+        expected: &Self::Tensor<Dim2>,
+        result: &mut Self::Tensor<Dim1>,
+        result_deriv: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            CrossEntropyProgram::get_or_create(
+                &self.context,
+                &self.cache,
+                self.vec_width,
+                output.dims().cols(),
+                output.buffer_dims().cols(),
+            )?
+            .binary_cross_entropy(&self.queue, output, expected, result, result_deriv)
+        })
+    }
+
+    fn softmax_cross_entropy(
+        &self,
+        output: &Self::Tensor<Dim2>,
+        expected: &Self::Tensor<Dim2>,
+        result: &mut Self::Tensor<Dim1>,
+        result_deriv: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            CrossEntropyProgram::get_or_create(
+                &self.context,
+                &self.cache,
+                self.vec_width,
+                output.dims().cols(),
+                output.buffer_dims().cols(),
+            )?
+            .softmax_cross_entropy(&self.queue, output, expected, result, result_deriv)
+        })
+    }
+
+    fn conv2d(
+        &self,
+        window: &Window2d,
+        input: Self::TensorRef<'_, Dim2>,
+        kernel: &Self::Tensor<Dim2>,
+        bias: Option<&Self::Tensor<Dim1>>,
+        output: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            let program =
+                self.conv2d_program(window, kernel, input.buffer_dims().cols(), output.buffer_dims().cols())?;
+            match bias {
+                Some(bias) => program.conv2d_bias(&self.queue, input, kernel, bias, output),
+                None => program.conv2d(&self.queue, input, kernel, output),
+            }
+        })
+    }
+
+    fn conv2d_input_error(
+        &self,
+        window: &Window2d,
+        out_error: &Self::Tensor<Dim2>,
+        kernel: &Self::Tensor<Dim2>,
+        input_error: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            self.conv2d_program(
+                window,
+                kernel,
+                input_error.buffer_dims().cols(),
+                out_error.buffer_dims().cols(),
+            )?
+            .conv2d_input_error(&self.queue, out_error, kernel, input_error)
+        })
+    }
+
+    fn conv2d_param_grad(
+        &self,
+        window: &Window2d,
+        input: Self::TensorRef<'_, Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        kernel_grad: &mut Self::Tensor<Dim2>,
+        bias_grad: Option<&mut Self::Tensor<Dim1>>,
+    ) {
+        self.record(|| {
+            let program = self.conv2d_program(
+                window,
+                kernel_grad,
+                input.buffer_dims().cols(),
+                out_error.buffer_dims().cols(),
+            )?;
+            program.conv2d_kernel_grad(&self.queue, input, out_error, kernel_grad)?;
+            match bias_grad {
+                Some(bias_grad) => program.conv2d_bias_grad(&self.queue, out_error, bias_grad),
+                None => Ok(()),
+            }
+        })
+    }
+
+    fn max_pool2d(
+        &self,
+        window: &Window2d,
+        input: Self::TensorRef<'_, Dim2>,
+        output: &mut Self::Tensor<Dim2>,
+        indices: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            self.pool2d_program(window, input.buffer_dims().cols(), output.buffer_dims().cols())?
+                .max_pool2d(&self.queue, input, output, indices)
+        })
+    }
+
+    fn max_pool2d_error(
+        &self,
+        window: &Window2d,
+        indices: &Self::Tensor<Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        input_error: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            self.pool2d_program(window, input_error.buffer_dims().cols(), out_error.buffer_dims().cols())?
+                .max_pool2d_error(&self.queue, indices, out_error, input_error)
+        })
+    }
+
+    fn avg_pool2d(&self, window: &Window2d, input: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>) {
+        self.record(|| {
+            self.pool2d_program(window, input.buffer_dims().cols(), output.buffer_dims().cols())?
+                .avg_pool2d(&self.queue, input, output)
+        })
+    }
+
+    fn avg_pool2d_error(
+        &self,
+        window: &Window2d,
+        out_error: &Self::Tensor<Dim2>,
+        input_error: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            self.pool2d_program(window, input_error.buffer_dims().cols(), out_error.buffer_dims().cols())?
+                .avg_pool2d_error(&self.queue, out_error, input_error)
+        })
+    }
+
+    fn column_mean_var(
+        &self,
+        a: Self::TensorRef<'_, Dim2>,
+        mean: &mut Self::Tensor<Dim1>,
+        var: &mut Self::Tensor<Dim1>,
+    ) {
+        self.record(|| {
+            self.batch_norm_program(a.dims().cols(), a.buffer_dims().cols())?
+                .column_mean_var(&self.queue, a, mean, var)
+        })
+    }
+
+    fn batch_norm(
+        &self,
+        epsilon: Self::Float,
+        input: Self::TensorRef<'_, Dim2>,
+        mean: &Self::Tensor<Dim1>,
+        var: &Self::Tensor<Dim1>,
+        gamma: &Self::Tensor<Dim1>,
+        beta: &Self::Tensor<Dim1>,
+        normalized: &mut Self::Tensor<Dim2>,
+        output: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            self.batch_norm_program(input.dims().cols(), input.buffer_dims().cols())?
+                .batch_norm(&self.queue, epsilon, input, mean, var, gamma, beta, normalized, output)
+        })
+    }
+
+    fn batch_norm_param_grad(
+        &self,
+        normalized: &Self::Tensor<Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        gamma_grad: &mut Self::Tensor<Dim1>,
+        beta_grad: &mut Self::Tensor<Dim1>,
+    ) {
+        self.record(|| {
+            self.batch_norm_program(normalized.dims().cols(), normalized.buffer_dims().cols())?
+                .batch_norm_param_grad(&self.queue, normalized, out_error, gamma_grad, beta_grad)
+        })
+    }
+
+    fn batch_norm_input_error(
+        &self,
+        epsilon: Self::Float,
+        normalized: &Self::Tensor<Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        var: &Self::Tensor<Dim1>,
+        gamma: &Self::Tensor<Dim1>,
+        gamma_grad: &Self::Tensor<Dim1>,
+        beta_grad: &Self::Tensor<Dim1>,
+        input_error: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            self.batch_norm_program(normalized.dims().cols(), normalized.buffer_dims().cols())?
+                .batch_norm_input_error(
+                    &self.queue,
+                    epsilon,
+                    normalized,
+                    out_error,
+                    var,
+                    gamma,
+                    gamma_grad,
+                    beta_grad,
+                    input_error,
+                )
+        })
+    }
+
+    fn row_mean_var(&self, a: Self::TensorRef<'_, Dim2>, mean: &mut Self::Tensor<Dim1>, var: &mut Self::Tensor<Dim1>) {
+        self.record(|| {
+            self.layer_norm_program(a.dims().cols(), a.buffer_dims().cols())?
+                .row_mean_var(&self.queue, a, mean, var)
+        })
+    }
+
+    fn layer_norm(
+        &self,
+        epsilon: Self::Float,
+        input: Self::TensorRef<'_, Dim2>,
+        mean: &Self::Tensor<Dim1>,
+        var: &Self::Tensor<Dim1>,
+        gain: &Self::Tensor<Dim1>,
+        bias: &Self::Tensor<Dim1>,
+        normalized: &mut Self::Tensor<Dim2>,
+        output: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            self.layer_norm_program(input.dims().cols(), input.buffer_dims().cols())?
+                .layer_norm(&self.queue, epsilon, input, mean, var, gain, bias, normalized, output)
+        })
+    }
+
+    fn layer_norm_input_error(
+        &self,
+        epsilon: Self::Float,
+        normalized: &Self::Tensor<Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        var: &Self::Tensor<Dim1>,
+        gain: &Self::Tensor<Dim1>,
+        input_error: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            self.layer_norm_program(normalized.dims().cols(), normalized.buffer_dims().cols())?
+                .layer_norm_input_error(&self.queue, epsilon, normalized, out_error, var, gain, input_error)
+        })
+    }
+
+    fn dropout(
+        &self,
+        mask: &DropoutMask,
+        input: Self::TensorRef<'_, Dim2>,
+        output: &mut Self::Tensor<Dim2>,
+        scale: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            self.dropout_program(input.dims().cols(), input.buffer_dims().cols())?
+                .dropout(&self.queue, mask, input, output, scale)
+        })
+    }
+
+    fn dropout_error(
+        &self,
+        scale: &Self::Tensor<Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        input_error: &mut Self::Tensor<Dim2>,
+    ) {
+        self.record(|| {
+            self.dropout_program(scale.dims().cols(), scale.buffer_dims().cols())?
+                .dropout_error(&self.queue, scale, out_error, input_error)
+        })
     }
 
     fn flush(&self) {
-        // enqueue a barrier that requires all previous commands finish before the next call
-        wrap_cl_error!(
-            unsafe { self.queue.enqueue_barrier_with_wait_list(&[]) },
-            "Error calling enqueue_barrier_with_wait_list"
-        )
-        .unwrap();
-        wrap_cl_error!(self.queue.flush(), "Error flushing queue").unwrap();
+        self.record(|| {
+            // enqueue a barrier that requires all previous commands finish before the next call
+            wrap_cl_error!(
+                unsafe { self.queue.enqueue_barrier_with_wait_list(&[]) },
+                "Error calling enqueue_barrier_with_wait_list"
+            )?;
+            wrap_cl_error!(self.queue.flush(), "Error flushing queue")
+        })
     }
 
     fn sync(&self) {
-        wrap_cl_error!(self.queue.finish(), "Error finishing queue").unwrap()
+        self.record(|| wrap_cl_error!(self.queue.finish(), "Error finishing queue"))
     }
 
     fn accum_confusion_matrix_multiclass(
@@ -105,7 +561,145 @@ impl<F: OclFloat> BackendOther for OpenCLBackend<F> {
         output: &Self::Tensor<Dim2>,
         expected: &Self::Tensor<Dim2>,
     ) {
-        self.scoring_program
-            .accum_multiclass_confusion_matrix(&self.context, &self.queue, matrix, output, expected);
+        self.record(|| {
+            self.scoring_program
+                .accum_multiclass_confusion_matrix(&self.context, &self.queue, matrix, output, expected)
+        })
+    }
+}
+
+impl<F: OclFloat> OpenCLBackend<F> {
+    fn conv2d_program(
+        &self,
+        window: &Window2d,
+        kernel: &OclTensor2<F>,
+        input_stride: usize,
+        output_stride: usize,
+    ) -> Result<Conv2dProgram<F>> {
+        Conv2dProgram::get_or_create(
+            &self.context,
+            &self.cache,
+            *window,
+            kernel.dims().rows(),
+            input_stride,
+            output_stride,
+            kernel.buffer_dims().cols(),
+        )
+    }
+
+    fn pool2d_program(&self, window: &Window2d, input_stride: usize, output_stride: usize) -> Result<Pool2dProgram<F>> {
+        Pool2dProgram::get_or_create(&self.context, &self.cache, *window, input_stride, output_stride)
+    }
+
+    fn batch_norm_program(&self, cols: usize, row_stride: usize) -> Result<BatchNormProgram<F>> {
+        BatchNormProgram::get_or_create(&self.context, &self.cache, cols, row_stride)
+    }
+
+    fn layer_norm_program(&self, cols: usize, row_stride: usize) -> Result<LayerNormProgram<F>> {
+        LayerNormProgram::get_or_create(&self.context, &self.cache, cols, row_stride)
+    }
+
+    fn dropout_program(&self, cols: usize, row_stride: usize) -> Result<DropoutProgram<F>> {
+        DropoutProgram::get_or_create(&self.context, &self.cache, cols, row_stride)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::backend::OpenCLBackend;
+    use crate::util::{Result, VecWidth};
+    use approx::assert_abs_diff_eq;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rand_distr::StandardNormal;
+    use rcann::activation::ActivationFn;
+    use rcann::backend::{CpuBackend, TensorOps};
+    use rcann::tensor::{Dim2, ITensor, Tensor2, TensorBase};
+
+    fn test_activation_fn(activation_fn: ActivationFn) -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(0x5718293);
+        let cpu = CpuBackend::<f32>::new(0);
+        let ocl = OpenCLBackend::<f32>::from_default_device(0, VecWidth::FOUR)?;
+
+        let activation = Tensor2::<f32>::from_distribution(&mut rng, StandardNormal, Dim2(23, 37));
+        let out_error = Tensor2::<f32>::from_distribution(&mut rng, StandardNormal, *activation.dims());
+
+        let mut output_expected = Tensor2::zeroed(*activation.dims());
+        let mut result_expected = Tensor2::zeroed(*activation.dims());
+        activation_fn.compute(&cpu, activation.view(), &mut output_expected);
+        activation_fn.compute_error(
+            &cpu,
+            activation.view(),
+            &output_expected,
+            &out_error,
+            &mut result_expected,
+        );
+
+        let ocl_activation = ocl.new_tensor_from_native(activation);
+        let ocl_out_error = ocl.new_tensor_from_native(out_error);
+        let mut ocl_output = ocl.new_tensor_exact(*ocl_activation.dims());
+        let mut ocl_result = ocl.new_tensor_exact(*ocl_activation.dims());
+        activation_fn.compute(&ocl, &ocl_activation, &mut ocl_output);
+        activation_fn.compute_error(&ocl, &ocl_activation, &ocl_output, &ocl_out_error, &mut ocl_result);
+
+        assert_abs_diff_eq!(output_expected, ocl.tensor_as_native(&ocl_output), epsilon = 0.001);
+        assert_abs_diff_eq!(result_expected, ocl.tensor_as_native(&ocl_result), epsilon = 0.001);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sigmoid() -> Result<()> {
+        test_activation_fn(ActivationFn::Sigmoid)
+    }
+
+    #[test]
+    fn test_relu() -> Result<()> {
+        test_activation_fn(ActivationFn::ReLU { leak: 0.0 })
+    }
+
+    #[test]
+    fn test_leaky_relu() -> Result<()> {
+        test_activation_fn(ActivationFn::ReLU { leak: 0.01 })
+    }
+
+    #[test]
+    fn test_softmax() -> Result<()> {
+        test_activation_fn(ActivationFn::Softmax)
+    }
+
+    #[test]
+    fn test_tanh() -> Result<()> {
+        test_activation_fn(ActivationFn::Tanh)
+    }
+
+    #[test]
+    fn test_elu() -> Result<()> {
+        test_activation_fn(ActivationFn::ELU { alpha: 1.0 })
+    }
+
+    #[test]
+    fn test_selu() -> Result<()> {
+        test_activation_fn(ActivationFn::SELU)
+    }
+
+    #[test]
+    fn test_gelu() -> Result<()> {
+        test_activation_fn(ActivationFn::GELU)
+    }
+
+    #[test]
+    fn test_swish() -> Result<()> {
+        test_activation_fn(ActivationFn::Swish)
+    }
+
+    #[test]
+    fn test_softplus() -> Result<()> {
+        test_activation_fn(ActivationFn::Softplus)
+    }
+
+    #[test]
+    fn test_identity() -> Result<()> {
+        test_activation_fn(ActivationFn::Identity)
     }
 }
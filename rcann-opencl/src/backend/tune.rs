@@ -0,0 +1,316 @@
+use crate::error::Error;
+use crate::kernels::gemm::GeMMProgram;
+use crate::kernels::BUFFER_BLOCK_SIZE;
+use crate::tensor::{OclFloat, OclTensor2};
+use crate::util::{
+    self, describe_device, is_power_of_two, is_valid_block_size, Profiler, ProgramCache, Result, VecWidth,
+};
+use crate::wrap_cl_error;
+use opencl3::command_queue::CommandQueue;
+use opencl3::context::Context;
+use opencl3::device::Device;
+use rcann::tensor::Dim2;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::Path;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::Duration;
+
+const VEC_WIDTHS: [VecWidth; 5] = [
+    VecWidth::ONE,
+    VecWidth::TWO,
+    VecWidth::FOUR,
+    VecWidth::EIGHT,
+    VecWidth::SIXTEEN,
+];
+
+/// The compile parameters of the matrix multiplication kernel. The fastest configuration depends on the
+/// device, see [GemmTuner].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GemmConfig {
+    /// the rows and columns of the tiles of both matrices each work group loads into local memory
+    pub tile_size: usize,
+    pub vec_width: VecWidth,
+}
+
+impl Default for GemmConfig {
+    fn default() -> Self {
+        GemmConfig::new(BUFFER_BLOCK_SIZE, VecWidth::SIXTEEN)
+    }
+}
+
+impl GemmConfig {
+    pub fn new(tile_size: usize, vec_width: VecWidth) -> Self {
+        GemmConfig { tile_size, vec_width }
+    }
+
+    /// every configuration the kernel can be compiled with
+    pub fn all() -> Vec<GemmConfig> {
+        (0..)
+            .map(|exp| 1usize << exp)
+            .take_while(|&tile_size| tile_size <= BUFFER_BLOCK_SIZE)
+            .filter(|&tile_size| is_valid_block_size(tile_size))
+            .flat_map(|tile_size| VEC_WIDTHS.map(|vec_width| GemmConfig::new(tile_size, vec_width)))
+            .filter(|config| config.validate().is_ok())
+            .collect()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !is_power_of_two(self.tile_size) || BUFFER_BLOCK_SIZE % self.tile_size != 0 {
+            return Err(Error::ValidationError(format!(
+                "tile_size must be a power of two dividing {BUFFER_BLOCK_SIZE}, got {}",
+                self.tile_size
+            )));
+        }
+        if self.tile_size % self.vec_width as usize != 0 {
+            return Err(Error::ValidationError(format!(
+                "tile_size must be a multiple of vec_width, got {self}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// whether the work groups of this configuration fit the limits of the device
+    fn fits_device<F: OclFloat>(&self, device: &Device) -> Result<bool> {
+        let work_group_size = self.tile_size * self.tile_size / self.vec_width as usize;
+        let local_mem_size = 2 * self.tile_size * self.tile_size * mem::size_of::<F>();
+        let max_work_group_size = wrap_cl_error!(device.max_work_group_size(), "Failed to get max work group size")?;
+        let max_local_mem_size = wrap_cl_error!(device.local_mem_size(), "Failed to get local memory size")?;
+        Ok(work_group_size <= max_work_group_size && local_mem_size as u64 <= max_local_mem_size)
+    }
+
+    /// stores the configuration along with the device and float type it was tuned for, since which
+    /// configurations fit the local memory of the device depends on the size of the floats
+    pub fn save<F: OclFloat, P: AsRef<Path>>(&self, device: &Device, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, format!("{}\n{self}\n", Self::tuned_for::<F>(device)?))?;
+        Ok(())
+    }
+
+    /// loads a configuration stored by [GemmConfig::save], or returns `None` if there is none or it was
+    /// tuned for another device, driver or float type
+    pub fn load<F: OclFloat, P: AsRef<Path>>(device: &Device, path: P) -> Result<Option<Self>> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        match contents.split_once('\n') {
+            Some((tuned_for, config)) if tuned_for == Self::tuned_for::<F>(device)? => Ok(Some(config.parse()?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// the first line of a stored configuration: the description of the device and the float type, e.g. `f32`
+    fn tuned_for<F: OclFloat>(device: &Device) -> Result<String> {
+        Ok(format!("{};f{}", describe_device(device)?, F::BITS))
+    }
+}
+
+impl Display for GemmConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "tile_size={},vec_width={}", self.tile_size, self.vec_width)
+    }
+}
+
+/// Parses the format written by [Display], e.g. `tile_size=16,vec_width=4`. Missing keys are left at
+/// their default.
+impl FromStr for GemmConfig {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let mut config = GemmConfig::default();
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let Some((key, value)) = part.split_once('=') else {
+                return Err(Error::ConversionError(format!("Invalid GeMM config: {s}")));
+            };
+            let value = value.trim();
+            match key.trim() {
+                "tile_size" => {
+                    config.tile_size = value
+                        .parse()
+                        .map_err(|_| Error::ConversionError(format!("Invalid tile size: {value}")))?
+                }
+                "vec_width" => {
+                    let vec_width: u8 = value
+                        .parse()
+                        .map_err(|_| Error::ConversionError(format!("Invalid vector width: {value}")))?;
+                    config.vec_width = vec_width.try_into()?
+                }
+                _ => return Err(Error::ConversionError(format!("Invalid GeMM config key: {key}"))),
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// The time a configuration took to compute all products of a [GemmTuner], with each operand transposed or not
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GemmTiming {
+    pub config: GemmConfig,
+    pub time: Duration,
+}
+
+/// The timings of every configuration the device could run, fastest first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GemmTuning {
+    pub timings: Vec<GemmTiming>,
+}
+
+impl GemmTuning {
+    pub fn best(&self) -> GemmConfig {
+        self.timings[0].config
+    }
+}
+
+/// Finds the fastest [GemmConfig] of a device by timing every configuration on products of the given shapes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GemmTuner {
+    /// the `(m, k, n)` shapes of the products to time, e.g. the batch size, inputs and outputs of each layer
+    pub shapes: Vec<(usize, usize, usize)>,
+    /// the number of times each product is timed, after an untimed run to warm up
+    pub iterations: usize,
+}
+
+impl Default for GemmTuner {
+    fn default() -> Self {
+        GemmTuner {
+            shapes: vec![(64, 784, 128), (64, 128, 64), (256, 256, 256), (1024, 1024, 1024)],
+            iterations: 10,
+        }
+    }
+}
+
+impl GemmTuner {
+    pub fn new(shapes: Vec<(usize, usize, usize)>) -> Self {
+        GemmTuner {
+            shapes,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    pub fn tune<F: OclFloat>(&self, device: &Device) -> Result<GemmTuning> {
+        let context = util::get_context(device)?;
+        let queue = util::create_profiling_queue(&context)?;
+        let profiler = Rc::new(Profiler::default());
+        let cache = ProgramCache::new(None, Some(profiler.clone()));
+        let mut timings = Vec::new();
+        for config in GemmConfig::all() {
+            if !config.fits_device::<F>(device)? {
+                continue;
+            }
+            // configurations the compiler or driver rejects are skipped
+            if let Ok(time) = self.time::<F>(&context, &queue, &cache, &profiler, config) {
+                timings.push(GemmTiming { config, time });
+            }
+        }
+        if timings.is_empty() {
+            return Err(Error::ValidationError(format!(
+                "No GeMM configuration runs on {}",
+                describe_device(device)?
+            )));
+        }
+        timings.sort_by_key(|timing| timing.time);
+        Ok(GemmTuning { timings })
+    }
+
+    /// loads the configuration stored at the path for this device, or tunes and stores it if there is none
+    pub fn load_or_tune<F: OclFloat, P: AsRef<Path>>(&self, device: &Device, path: P) -> Result<GemmConfig> {
+        if let Some(config) = GemmConfig::load::<F, _>(device, &path)? {
+            return Ok(config);
+        }
+        let config = self.tune::<F>(device)?.best();
+        config.save::<F, _>(device, &path)?;
+        Ok(config)
+    }
+
+    fn time<F: OclFloat>(
+        &self,
+        context: &Context,
+        queue: &CommandQueue,
+        cache: &ProgramCache,
+        profiler: &Profiler,
+        config: GemmConfig,
+    ) -> Result<Duration> {
+        profiler.reset();
+        let program = GeMMProgram::<F>::get_or_create(context, cache, config.vec_width, config.tile_size)?;
+        let mut products = Vec::with_capacity(self.shapes.len());
+        for &(m, k, n) in &self.shapes {
+            products.push((
+                OclTensor2::<F>::zeroed(context, queue, Dim2(m, k))?,
+                OclTensor2::<F>::zeroed(context, queue, Dim2(k, m))?,
+                OclTensor2::<F>::zeroed(context, queue, Dim2(k, n))?,
+                OclTensor2::<F>::zeroed(context, queue, Dim2(n, k))?,
+                OclTensor2::<F>::zeroed(context, queue, Dim2(m, n))?,
+            ));
+        }
+        for run in 0..=self.iterations {
+            // the variants with transposed operands load their tiles differently, so they are timed as well
+            for (a, a_t, b, b_t, c) in products.iter_mut() {
+                program.gemm(queue, F::ONE, a, b, F::ZERO, c)?;
+                program.gemm_tn(queue, F::ONE, a_t, b, F::ZERO, c)?;
+                program.gemm_nt(queue, F::ONE, a, b_t, F::ZERO, c)?;
+                program.gemm_tt(queue, F::ONE, a_t, b_t, F::ZERO, c)?;
+            }
+            if run == 0 {
+                // waits for the warm up, so it isn't included in the time
+                profiler.report()?;
+                profiler.reset();
+            }
+        }
+        Ok(profiler.report()?.total())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::backend::{GemmConfig, GemmTuner};
+    use crate::util::{self, Result, VecWidth};
+    use std::fs;
+
+    #[test]
+    fn test_parse_config() {
+        let config = GemmConfig::new(8, VecWidth::FOUR);
+        assert_eq!("tile_size=8, vec_width=4".parse::<GemmConfig>().unwrap(), config);
+        assert_eq!(config.to_string().parse::<GemmConfig>().unwrap(), config);
+        assert_eq!("".parse::<GemmConfig>().unwrap(), GemmConfig::default());
+        assert!("tile_size=4,vec_width=8".parse::<GemmConfig>().is_err());
+        assert!("tile_size=12,vec_width=4".parse::<GemmConfig>().is_err());
+        assert!("vec_width=3".parse::<GemmConfig>().is_err());
+        assert!("block_size=8".parse::<GemmConfig>().is_err());
+        assert!(GemmConfig::all().contains(&GemmConfig::default()));
+        assert!(GemmConfig::all().iter().all(|config| config.validate().is_ok()));
+    }
+
+    #[test]
+    fn test_tune() -> Result<()> {
+        let device = util::get_default_device()?;
+        let tuning = GemmTuner::new(vec![(32, 48, 64)])
+            .with_iterations(2)
+            .tune::<f32>(&device)?;
+        assert!(tuning.timings.windows(2).all(|w| w[0].time <= w[1].time));
+        assert!(GemmConfig::all().contains(&tuning.best()));
+
+        let path = std::env::temp_dir().join(format!("rcann-gemm-config-{}.txt", std::process::id()));
+        assert_eq!(GemmConfig::load::<f32, _>(&device, &path)?, None);
+        tuning.best().save::<f32, _>(&device, &path)?;
+        assert_eq!(GemmConfig::load::<f32, _>(&device, &path)?, Some(tuning.best()));
+        // a config tuned for another float type or device isn't loaded
+        assert_eq!(GemmConfig::load::<f64, _>(&device, &path)?, None);
+        fs::write(&path, format!("Other Device;OpenCL 3.0;1.0;f32\n{}\n", tuning.best()))?;
+        assert_eq!(GemmConfig::load::<f32, _>(&device, &path)?, None);
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
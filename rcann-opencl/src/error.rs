@@ -1,5 +1,6 @@
 use opencl3::error_codes::ClError;
 use opencl3::types::cl_int;
+use std::fmt::{Display, Formatter};
 
 #[derive(Debug)]
 pub enum Error {
@@ -11,8 +12,11 @@ pub enum Error {
     CreateProgramError(String),
     TensorResizeError(String),
     NoDevicesFound,
+    /// No device matches a [crate::util::DeviceSelector]
+    DeviceNotFound(String),
     ValidationError(String),
     ConversionError(String),
+    IoError(std::io::Error),
 }
 
 impl Error {
@@ -29,3 +33,35 @@ impl Error {
         }
     }
 }
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ClError {
+                code,
+                code_str,
+                msg: Some(msg),
+            } => write!(f, "{msg}: {code_str} ({code})"),
+            Error::ClError {
+                code,
+                code_str,
+                msg: None,
+            } => write!(f, "OpenCL error: {code_str} ({code})"),
+            Error::CreateProgramError(log) => write!(f, "Failed to create program: {log}"),
+            Error::TensorResizeError(msg) => write!(f, "Failed to resize tensor: {msg}"),
+            Error::NoDevicesFound => write!(f, "No OpenCL devices found"),
+            Error::DeviceNotFound(msg) => write!(f, "{msg}"),
+            Error::ValidationError(msg) => write!(f, "{msg}"),
+            Error::ConversionError(msg) => write!(f, "{msg}"),
+            Error::IoError(err) => write!(f, "I/O error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::IoError(err)
+    }
+}
@@ -0,0 +1,165 @@
+#define IN_LEN (IN_C * IN_H * IN_W)
+#define OUT_PLANE (OUT_H * OUT_W)
+#define OUT_LEN (FILTERS * OUT_PLANE)
+#define PATCH_LEN (IN_C * KSIZE * KSIZE)
+
+// computes the dot product of a filter with the window of the input at output position (oy, ox)
+inline real conv2d_window(
+        const __global real* in_row,
+        const __global real* filter,
+        const uint oy,
+        const uint ox
+) {
+    const int y0 = (int)(oy * STRIDE) - PAD;
+    const int x0 = (int)(ox * STRIDE) - PAD;
+    real sum = (real)0.0;
+    for (uint c = 0; c < IN_C; c++) {
+        for (uint ky = 0; ky < KSIZE; ky++) {
+            const int y = y0 + (int)ky;
+            if (y < 0 || y >= IN_H) {
+                continue;
+            }
+            for (uint kx = 0; kx < KSIZE; kx++) {
+                const int x = x0 + (int)kx;
+                if (x < 0 || x >= IN_W) {
+                    continue;
+                }
+                sum += filter[(c * KSIZE + ky) * KSIZE + kx] * in_row[(c * IN_H + y) * IN_W + x];
+            }
+        }
+    }
+    return sum;
+}
+
+__kernel void conv2d(
+        const uint ROWS,
+        const __global real* input,
+        const __global real* weights,
+        __global real* output
+) {
+    const uint row = get_global_id(0);
+    const uint idx = get_global_id(1);
+    if (row >= ROWS || idx >= OUT_LEN) {
+        return;
+    }
+    const uint f = idx / OUT_PLANE;
+    const uint oy = (idx % OUT_PLANE) / OUT_W;
+    const uint ox = idx % OUT_W;
+    output[row * OUT_STRIDE + idx] = conv2d_window(input + row * IN_STRIDE, weights + f * KERNEL_STRIDE, oy, ox);
+}
+
+__kernel void conv2d_bias(
+        const uint ROWS,
+        const __global real* input,
+        const __global real* weights,
+        const __global real* bias,
+        __global real* output
+) {
+    const uint row = get_global_id(0);
+    const uint idx = get_global_id(1);
+    if (row >= ROWS || idx >= OUT_LEN) {
+        return;
+    }
+    const uint f = idx / OUT_PLANE;
+    const uint oy = (idx % OUT_PLANE) / OUT_W;
+    const uint ox = idx % OUT_W;
+    output[row * OUT_STRIDE + idx] = bias[f] + conv2d_window(input + row * IN_STRIDE, weights + f * KERNEL_STRIDE, oy, ox);
+}
+
+// one work item per input value, gathering the error of every output whose window covers it
+__kernel void conv2d_input_error(
+        const uint ROWS,
+        const __global real* out_error,
+        const __global real* weights,
+        __global real* input_error
+) {
+    const uint row = get_global_id(0);
+    const uint idx = get_global_id(1);
+    if (row >= ROWS || idx >= IN_LEN) {
+        return;
+    }
+    const uint c = idx / (IN_H * IN_W);
+    const int y = (int)((idx % (IN_H * IN_W)) / IN_W) + PAD;
+    const int x = (int)(idx % IN_W) + PAD;
+    const __global real* err_row = out_error + row * OUT_STRIDE;
+    real sum = (real)0.0;
+    for (uint ky = 0; ky < KSIZE; ky++) {
+        const int ty = y - (int)ky;
+        if (ty < 0 || ty % STRIDE != 0 || ty / STRIDE >= OUT_H) {
+            continue;
+        }
+        const uint oy = ty / STRIDE;
+        for (uint kx = 0; kx < KSIZE; kx++) {
+            const int tx = x - (int)kx;
+            if (tx < 0 || tx % STRIDE != 0 || tx / STRIDE >= OUT_W) {
+                continue;
+            }
+            const uint ox = tx / STRIDE;
+            const uint k = (c * KSIZE + ky) * KSIZE + kx;
+            for (uint f = 0; f < FILTERS; f++) {
+                sum += weights[f * KERNEL_STRIDE + k] * err_row[f * OUT_PLANE + oy * OUT_W + ox];
+            }
+        }
+    }
+    input_error[row * IN_STRIDE + idx] = sum;
+}
+
+// one work group per kernel value, whose work items each sum a strided share of the rows and output positions,
+// before the partial sums are added up in local memory
+__kernel void conv2d_kernel_grad(
+        const uint ROWS,
+        const __global real* input,
+        const __global real* out_error,
+        __global real* weights_grad
+) {
+    __local real partial[GRAD_GROUP_SIZE];
+    const uint f = get_group_id(0);
+    const uint k = get_global_id(1);
+    const uint lid = get_local_id(0);
+    const uint c = k / (KSIZE * KSIZE);
+    const uint ky = (k / KSIZE) % KSIZE;
+    const uint kx = k % KSIZE;
+    real sum = (real)0.0;
+    for (uint i = lid; i < ROWS * OUT_PLANE; i += GRAD_GROUP_SIZE) {
+        const uint row = i / OUT_PLANE;
+        const uint oy = (i % OUT_PLANE) / OUT_W;
+        const uint ox = i % OUT_W;
+        const int y = (int)(oy * STRIDE + ky) - PAD;
+        const int x = (int)(ox * STRIDE + kx) - PAD;
+        if (y < 0 || y >= IN_H || x < 0 || x >= IN_W) {
+            continue;
+        }
+        sum += input[row * IN_STRIDE + (c * IN_H + y) * IN_W + x]
+            * out_error[row * OUT_STRIDE + f * OUT_PLANE + oy * OUT_W + ox];
+    }
+    partial[lid] = sum;
+    barrier(CLK_LOCAL_MEM_FENCE);
+    for (uint offset = GRAD_GROUP_SIZE / 2; offset > 0; offset /= 2) {
+        if (lid < offset) {
+            partial[lid] += partial[lid + offset];
+        }
+        barrier(CLK_LOCAL_MEM_FENCE);
+    }
+    if (lid == 0) {
+        weights_grad[f * KERNEL_STRIDE + k] = partial[0];
+    }
+}
+
+__kernel void conv2d_bias_grad(
+        const uint ROWS,
+        const __global real* out_error,
+        __global real* bias_grad
+) {
+    const uint f = get_global_id(0);
+    if (f >= FILTERS) {
+        return;
+    }
+    real sum = (real)0.0;
+    for (uint row = 0; row < ROWS; row++) {
+        const __global real* err_plane = out_error + row * OUT_STRIDE + f * OUT_PLANE;
+        for (uint i = 0; i < OUT_PLANE; i++) {
+            sum += err_plane[i];
+        }
+    }
+    bias_grad[f] = sum;
+}
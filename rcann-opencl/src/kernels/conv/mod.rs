@@ -0,0 +1,163 @@
+#[cfg(test)]
+mod test;
+
+use crate::tensor::{OclFloat, OclTensor1, OclTensor2};
+use crate::util::*;
+use rcann::backend::Window2d;
+use rcann::tensor::Dims;
+
+/// the number of work items summing the gradient of each kernel value, must be a power of two
+const GRAD_GROUP_SIZE: usize = 64;
+
+ocl_program! {
+    name = Conv2dProgram,
+    source = "conv2d.cl",
+    generic_args = <T: OclFloat>,
+    compile_params = (
+        window: Window2d,
+        filters: usize,
+        input_stride: usize,
+        output_stride: usize,
+        kernel_stride: usize,
+    ),
+    validation = {
+        validate!(window.is_valid(), "Invalid convolution window");
+        validate!(*filters > 0, "filters must be positive");
+        validate!(*input_stride >= window.input_dims.tensor_len(), "Input stride too small");
+        validate!(*output_stride >= window.output_dims(*filters).tensor_len(), "Output stride too small");
+        validate!(*kernel_stride >= window.patch_len(), "Kernel stride too small");
+    },
+    defines = {
+        FLOAT_BITS = T::BITS,
+        IN_C = window.input_dims.0,
+        IN_H = window.input_dims.1,
+        IN_W = window.input_dims.2,
+        KSIZE = window.size,
+        STRIDE = window.stride,
+        PAD = window.padding,
+        OUT_H = window.output_height(),
+        OUT_W = window.output_width(),
+        FILTERS = *filters,
+        IN_STRIDE = *input_stride,
+        OUT_STRIDE = *output_stride,
+        KERNEL_STRIDE = *kernel_stride,
+        GRAD_GROUP_SIZE = GRAD_GROUP_SIZE,
+    },
+    kernels = {
+        conv2d {
+            call_params = (
+                input: &OclTensor2<T>,
+                kernel: &OclTensor2<T>,
+                output: &mut OclTensor2<T>,
+            ),
+            validation = {
+                assert_eq!(input.buffer_dims().cols(), *input_stride);
+                assert_eq!(kernel.dims(), &Dim2(*filters, window.patch_len()));
+                assert_eq!(kernel.buffer_dims().cols(), *kernel_stride);
+                assert_eq!(output.dims(), &Dim2(input.dims().rows(), window.output_dims(*filters).tensor_len()));
+                assert_eq!(output.buffer_dims().cols(), *output_stride);
+            },
+            inputs = [input, kernel],
+            outputs = [output],
+            kernel_args = [
+                &(input.dims().rows() as u32),
+                input.buffer(),
+                kernel.buffer(),
+                output.buffer(),
+            ],
+            global_dims = [next_multiple(input.dims().rows(), 16), output.buffer_dims().cols()],
+        },
+        conv2d_bias {
+            call_params = (
+                input: &OclTensor2<T>,
+                kernel: &OclTensor2<T>,
+                bias: &OclTensor1<T>,
+                output: &mut OclTensor2<T>,
+            ),
+            validation = {
+                assert_eq!(input.buffer_dims().cols(), *input_stride);
+                assert_eq!(kernel.dims(), &Dim2(*filters, window.patch_len()));
+                assert_eq!(kernel.buffer_dims().cols(), *kernel_stride);
+                assert_eq!(bias.dims(), &Dim1(*filters));
+                assert_eq!(output.dims(), &Dim2(input.dims().rows(), window.output_dims(*filters).tensor_len()));
+                assert_eq!(output.buffer_dims().cols(), *output_stride);
+            },
+            inputs = [input, kernel, bias],
+            outputs = [output],
+            kernel_args = [
+                &(input.dims().rows() as u32),
+                input.buffer(),
+                kernel.buffer(),
+                bias.buffer(),
+                output.buffer(),
+            ],
+            global_dims = [next_multiple(input.dims().rows(), 16), output.buffer_dims().cols()],
+        },
+        conv2d_input_error {
+            call_params = (
+                out_error: &OclTensor2<T>,
+                kernel: &OclTensor2<T>,
+                input_error: &mut OclTensor2<T>,
+            ),
+            validation = {
+                assert_eq!(out_error.dims(), &Dim2(input_error.dims().rows(), window.output_dims(*filters).tensor_len()));
+                assert_eq!(out_error.buffer_dims().cols(), *output_stride);
+                assert_eq!(kernel.dims(), &Dim2(*filters, window.patch_len()));
+                assert_eq!(kernel.buffer_dims().cols(), *kernel_stride);
+                assert_eq!(input_error.dims().cols(), window.input_dims.tensor_len());
+                assert_eq!(input_error.buffer_dims().cols(), *input_stride);
+            },
+            inputs = [out_error, kernel],
+            outputs = [input_error],
+            kernel_args = [
+                &(input_error.dims().rows() as u32),
+                out_error.buffer(),
+                kernel.buffer(),
+                input_error.buffer(),
+            ],
+            global_dims = [next_multiple(input_error.dims().rows(), 16), input_error.buffer_dims().cols()],
+        },
+        conv2d_kernel_grad {
+            call_params = (
+                input: &OclTensor2<T>,
+                out_error: &OclTensor2<T>,
+                kernel_grad: &mut OclTensor2<T>,
+            ),
+            validation = {
+                assert_eq!(input.buffer_dims().cols(), *input_stride);
+                assert_eq!(out_error.dims(), &Dim2(input.dims().rows(), window.output_dims(*filters).tensor_len()));
+                assert_eq!(out_error.buffer_dims().cols(), *output_stride);
+                assert_eq!(kernel_grad.dims(), &Dim2(*filters, window.patch_len()));
+                assert_eq!(kernel_grad.buffer_dims().cols(), *kernel_stride);
+            },
+            inputs = [input, out_error],
+            outputs = [kernel_grad],
+            kernel_args = [
+                &(input.dims().rows() as u32),
+                input.buffer(),
+                out_error.buffer(),
+                kernel_grad.buffer(),
+            ],
+            global_dims = [*filters * GRAD_GROUP_SIZE, window.patch_len()],
+            local_dims = [GRAD_GROUP_SIZE, 1],
+        },
+        conv2d_bias_grad {
+            call_params = (
+                out_error: &OclTensor2<T>,
+                bias_grad: &mut OclTensor1<T>,
+            ),
+            validation = {
+                assert_eq!(out_error.buffer_dims().cols(), *output_stride);
+                assert_eq!(bias_grad.dims(), &Dim1(*filters));
+            },
+            inputs = [out_error],
+            outputs = [bias_grad],
+            kernel_args = [
+                &(out_error.dims().rows() as u32),
+                out_error.buffer(),
+                bias_grad.buffer(),
+            ],
+            global_dims = [next_multiple(*filters, 16)],
+        },
+    },
+}
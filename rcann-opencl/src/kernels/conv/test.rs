@@ -0,0 +1,89 @@
+use super::Conv2dProgram;
+use crate::tensor::{OclTensor1, OclTensor2};
+use crate::util::*;
+use approx::assert_abs_diff_eq;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rand_distr::StandardNormal;
+use rcann::backend::{BackendOther, CpuBackend, Window2d};
+use rcann::tensor::{Dim1, Dim2, Dim3, Dims, ITensor, Tensor1, Tensor2, TensorBase};
+
+fn test_conv2d(window: Window2d, filters: usize) -> Result<()> {
+    let TestContext { context, queue, .. } = create_test_context()?;
+    let cpu = CpuBackend::<f32>::new(0);
+    let mut rng = StdRng::seed_from_u64(0x2d2d2d2d);
+    let rows = 21;
+    let out_len = window.output_dims(filters).tensor_len();
+
+    let input = Tensor2::from_distribution(&mut rng, StandardNormal, Dim2(rows, window.input_dims.tensor_len()));
+    let kernel = Tensor2::from_distribution(&mut rng, StandardNormal, Dim2(filters, window.patch_len()));
+    let bias = Tensor1::from_distribution(&mut rng, StandardNormal, Dim1(filters));
+    let out_error = Tensor2::from_distribution(&mut rng, StandardNormal, Dim2(rows, out_len));
+
+    let mut output = Tensor2::zeroed(Dim2(rows, out_len));
+    let mut output_bias = Tensor2::zeroed(Dim2(rows, out_len));
+    let mut input_error = Tensor2::zeroed(*input.dims());
+    let mut kernel_grad = Tensor2::zeroed(*kernel.dims());
+    let mut bias_grad = Tensor1::zeroed(*bias.dims());
+    cpu.conv2d(&window, input.view(), &kernel, None, &mut output);
+    cpu.conv2d(&window, input.view(), &kernel, Some(&bias), &mut output_bias);
+    cpu.conv2d_input_error(&window, &out_error, &kernel, &mut input_error);
+    cpu.conv2d_param_grad(&window, input.view(), &out_error, &mut kernel_grad, Some(&mut bias_grad));
+
+    let input_ocl = OclTensor2::from_native(&context, &queue, &input)?;
+    let kernel_ocl = OclTensor2::from_native(&context, &queue, &kernel)?;
+    let bias_ocl = OclTensor1::from_native(&context, &queue, &bias)?;
+    let out_error_ocl = OclTensor2::from_native(&context, &queue, &out_error)?;
+    let mut output_ocl = OclTensor2::zeroed(&context, &queue, *output.dims())?;
+    let mut output_bias_ocl = OclTensor2::zeroed(&context, &queue, *output.dims())?;
+    let mut input_error_ocl = OclTensor2::zeroed(&context, &queue, *input.dims())?;
+    let mut kernel_grad_ocl = OclTensor2::zeroed(&context, &queue, *kernel.dims())?;
+    let mut bias_grad_ocl = OclTensor1::zeroed(&context, &queue, *bias.dims())?;
+
+    let program = Conv2dProgram::<f32>::create(
+        &context,
+        window,
+        filters,
+        input_ocl.buffer_dims().cols(),
+        output_ocl.buffer_dims().cols(),
+        kernel_ocl.buffer_dims().cols(),
+    )?;
+    program.conv2d(&queue, &input_ocl, &kernel_ocl, &mut output_ocl)?;
+    program.conv2d_bias(&queue, &input_ocl, &kernel_ocl, &bias_ocl, &mut output_bias_ocl)?;
+    program.conv2d_input_error(&queue, &out_error_ocl, &kernel_ocl, &mut input_error_ocl)?;
+    program.conv2d_kernel_grad(&queue, &input_ocl, &out_error_ocl, &mut kernel_grad_ocl)?;
+    program.conv2d_bias_grad(&queue, &out_error_ocl, &mut bias_grad_ocl)?;
+
+    assert_abs_diff_eq!(output, output_ocl.as_native(&queue)?, epsilon = 0.001);
+    assert_abs_diff_eq!(output_bias, output_bias_ocl.as_native(&queue)?, epsilon = 0.001);
+    assert_abs_diff_eq!(input_error, input_error_ocl.as_native(&queue)?, epsilon = 0.001);
+    assert_abs_diff_eq!(kernel_grad, kernel_grad_ocl.as_native(&queue)?, epsilon = 0.01);
+    assert_abs_diff_eq!(bias_grad, bias_grad_ocl.as_native(&queue)?, epsilon = 0.01);
+    Ok(())
+}
+
+#[test]
+fn test_conv2d_single_channel() -> Result<()> {
+    test_conv2d(
+        Window2d {
+            input_dims: Dim3(1, 28, 28),
+            size: 5,
+            stride: 1,
+            padding: 0,
+        },
+        6,
+    )
+}
+
+#[test]
+fn test_conv2d_strided_padded() -> Result<()> {
+    test_conv2d(
+        Window2d {
+            input_dims: Dim3(3, 13, 11),
+            size: 3,
+            stride: 2,
+            padding: 1,
+        },
+        4,
+    )
+}
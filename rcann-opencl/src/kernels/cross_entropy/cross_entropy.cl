@@ -0,0 +1,152 @@
+// lower bound applied to probabilities before taking their log (matches rcann::loss::CROSS_ENTROPY_EPSILON)
+#define EPSILON ((real)1e-7)
+
+__kernel void categorical_cross_entropy(
+        const uint ROWS,
+        const __global realX* output,
+        const __global realX* expected,
+        __global real* result,
+        __global realX* result_deriv
+) {
+    const uint row = get_global_id(0);
+    if (row >= ROWS) {
+        return;
+    }
+
+    const uint row_offset = row * (ROW_STRIDE / VEC_WIDTH);
+
+    realX accum = (realX)(0.0);
+    for (uint c = 0; c < VEC_COLS; c++) {
+        const uint idx = row_offset + c;
+        const realX out = fmax(output[idx], (realX)(EPSILON));
+        const realX target = expected[idx];
+        result_deriv[idx] = -target / out;
+        accum -= target * log(out);
+    }
+
+    if (VEC_COLS_REM > 0) {
+        const uint rem_offset = row_offset + VEC_COLS;
+        #pragma unroll
+        for (uint c = 0; c < VEC_COLS_REM; c++) {
+            const real out = fmax(VEC_IDX(output[rem_offset], c), EPSILON);
+            const real target = VEC_IDX(expected[rem_offset], c);
+            VEC_IDX(result_deriv[rem_offset], c) = -target / out;
+            VEC_IDX(accum, c) -= target * log(out);
+        }
+    }
+
+    result[row] = VEC_DOT_SCALAR(accum, (real)1.0);
+}
+
+__kernel void binary_cross_entropy(
+        const uint ROWS,
+        const __global realX* output,
+        const __global realX* expected,
+        __global real* result,
+        __global realX* result_deriv
+) {
+    const uint row = get_global_id(0);
+    if (row >= ROWS) {
+        return;
+    }
+
+    const uint row_offset = row * (ROW_STRIDE / VEC_WIDTH);
+
+    realX accum = (realX)(0.0);
+    for (uint c = 0; c < VEC_COLS; c++) {
+        const uint idx = row_offset + c;
+        const realX out = clamp(output[idx], (realX)(EPSILON), (realX)((real)1.0 - EPSILON));
+        const realX target = expected[idx];
+        result_deriv[idx] = (out - target) / (out * ((realX)(1.0) - out));
+        accum -= target * log(out) + ((realX)(1.0) - target) * log((realX)(1.0) - out);
+    }
+
+    if (VEC_COLS_REM > 0) {
+        const uint rem_offset = row_offset + VEC_COLS;
+        #pragma unroll
+        for (uint c = 0; c < VEC_COLS_REM; c++) {
+            const real out = clamp(VEC_IDX(output[rem_offset], c), EPSILON, (real)1.0 - EPSILON);
+            const real target = VEC_IDX(expected[rem_offset], c);
+            VEC_IDX(result_deriv[rem_offset], c) = (out - target) / (out * ((real)1.0 - out));
+            VEC_IDX(accum, c) -= target * log(out) + ((real)1.0 - target) * log((real)1.0 - out);
+        }
+    }
+
+    result[row] = VEC_DOT_SCALAR(accum, (real)1.0/(real)COLS);
+}
+
+/**
+ * Computes the categorical cross-entropy of softmax(output) directly from the logits, using
+ * loss = sum(expected * (lse - output)) and deriv = softmax(output) * sum(expected) - expected
+ * where lse = log(sum(exp(output)))
+ */
+__kernel void softmax_cross_entropy(
+        const uint ROWS,
+        const __global realX* output,
+        const __global realX* expected,
+        __global real* result,
+        __global realX* result_deriv
+) {
+    const uint row = get_global_id(0);
+    if (row >= ROWS) {
+        return;
+    }
+
+    const uint row_offset = row * (ROW_STRIDE / VEC_WIDTH);
+    const uint rem_offset = row_offset + VEC_COLS;
+
+    // compute max(output) to prevent overflow
+    realX max_v = (realX)(-INFINITY);
+    for (uint c = 0; c < VEC_COLS; c++) {
+        max_v = fmax(max_v, output[row_offset + c]);
+    }
+    real max_out = VEC_MAX(max_v);
+    if (VEC_COLS_REM > 0) {
+        #pragma unroll
+        for (uint c = 0; c < VEC_COLS_REM; c++) {
+            max_out = fmax(max_out, VEC_IDX(output[rem_offset], c));
+        }
+    }
+
+    // compute lse and sum(expected)
+    const realX max_out_v = (realX)(max_out);
+    realX sum_exp_v = (realX)(0.0);
+    realX sum_expected_v = (realX)(0.0);
+    for (uint c = 0; c < VEC_COLS; c++) {
+        const uint idx = row_offset + c;
+        sum_exp_v += exp(output[idx] - max_out_v);
+        sum_expected_v += expected[idx];
+    }
+    real sum_exp = VEC_DOT_SCALAR(sum_exp_v, (real)1.0);
+    real sum_expected = VEC_DOT_SCALAR(sum_expected_v, (real)1.0);
+    if (VEC_COLS_REM > 0) {
+        #pragma unroll
+        for (uint c = 0; c < VEC_COLS_REM; c++) {
+            sum_exp += exp(VEC_IDX(output[rem_offset], c) - max_out);
+            sum_expected += VEC_IDX(expected[rem_offset], c);
+        }
+    }
+    const real lse = max_out + log(sum_exp);
+    const realX lse_v = (realX)(lse);
+
+    // compute the loss and derivative
+    realX accum = (realX)(0.0);
+    for (uint c = 0; c < VEC_COLS; c++) {
+        const uint idx = row_offset + c;
+        const realX out = output[idx];
+        const realX target = expected[idx];
+        result_deriv[idx] = exp(out - lse_v) * (realX)(sum_expected) - target;
+        accum += target * (lse_v - out);
+    }
+    if (VEC_COLS_REM > 0) {
+        #pragma unroll
+        for (uint c = 0; c < VEC_COLS_REM; c++) {
+            const real out = VEC_IDX(output[rem_offset], c);
+            const real target = VEC_IDX(expected[rem_offset], c);
+            VEC_IDX(result_deriv[rem_offset], c) = exp(out - lse) * sum_expected - target;
+            VEC_IDX(accum, c) += target * (lse - out);
+        }
+    }
+
+    result[row] = VEC_DOT_SCALAR(accum, (real)1.0);
+}
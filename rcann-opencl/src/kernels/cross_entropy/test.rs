@@ -0,0 +1,125 @@
+macro_rules! impl_tests {
+    ($mod_name:ident, $ty:ty) => {
+        mod $mod_name {
+
+            use crate::kernels::cross_entropy::CrossEntropyProgram;
+            use crate::tensor::{OclTensor1, OclTensor2};
+            use crate::util;
+            use crate::util::{Result, TestContext, VecWidth};
+            use approx::assert_abs_diff_eq;
+            use opencl3::command_queue::CommandQueue;
+            use rand::prelude::StdRng;
+            use rand::SeedableRng;
+            use rand_distr::{StandardNormal, Uniform};
+            use rcann::backend::{BackendOther, CpuBackend};
+            use rcann::tensor::{Dim1, Dim2, ITensor, Tensor1, Tensor2, TensorBase, TensorView2};
+
+            type CpuLossFn = fn(
+                &CpuBackend<$ty>,
+                &Tensor2<$ty>,
+                TensorView2<$ty>,
+                &mut Tensor1<$ty>,
+                &mut Tensor2<$ty>,
+            );
+            type OclLossFn = fn(
+                &CrossEntropyProgram<$ty>,
+                &CommandQueue,
+                &OclTensor2<$ty>,
+                &OclTensor2<$ty>,
+                &mut OclTensor1<$ty>,
+                &mut OclTensor2<$ty>,
+            ) -> Result<()>;
+
+            fn test_loss_fn(
+                output_vals: Tensor2<$ty>,
+                expected_vals: Tensor2<$ty>,
+                cpu_fn: CpuLossFn,
+                ocl_fn: OclLossFn,
+            ) -> Result<()> {
+                let TestContext { context, queue, .. } = util::create_test_context()?;
+                let cpu = CpuBackend::<$ty>::new(0);
+
+                let mut expected_result = Tensor1::zeroed(Dim1(output_vals.dims().rows()));
+                let mut expected_result_deriv = Tensor2::zeroed(*output_vals.dims());
+                cpu_fn(
+                    &cpu,
+                    &output_vals,
+                    expected_vals.view(),
+                    &mut expected_result,
+                    &mut expected_result_deriv,
+                );
+
+                let output_vals_ocl = OclTensor2::from_native(&context, &queue, &output_vals)?;
+                let expected_vals_ocl = OclTensor2::from_native(&context, &queue, &expected_vals)?;
+                let mut actual_result = OclTensor1::zeroed(&context, &queue, *expected_result.dims())?;
+                let mut actual_result_deriv = OclTensor2::zeroed(&context, &queue, *expected_result_deriv.dims())?;
+
+                let program = CrossEntropyProgram::<$ty>::create(
+                    &context,
+                    VecWidth::FOUR,
+                    output_vals_ocl.dims().cols(),
+                    output_vals_ocl.buffer_dims().cols(),
+                )?;
+                ocl_fn(
+                    &program,
+                    &queue,
+                    &output_vals_ocl,
+                    &expected_vals_ocl,
+                    &mut actual_result,
+                    &mut actual_result_deriv,
+                )?;
+
+                assert_abs_diff_eq!(expected_result, actual_result.as_native(&queue)?, epsilon = 0.001);
+                assert_abs_diff_eq!(
+                    expected_result_deriv,
+                    actual_result_deriv.as_native(&queue)?,
+                    epsilon = 0.001
+                );
+
+                Ok(())
+            }
+
+            #[test]
+            fn test_categorical_cross_entropy() -> Result<()> {
+                let mut rng = StdRng::seed_from_u64(0x82379173);
+                let output_vals = Tensor2::from_distribution(&mut rng, Uniform::new(0.05, 1.0), Dim2(90, 83));
+                let expected_vals = Tensor2::from_distribution(&mut rng, Uniform::new(0.0, 1.0), *output_vals.dims());
+                test_loss_fn(
+                    output_vals,
+                    expected_vals,
+                    CpuBackend::<$ty>::categorical_cross_entropy,
+                    CrossEntropyProgram::<$ty>::categorical_cross_entropy,
+                )
+            }
+
+            #[test]
+            fn test_binary_cross_entropy() -> Result<()> {
+                let mut rng = StdRng::seed_from_u64(0x82379173);
+                let output_vals = Tensor2::from_distribution(&mut rng, Uniform::new(0.05, 0.95), Dim2(90, 83));
+                let expected_vals = Tensor2::from_distribution(&mut rng, Uniform::new(0.0, 1.0), *output_vals.dims());
+                test_loss_fn(
+                    output_vals,
+                    expected_vals,
+                    CpuBackend::<$ty>::binary_cross_entropy,
+                    CrossEntropyProgram::<$ty>::binary_cross_entropy,
+                )
+            }
+
+            #[test]
+            fn test_softmax_cross_entropy() -> Result<()> {
+                let mut rng = StdRng::seed_from_u64(0x82379173);
+                let output_vals = Tensor2::from_distribution(&mut rng, StandardNormal, Dim2(90, 83));
+                let expected_vals = Tensor2::from_distribution(&mut rng, Uniform::new(0.0, 1.0), *output_vals.dims());
+                test_loss_fn(
+                    output_vals,
+                    expected_vals,
+                    CpuBackend::<$ty>::softmax_cross_entropy,
+                    CrossEntropyProgram::<$ty>::softmax_cross_entropy,
+                )
+            }
+        }
+    };
+}
+
+impl_tests!(tests_f32, f32);
+impl_tests!(tests_f64, f64);
@@ -0,0 +1,45 @@
+// the "lowbias32" integer hash, must match rcann::backend::hash32
+inline uint hash32(uint x) {
+    x ^= x >> 16;
+    x *= 0x7feb352dU;
+    x ^= x >> 15;
+    x *= 0x846ca68bU;
+    x ^= x >> 16;
+    return x;
+}
+
+// the mask is computed from the logical (unpadded) position of each value, so it matches the cpu backend
+__kernel void dropout(
+        const uint ROWS,
+        const uint seed,
+        const uint threshold,
+        const real keep_scale,
+        const __global real* input,
+        __global real* output,
+        __global real* scale
+) {
+    const uint row = get_global_id(0);
+    const uint col = get_global_id(1);
+    if (row >= ROWS || col >= COLS) {
+        return;
+    }
+    const uint idx = row * ROW_STRIDE + col;
+    const real s = (hash32((row * COLS + col) ^ seed) >> 8) >= threshold ? keep_scale : (real)0.0;
+    scale[idx] = s;
+    output[idx] = input[idx] * s;
+}
+
+__kernel void dropout_error(
+        const uint ROWS,
+        const __global real* scale,
+        const __global real* out_error,
+        __global real* input_error
+) {
+    const uint row = get_global_id(0);
+    const uint col = get_global_id(1);
+    if (row >= ROWS || col >= COLS) {
+        return;
+    }
+    const uint idx = row * ROW_STRIDE + col;
+    input_error[idx] = scale[idx] * out_error[idx];
+}
@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod test;
+
+use crate::tensor::{OclFloat, OclTensor2};
+use crate::util::*;
+use rcann::backend::DropoutMask;
+
+ocl_program! {
+    name = DropoutProgram,
+    source = "dropout.cl",
+    generic_args = <T: OclFloat>,
+    compile_params = (
+        cols: usize,
+        row_stride: usize,
+    ),
+    validation = {
+        validate!(*cols > 0, "cols must be positive");
+        validate!(*row_stride >= *cols, "Row stride too small");
+    },
+    defines = {
+        FLOAT_BITS = T::BITS,
+        COLS = *cols,
+        ROW_STRIDE = *row_stride,
+    },
+    kernels = {
+        dropout {
+            call_params = (
+                mask: &DropoutMask,
+                input: &OclTensor2<T>,
+                output: &mut OclTensor2<T>,
+                scale: &mut OclTensor2<T>,
+            ),
+            validation = {
+                assert_eq!(input.dims(), output.dims());
+                assert_eq!(input.dims(), scale.dims());
+                assert_eq!(input.buffer_dims().cols(), *row_stride);
+                assert_eq!(output.buffer_dims().cols(), *row_stride);
+                assert_eq!(scale.buffer_dims().cols(), *row_stride);
+            },
+            inputs = [input],
+            outputs = [output, scale],
+            kernel_args = [
+                &(input.dims().rows() as u32),
+                &mask.seed(),
+                &mask.threshold(),
+                &T::from_f64(mask.scale()),
+                input.buffer(),
+                output.buffer(),
+                scale.buffer(),
+            ],
+            global_dims = [next_multiple(input.dims().rows(), 16), input.buffer_dims().cols()],
+        },
+        dropout_error {
+            call_params = (
+                scale: &OclTensor2<T>,
+                out_error: &OclTensor2<T>,
+                input_error: &mut OclTensor2<T>,
+            ),
+            validation = {
+                assert_eq!(scale.dims(), out_error.dims());
+                assert_eq!(scale.dims(), input_error.dims());
+                assert_eq!(scale.buffer_dims().cols(), *row_stride);
+                assert_eq!(out_error.buffer_dims().cols(), *row_stride);
+                assert_eq!(input_error.buffer_dims().cols(), *row_stride);
+            },
+            inputs = [scale, out_error],
+            outputs = [input_error],
+            kernel_args = [
+                &(scale.dims().rows() as u32),
+                scale.buffer(),
+                out_error.buffer(),
+                input_error.buffer(),
+            ],
+            global_dims = [next_multiple(scale.dims().rows(), 16), scale.buffer_dims().cols()],
+        },
+    },
+}
@@ -0,0 +1,51 @@
+use super::DropoutProgram;
+use crate::tensor::OclTensor2;
+use crate::util::*;
+use approx::assert_abs_diff_eq;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rand_distr::StandardNormal;
+use rcann::backend::{BackendOther, CpuBackend, DropoutMask};
+use rcann::tensor::{Dim2, Tensor2, TensorBase};
+
+fn test_dropout(dims: Dim2, mask: DropoutMask) -> Result<()> {
+    let TestContext { context, queue, .. } = create_test_context()?;
+    let cpu = CpuBackend::<f32>::new(0);
+    let mut rng = StdRng::seed_from_u64(0xd40d);
+
+    let input = Tensor2::from_distribution(&mut rng, StandardNormal, dims);
+    let out_error = Tensor2::from_distribution(&mut rng, StandardNormal, dims);
+
+    let mut output = Tensor2::zeroed(dims);
+    let mut scale = Tensor2::zeroed(dims);
+    let mut input_error = Tensor2::zeroed(dims);
+    cpu.dropout(&mask, input.view(), &mut output, &mut scale);
+    cpu.dropout_error(&scale, &out_error, &mut input_error);
+
+    let input_ocl = OclTensor2::from_native(&context, &queue, &input)?;
+    let out_error_ocl = OclTensor2::from_native(&context, &queue, &out_error)?;
+    let mut output_ocl = OclTensor2::zeroed(&context, &queue, dims)?;
+    let mut scale_ocl = OclTensor2::zeroed(&context, &queue, dims)?;
+    let mut input_error_ocl = OclTensor2::zeroed(&context, &queue, dims)?;
+
+    let program = DropoutProgram::<f32>::create(&context, dims.cols(), input_ocl.buffer_dims().cols())?;
+    program.dropout(&queue, &mask, &input_ocl, &mut output_ocl, &mut scale_ocl)?;
+    program.dropout_error(&queue, &scale_ocl, &out_error_ocl, &mut input_error_ocl)?;
+
+    // the masks must match exactly
+    assert_eq!(scale, scale_ocl.as_native(&queue)?);
+    assert_abs_diff_eq!(output, output_ocl.as_native(&queue)?, epsilon = 0.0001);
+    assert_abs_diff_eq!(input_error, input_error_ocl.as_native(&queue)?, epsilon = 0.0001);
+    Ok(())
+}
+
+#[test]
+fn test_dropout_mask() -> Result<()> {
+    test_dropout(Dim2(37, 53), DropoutMask::new(0.3, 0x1234, 1))?;
+    test_dropout(Dim2(16, 200), DropoutMask::new(0.5, u64::MAX, 1 << 40))
+}
+
+#[test]
+fn test_dropout_identity() -> Result<()> {
+    test_dropout(Dim2(5, 7), DropoutMask::identity())
+}
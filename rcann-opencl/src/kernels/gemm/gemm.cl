@@ -1,21 +1,31 @@
+#define TILE_WIDTH (TILE_SIZE / VECTOR_WIDTH)
 
-__kernel void gemm(
+// Computes C = ALPHA * op(A) * op(B) + BETA * C, where op(A) is M x K and op(B) is K x N.
+// A transposed operand is stored as K x M or N x K, and is transposed while loading its tiles.
+inline void gemm_tiled(
         const uint M, const uint K, const uint N,
         const real ALPHA,
         const __global realX* A,
         const __global realX* B,
         const real BETA,
-        __global realX* C
+        __global realX* C,
+        __local realX* Asub,
+        __local realX* Bsub,
+        const bool TRANS_A,
+        const bool TRANS_B
 ) {
 
     const uint l_row = get_local_id(0); // (0..TILE_SIZE]
     const uint l_col = get_local_id(1); // (0..TILE_SIZE / WIDTH]
     const uint g_row = get_global_id(0); // row of C (0..M]
-    const uint g_col = TILE_SIZE / VECTOR_WIDTH * get_group_id(1) + l_col; // col of C (0..N]
+    const uint g_col = TILE_WIDTH * get_group_id(1) + l_col; // col of C (0..N]
 
-    // Local memory to fit a tile of TILE_SIZE*TILE_SIZE elements of A and B
-    __local realX Asub[TILE_SIZE][TILE_SIZE / VECTOR_WIDTH];
-    __local realX Bsub[TILE_SIZE][TILE_SIZE / VECTOR_WIDTH];
+    // Scalar views to load the vectors of transposed operands one element at a time
+    const __global real* A_s = (const __global real*) A;
+    const __global real* B_s = (const __global real*) B;
+    __local real* Asub_s = (__local real*) Asub;
+    __local real* Bsub_s = (__local real*) Bsub;
+    const uint l_idx = l_row * TILE_WIDTH + l_col;
 
     // Initialise the accumulation registers
     realX acc = (realX)(0.0);
@@ -24,21 +34,35 @@ __kernel void gemm(
     for (uint t = 0; t < num_tiles; t++) {
 
         const uint t_row = TILE_SIZE * t + l_row;
-        const uint t_col = TILE_SIZE / VECTOR_WIDTH * t + l_col;
+        const uint t_col = TILE_WIDTH * t + l_col;
 
         // Load one tile of A and B into local memory
-        Asub[l_row][l_col] = A[g_row * K / VECTOR_WIDTH + t_col];
-        Bsub[l_row][l_col] = B[t_row * N / VECTOR_WIDTH + g_col];
+        if (TRANS_A) {
+            #pragma unroll
+            for (uint w = 0; w < VECTOR_WIDTH; w++) {
+                Asub_s[l_idx * VECTOR_WIDTH + w] = A_s[(t_col * VECTOR_WIDTH + w) * M + g_row];
+            }
+        } else {
+            Asub[l_idx] = A[g_row * K / VECTOR_WIDTH + t_col];
+        }
+        if (TRANS_B) {
+            #pragma unroll
+            for (uint w = 0; w < VECTOR_WIDTH; w++) {
+                Bsub_s[l_idx * VECTOR_WIDTH + w] = B_s[(g_col * VECTOR_WIDTH + w) * K + t_row];
+            }
+        } else {
+            Bsub[l_idx] = B[t_row * N / VECTOR_WIDTH + g_col];
+        }
 
         // Synchronise to make sure the tile is loaded
         barrier(CLK_LOCAL_MEM_FENCE);
 
         #pragma unroll
-        for (uint k = 0; k < TILE_SIZE / VECTOR_WIDTH; k++) {
-            const realX vecA = Asub[l_row][k];
+        for (uint k = 0; k < TILE_WIDTH; k++) {
+            const realX vecA = Asub[l_row * TILE_WIDTH + k];
             #pragma unroll
             for (uint w = 0; w < VECTOR_WIDTH; w++) {
-                acc += VEC_IDX(vecA, w) * Bsub[k * VECTOR_WIDTH + w][l_col];
+                acc += VEC_IDX(vecA, w) * Bsub[(k * VECTOR_WIDTH + w) * TILE_WIDTH + l_col];
             }
         }
 
@@ -51,3 +75,56 @@ __kernel void gemm(
     const uint c_idx = g_row * N / VECTOR_WIDTH + g_col;
     C[c_idx] = ALPHA * acc + BETA * C[c_idx];
 }
+
+__kernel void gemm(
+        const uint M, const uint K, const uint N,
+        const real ALPHA,
+        const __global realX* A,
+        const __global realX* B,
+        const real BETA,
+        __global realX* C
+) {
+    // Local memory to fit a tile of TILE_SIZE*TILE_SIZE elements of A and B
+    __local realX Asub[TILE_SIZE * TILE_WIDTH];
+    __local realX Bsub[TILE_SIZE * TILE_WIDTH];
+    gemm_tiled(M, K, N, ALPHA, A, B, BETA, C, Asub, Bsub, false, false);
+}
+
+__kernel void gemm_tn(
+        const uint M, const uint K, const uint N,
+        const real ALPHA,
+        const __global realX* A,
+        const __global realX* B,
+        const real BETA,
+        __global realX* C
+) {
+    __local realX Asub[TILE_SIZE * TILE_WIDTH];
+    __local realX Bsub[TILE_SIZE * TILE_WIDTH];
+    gemm_tiled(M, K, N, ALPHA, A, B, BETA, C, Asub, Bsub, true, false);
+}
+
+__kernel void gemm_nt(
+        const uint M, const uint K, const uint N,
+        const real ALPHA,
+        const __global realX* A,
+        const __global realX* B,
+        const real BETA,
+        __global realX* C
+) {
+    __local realX Asub[TILE_SIZE * TILE_WIDTH];
+    __local realX Bsub[TILE_SIZE * TILE_WIDTH];
+    gemm_tiled(M, K, N, ALPHA, A, B, BETA, C, Asub, Bsub, false, true);
+}
+
+__kernel void gemm_tt(
+        const uint M, const uint K, const uint N,
+        const real ALPHA,
+        const __global realX* A,
+        const __global realX* B,
+        const real BETA,
+        __global realX* C
+) {
+    __local realX Asub[TILE_SIZE * TILE_WIDTH];
+    __local realX Bsub[TILE_SIZE * TILE_WIDTH];
+    gemm_tiled(M, K, N, ALPHA, A, B, BETA, C, Asub, Bsub, true, true);
+}
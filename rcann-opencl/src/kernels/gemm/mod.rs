@@ -8,6 +8,34 @@ use opencl3::kernel::{ExecuteKernel};
 use rcann::tensor::Dim2;
 use crate::kernels::BUFFER_BLOCK_SIZE;
 
+/// The shape of a product `C = op(A) * op(B)`, where `op(A)` is `m x k` and `op(B)` is `k x n`
+struct GemmDims {
+    m: usize,
+    k: usize,
+    n: usize,
+}
+
+/// derives the shape of the product from the buffers of the operands, where `a` and `b` are stored transposed
+/// if `ta` and `tb` are set, and checks that they match each other and the tiles
+fn gemm_dims<T: OclFloat>(
+    a: &OclTensor<T, Dim2>,
+    ta: bool,
+    b: &OclTensor<T, Dim2>,
+    tb: bool,
+    c: &OclTensor<T, Dim2>,
+    tile_size: usize,
+) -> GemmDims {
+    let a_dims = if ta { a.buffer_dims().transposed() } else { *a.buffer_dims() };
+    let b_dims = if tb { b.buffer_dims().transposed() } else { *b.buffer_dims() };
+    let (m, k, n) = (a_dims.rows(), a_dims.cols(), b_dims.cols());
+    assert_eq!(b_dims.rows(), k);
+    assert_eq!(c.buffer_dims(), &Dim2(m, n));
+    assert_eq!(m % tile_size, 0);
+    assert_eq!(n % tile_size, 0);
+    assert_eq!(k % tile_size, 0);
+    GemmDims { m, k, n }
+}
+
 #[allow(unused)]
 pub mod constants {
     pub const TILE_SIZE: usize = 16;
@@ -42,30 +70,99 @@ ocl_program! {
                 c: &mut OclTensor<T, Dim2>,
             ),
             pre = {
-                let m = a.buffer_dims().rows();
-                let k = a.buffer_dims().cols();
-                let n = b.buffer_dims().cols();
+                let dims = gemm_dims(a, false, b, false, c, *tile_size);
+            },
+            inputs = [a, b, c],
+            outputs = [c],
+            kernel_args = [
+                &(dims.m as u32),
+                &(dims.k as u32),
+                &(dims.n as u32),
+                &alpha,
+                a.buffer(),
+                b.buffer(),
+                &beta,
+                c.buffer(),
+            ],
+            global_dims = [dims.m, dims.n / *vec_width as usize],
+            local_dims = [*tile_size, *tile_size / *vec_width as usize],
+        },
+        gemm_tn {
+            call_params = (
+                alpha: T,
+                a: &OclTensor<T, Dim2>,
+                b: &OclTensor<T, Dim2>,
+                beta: T,
+                c: &mut OclTensor<T, Dim2>,
+            ),
+            pre = {
+                let dims = gemm_dims(a, true, b, false, c, *tile_size);
             },
-            validation = {
-                assert_eq!(b.buffer_dims().rows(), k);
-                assert_eq!(c.buffer_dims(), &Dim2(m, n));
-                assert_eq!(m % *tile_size, 0);
-                assert_eq!(n % *tile_size, 0);
-                assert_eq!(k % *tile_size, 0);
+            inputs = [a, b, c],
+            outputs = [c],
+            kernel_args = [
+                &(dims.m as u32),
+                &(dims.k as u32),
+                &(dims.n as u32),
+                &alpha,
+                a.buffer(),
+                b.buffer(),
+                &beta,
+                c.buffer(),
+            ],
+            global_dims = [dims.m, dims.n / *vec_width as usize],
+            local_dims = [*tile_size, *tile_size / *vec_width as usize],
+        },
+        gemm_nt {
+            call_params = (
+                alpha: T,
+                a: &OclTensor<T, Dim2>,
+                b: &OclTensor<T, Dim2>,
+                beta: T,
+                c: &mut OclTensor<T, Dim2>,
+            ),
+            pre = {
+                let dims = gemm_dims(a, false, b, true, c, *tile_size);
+            },
+            inputs = [a, b, c],
+            outputs = [c],
+            kernel_args = [
+                &(dims.m as u32),
+                &(dims.k as u32),
+                &(dims.n as u32),
+                &alpha,
+                a.buffer(),
+                b.buffer(),
+                &beta,
+                c.buffer(),
+            ],
+            global_dims = [dims.m, dims.n / *vec_width as usize],
+            local_dims = [*tile_size, *tile_size / *vec_width as usize],
+        },
+        gemm_tt {
+            call_params = (
+                alpha: T,
+                a: &OclTensor<T, Dim2>,
+                b: &OclTensor<T, Dim2>,
+                beta: T,
+                c: &mut OclTensor<T, Dim2>,
+            ),
+            pre = {
+                let dims = gemm_dims(a, true, b, true, c, *tile_size);
             },
             inputs = [a, b, c],
             outputs = [c],
             kernel_args = [
-                &(m as u32),
-                &(k as u32),
-                &(n as u32),
+                &(dims.m as u32),
+                &(dims.k as u32),
+                &(dims.n as u32),
                 &alpha,
                 a.buffer(),
                 b.buffer(),
                 &beta,
                 c.buffer(),
             ],
-            global_dims = [m, n / *vec_width as usize],
+            global_dims = [dims.m, dims.n / *vec_width as usize],
             local_dims = [*tile_size, *tile_size / *vec_width as usize],
         },
     },
@@ -43,7 +43,7 @@ macro_rules! impl_tests{
                 let b_ocl = OclTensor::from_native(&context, &queue, &b_native)?;
                 let mut c_ocl = OclTensor::zeroed(&context, &queue, Dim2(m, n))?;
 
-                kernel.gemm(&queue, 1.0, &a_ocl, &b_ocl, 0.0, &mut c_ocl);
+                kernel.gemm(&queue, 1.0, &a_ocl, &b_ocl, 0.0, &mut c_ocl)?;
 
                 let c_actual = c_ocl.as_native(&queue)?;
 
@@ -51,6 +51,55 @@ macro_rules! impl_tests{
 
                 Ok(())
             }
+
+            #[test]
+            fn test_gemm_transposed() -> Result<()> {
+                let cpu_backend = CpuBackend::<$ty>::new(0);
+
+                let TestContext { device, context, queue } = create_test_context()?;
+                let kernel = GeMMProgram::<$ty>::create(&context, VecWidth::FOUR, 8)?;
+
+                let mut rng = StdRng::seed_from_u64(0x1234567);
+
+                let m = 120;
+                let k = 40;
+                let n = 50;
+
+                for (ta, tb) in [(true, false), (false, true), (true, true)] {
+                    let dim_a = if ta { Dim2(k, m) } else { Dim2(m, k) };
+                    let dim_b = if tb { Dim2(n, k) } else { Dim2(k, n) };
+                    let a_native = Tensor::from_distribution(&mut rng, StandardNormal, dim_a);
+                    let b_native = Tensor::from_distribution(&mut rng, StandardNormal, dim_b);
+                    let c_native = Tensor::from_distribution(&mut rng, StandardNormal, Dim2(m, n));
+                    let mut c_expected = c_native.clone();
+
+                    cpu_backend.matmul(
+                        0.5,
+                        a_native.view(),
+                        ta,
+                        b_native.view(),
+                        tb,
+                        0.25,
+                        &mut c_expected,
+                    );
+
+                    let a_ocl = OclTensor::from_native(&context, &queue, &a_native)?;
+                    let b_ocl = OclTensor::from_native(&context, &queue, &b_native)?;
+                    let mut c_ocl = OclTensor::from_native(&context, &queue, &c_native)?;
+
+                    match (ta, tb) {
+                        (true, false) => kernel.gemm_tn(&queue, 0.5, &a_ocl, &b_ocl, 0.25, &mut c_ocl)?,
+                        (false, true) => kernel.gemm_nt(&queue, 0.5, &a_ocl, &b_ocl, 0.25, &mut c_ocl)?,
+                        _ => kernel.gemm_tt(&queue, 0.5, &a_ocl, &b_ocl, 0.25, &mut c_ocl)?,
+                    }
+
+                    let c_actual = c_ocl.as_native(&queue)?;
+
+                    assert_abs_diff_eq!(c_expected, c_actual, epsilon = 0.001);
+                }
+
+                Ok(())
+            }
         }
     };
 }
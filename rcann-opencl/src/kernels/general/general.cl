@@ -22,6 +22,183 @@ __kernel void sigmoid_error(
     }
 }
 
+__kernel void relu(
+        const real leak,
+        const __global realX* activation,
+        __global realX* output
+) {
+    const uint offset = get_global_id(0) * VECTOR_PER_THREAD;
+    #pragma unroll
+    for (uint k = 0; k < VECTOR_PER_THREAD; k++) {
+        const realX act = activation[offset + k];
+        // step(0, x) is 0 for x < 0 and 1 otherwise
+        output[offset + k] = act * (leak + ((real)(1.0) - leak) * step((realX)(0.0), act));
+    }
+}
+
+__kernel void relu_error(
+        const real leak,
+        const __global realX* activation,
+        const __global realX* error,
+        __global realX* result
+) {
+    const uint offset = get_global_id(0) * VECTOR_PER_THREAD;
+    #pragma unroll
+    for (uint k = 0; k < VECTOR_PER_THREAD; k++) {
+        const realX act = activation[offset + k];
+        result[offset + k] = error[offset + k] * (leak + ((real)(1.0) - leak) * step((realX)(0.0), act));
+    }
+}
+
+// named to avoid clashing with the builtin tanh
+__kernel void tanh_activation(
+        const __global realX* activation,
+        __global realX* output
+) {
+    const uint offset = get_global_id(0) * VECTOR_PER_THREAD;
+    #pragma unroll
+    for (uint k = 0; k < VECTOR_PER_THREAD; k++) {
+        output[offset + k] = tanh(activation[offset + k]);
+    }
+}
+
+__kernel void tanh_activation_error(
+        const __global realX* output,
+        const __global realX* error,
+        __global realX* result
+) {
+    const uint offset = get_global_id(0) * VECTOR_PER_THREAD;
+    #pragma unroll
+    for (uint k = 0; k < VECTOR_PER_THREAD; k++) {
+        const realX out = output[offset + k];
+        result[offset + k] = error[offset + k] * ((realX)(1.0) - out * out);
+    }
+}
+
+__kernel void elu(
+        const real alpha,
+        const real scale,
+        const __global realX* activation,
+        __global realX* output
+) {
+    const uint offset = get_global_id(0) * VECTOR_PER_THREAD;
+    #pragma unroll
+    for (uint k = 0; k < VECTOR_PER_THREAD; k++) {
+        const realX act = activation[offset + k];
+        const realX pos = step((realX)(0.0), act);
+        // clamped so that exp can't overflow for the positive values it doesn't apply to
+        const realX neg = alpha * expm1(fmin(act, (realX)(0.0)));
+        output[offset + k] = scale * (pos * act + ((real)(1.0) - pos) * neg);
+    }
+}
+
+__kernel void elu_error(
+        const real alpha,
+        const real scale,
+        const __global realX* activation,
+        const __global realX* error,
+        __global realX* result
+) {
+    const uint offset = get_global_id(0) * VECTOR_PER_THREAD;
+    #pragma unroll
+    for (uint k = 0; k < VECTOR_PER_THREAD; k++) {
+        const realX act = activation[offset + k];
+        const realX pos = step((realX)(0.0), act);
+        const realX neg = alpha * exp(fmin(act, (realX)(0.0)));
+        result[offset + k] = error[offset + k] * scale * (pos + ((real)(1.0) - pos) * neg);
+    }
+}
+
+__kernel void gelu(
+        const __global realX* activation,
+        __global realX* output
+) {
+    const uint offset = get_global_id(0) * VECTOR_PER_THREAD;
+    #pragma unroll
+    for (uint k = 0; k < VECTOR_PER_THREAD; k++) {
+        const realX act = activation[offset + k];
+        const realX t = tanh((real)(GELU_C) * (act + (real)(GELU_K) * act * act * act));
+        output[offset + k] = (real)(0.5) * act * ((real)(1.0) + t);
+    }
+}
+
+__kernel void gelu_error(
+        const __global realX* activation,
+        const __global realX* error,
+        __global realX* result
+) {
+    const uint offset = get_global_id(0) * VECTOR_PER_THREAD;
+    #pragma unroll
+    for (uint k = 0; k < VECTOR_PER_THREAD; k++) {
+        const realX act = activation[offset + k];
+        const realX t = tanh((real)(GELU_C) * (act + (real)(GELU_K) * act * act * act));
+        const realX dt = ((real)(1.0) - t * t) * (real)(GELU_C) * ((real)(1.0) + (real)(3.0 * GELU_K) * act * act);
+        result[offset + k] = error[offset + k] * (real)(0.5) * ((real)(1.0) + t + act * dt);
+    }
+}
+
+__kernel void swish(
+        const __global realX* activation,
+        __global realX* output
+) {
+    const uint offset = get_global_id(0) * VECTOR_PER_THREAD;
+    #pragma unroll
+    for (uint k = 0; k < VECTOR_PER_THREAD; k++) {
+        const realX act = activation[offset + k];
+        output[offset + k] = act / ((realX)(1.0) + exp(-act));
+    }
+}
+
+__kernel void swish_error(
+        const __global realX* activation,
+        const __global realX* error,
+        __global realX* result
+) {
+    const uint offset = get_global_id(0) * VECTOR_PER_THREAD;
+    #pragma unroll
+    for (uint k = 0; k < VECTOR_PER_THREAD; k++) {
+        const realX act = activation[offset + k];
+        const realX s = (realX)(1.0) / ((realX)(1.0) + exp(-act));
+        result[offset + k] = error[offset + k] * s * ((realX)(1.0) + act * ((realX)(1.0) - s));
+    }
+}
+
+__kernel void softplus(
+        const __global realX* activation,
+        __global realX* output
+) {
+    const uint offset = get_global_id(0) * VECTOR_PER_THREAD;
+    #pragma unroll
+    for (uint k = 0; k < VECTOR_PER_THREAD; k++) {
+        const realX act = activation[offset + k];
+        // rearranged to avoid overflowing exp for large inputs
+        output[offset + k] = fmax(act, (realX)(0.0)) + log1p(exp(-fabs(act)));
+    }
+}
+
+__kernel void softplus_error(
+        const __global realX* activation,
+        const __global realX* error,
+        __global realX* result
+) {
+    const uint offset = get_global_id(0) * VECTOR_PER_THREAD;
+    #pragma unroll
+    for (uint k = 0; k < VECTOR_PER_THREAD; k++) {
+        result[offset + k] = error[offset + k] / ((realX)(1.0) + exp(-activation[offset + k]));
+    }
+}
+
+__kernel void identity(
+        const __global realX* activation,
+        __global realX* output
+) {
+    const uint offset = get_global_id(0) * VECTOR_PER_THREAD;
+    #pragma unroll
+    for (uint k = 0; k < VECTOR_PER_THREAD; k++) {
+        output[offset + k] = activation[offset + k];
+    }
+}
+
 __kernel void add_assign(
         const real alpha,
         const real beta,
@@ -35,6 +212,48 @@ __kernel void add_assign(
     }
 }
 
+__kernel void adaptive_update(
+        const real learn_rate,
+        const real decay,
+        const real scale,
+        const real epsilon,
+        const __global realX* grad,
+        __global realX* accum,
+        __global realX* params
+) {
+    const uint offset = get_global_id(0) * VECTOR_PER_THREAD;
+    #pragma unroll
+    for (uint k = 0; k < VECTOR_PER_THREAD; k++) {
+        const realX g = grad[offset + k];
+        const realX a = decay * accum[offset + k] + scale * g * g;
+        accum[offset + k] = a;
+        params[offset + k] -= learn_rate * g / (sqrt(a) + epsilon);
+    }
+}
+
+__kernel void adam_update(
+        const real step_size,
+        const real beta1,
+        const real beta2,
+        const real epsilon,
+        const real decay,
+        const __global realX* grad,
+        __global realX* m,
+        __global realX* v,
+        __global realX* params
+) {
+    const uint offset = get_global_id(0) * VECTOR_PER_THREAD;
+    #pragma unroll
+    for (uint k = 0; k < VECTOR_PER_THREAD; k++) {
+        const realX g = grad[offset + k];
+        const realX mk = beta1 * m[offset + k] + ((real)(1.0) - beta1) * g;
+        const realX vk = beta2 * v[offset + k] + ((real)(1.0) - beta2) * g * g;
+        m[offset + k] = mk;
+        v[offset + k] = vk;
+        params[offset + k] = ((real)(1.0) - decay) * params[offset + k] - step_size * mk / (sqrt(vk) + epsilon);
+    }
+}
+
 __kernel void column_sum(
         const uint ROWS,
         const uint COLS,
@@ -51,3 +270,16 @@ __kernel void column_sum(
     }
     output[col] = alpha * sum + beta * output[col];
 }
+
+__kernel void add_assign_row_broadcast(
+        const uint VEC_ROW_STRIDE,
+        const real alpha,
+        const real beta,
+        const __global realX* input,
+        __global realX* output
+) {
+    const uint row = get_global_id(0);
+    const uint col = get_global_id(1);
+    const uint idx = row * VEC_ROW_STRIDE + col;
+    output[idx] = alpha * input[col] + beta * output[idx];
+}
@@ -5,6 +5,7 @@ use crate::kernels::BUFFER_BLOCK_SIZE;
 use crate::tensor::event_list::EventList;
 use crate::tensor::{OclFloat, OclTensor, OclTensor1, OclTensor2};
 use crate::util::{ocl_program, VecWidth};
+use rcann::activation::{GELU_C, GELU_K};
 use opencl3::kernel::ExecuteKernel;
 use rcann::tensor::{Dims, ITensor};
 
@@ -23,6 +24,8 @@ ocl_program! {
         FLOAT_BITS = T::BITS,
         VECTOR_WIDTH = *vec_width,
         VECTOR_PER_THREAD = *vec_per_thread,
+        GELU_C = GELU_C,
+        GELU_K = GELU_K,
     },
     kernels = {
         sigmoid {
@@ -70,6 +73,309 @@ ocl_program! {
             ],
             global_dims = [n / unit_width],
         },
+        relu {
+            call_params = (
+                leak: T,
+                activation: &OclTensor2<T>,
+                output: &mut OclTensor2<T>,
+            ),
+            pre = {
+                let unit_width = *vec_width as usize * *vec_per_thread;
+                let n = activation.buffer_len();
+            },
+            validation = {
+                assert_eq!(activation.buffer_dims(), output.buffer_dims());
+                assert_eq!(n % unit_width, 0);
+            },
+            inputs = [activation],
+            outputs = [output],
+            kernel_args = [
+                &leak,
+                activation.buffer(),
+                output.buffer(),
+            ],
+            global_dims = [n / unit_width],
+        },
+        relu_error {
+            call_params = (
+                leak: T,
+                activation: &OclTensor2<T>,
+                error: &OclTensor2<T>,
+                result: &mut OclTensor2<T>,
+            ),
+            pre = {
+                let unit_width = *vec_width as usize * *vec_per_thread;
+                let n = activation.buffer_len();
+            },
+            validation = {
+                assert_eq!(activation.buffer_dims(), error.buffer_dims());
+                assert_eq!(activation.buffer_dims(), result.buffer_dims());
+                assert_eq!(n % unit_width, 0);
+            },
+            inputs = [activation, error],
+            outputs = [result],
+            kernel_args = [
+                &leak,
+                activation.buffer(),
+                error.buffer(),
+                result.buffer(),
+            ],
+            global_dims = [n / unit_width],
+        },
+        tanh_activation {
+            call_params = (
+                activation: &OclTensor2<T>,
+                output: &mut OclTensor2<T>,
+            ),
+            pre = {
+                let unit_width = *vec_width as usize * *vec_per_thread;
+                let n = activation.buffer_len();
+            },
+            validation = {
+                assert_eq!(activation.buffer_dims(), output.buffer_dims());
+                assert_eq!(n % unit_width, 0);
+            },
+            inputs = [activation],
+            outputs = [output],
+            kernel_args = [
+                activation.buffer(),
+                output.buffer(),
+            ],
+            global_dims = [n / unit_width],
+        },
+        tanh_activation_error {
+            call_params = (
+                output: &OclTensor2<T>,
+                error: &OclTensor2<T>,
+                result: &mut OclTensor2<T>,
+            ),
+            pre = {
+                let unit_width = *vec_width as usize * *vec_per_thread;
+                let n = output.buffer_len();
+            },
+            validation = {
+                assert_eq!(output.buffer_dims(), error.buffer_dims());
+                assert_eq!(output.buffer_dims(), result.buffer_dims());
+                assert_eq!(n % unit_width, 0);
+            },
+            inputs = [output, error],
+            outputs = [result],
+            kernel_args = [
+                output.buffer(),
+                error.buffer(),
+                result.buffer(),
+            ],
+            global_dims = [n / unit_width],
+        },
+        elu {
+            call_params = (
+                alpha: T,
+                scale: T,
+                activation: &OclTensor2<T>,
+                output: &mut OclTensor2<T>,
+            ),
+            pre = {
+                let unit_width = *vec_width as usize * *vec_per_thread;
+                let n = activation.buffer_len();
+            },
+            validation = {
+                assert_eq!(activation.buffer_dims(), output.buffer_dims());
+                assert_eq!(n % unit_width, 0);
+            },
+            inputs = [activation],
+            outputs = [output],
+            kernel_args = [
+                &alpha,
+                &scale,
+                activation.buffer(),
+                output.buffer(),
+            ],
+            global_dims = [n / unit_width],
+        },
+        elu_error {
+            call_params = (
+                alpha: T,
+                scale: T,
+                activation: &OclTensor2<T>,
+                error: &OclTensor2<T>,
+                result: &mut OclTensor2<T>,
+            ),
+            pre = {
+                let unit_width = *vec_width as usize * *vec_per_thread;
+                let n = activation.buffer_len();
+            },
+            validation = {
+                assert_eq!(activation.buffer_dims(), error.buffer_dims());
+                assert_eq!(activation.buffer_dims(), result.buffer_dims());
+                assert_eq!(n % unit_width, 0);
+            },
+            inputs = [activation, error],
+            outputs = [result],
+            kernel_args = [
+                &alpha,
+                &scale,
+                activation.buffer(),
+                error.buffer(),
+                result.buffer(),
+            ],
+            global_dims = [n / unit_width],
+        },
+        gelu {
+            call_params = (
+                activation: &OclTensor2<T>,
+                output: &mut OclTensor2<T>,
+            ),
+            pre = {
+                let unit_width = *vec_width as usize * *vec_per_thread;
+                let n = activation.buffer_len();
+            },
+            validation = {
+                assert_eq!(activation.buffer_dims(), output.buffer_dims());
+                assert_eq!(n % unit_width, 0);
+            },
+            inputs = [activation],
+            outputs = [output],
+            kernel_args = [
+                activation.buffer(),
+                output.buffer(),
+            ],
+            global_dims = [n / unit_width],
+        },
+        gelu_error {
+            call_params = (
+                activation: &OclTensor2<T>,
+                error: &OclTensor2<T>,
+                result: &mut OclTensor2<T>,
+            ),
+            pre = {
+                let unit_width = *vec_width as usize * *vec_per_thread;
+                let n = activation.buffer_len();
+            },
+            validation = {
+                assert_eq!(activation.buffer_dims(), error.buffer_dims());
+                assert_eq!(activation.buffer_dims(), result.buffer_dims());
+                assert_eq!(n % unit_width, 0);
+            },
+            inputs = [activation, error],
+            outputs = [result],
+            kernel_args = [
+                activation.buffer(),
+                error.buffer(),
+                result.buffer(),
+            ],
+            global_dims = [n / unit_width],
+        },
+        swish {
+            call_params = (
+                activation: &OclTensor2<T>,
+                output: &mut OclTensor2<T>,
+            ),
+            pre = {
+                let unit_width = *vec_width as usize * *vec_per_thread;
+                let n = activation.buffer_len();
+            },
+            validation = {
+                assert_eq!(activation.buffer_dims(), output.buffer_dims());
+                assert_eq!(n % unit_width, 0);
+            },
+            inputs = [activation],
+            outputs = [output],
+            kernel_args = [
+                activation.buffer(),
+                output.buffer(),
+            ],
+            global_dims = [n / unit_width],
+        },
+        swish_error {
+            call_params = (
+                activation: &OclTensor2<T>,
+                error: &OclTensor2<T>,
+                result: &mut OclTensor2<T>,
+            ),
+            pre = {
+                let unit_width = *vec_width as usize * *vec_per_thread;
+                let n = activation.buffer_len();
+            },
+            validation = {
+                assert_eq!(activation.buffer_dims(), error.buffer_dims());
+                assert_eq!(activation.buffer_dims(), result.buffer_dims());
+                assert_eq!(n % unit_width, 0);
+            },
+            inputs = [activation, error],
+            outputs = [result],
+            kernel_args = [
+                activation.buffer(),
+                error.buffer(),
+                result.buffer(),
+            ],
+            global_dims = [n / unit_width],
+        },
+        softplus {
+            call_params = (
+                activation: &OclTensor2<T>,
+                output: &mut OclTensor2<T>,
+            ),
+            pre = {
+                let unit_width = *vec_width as usize * *vec_per_thread;
+                let n = activation.buffer_len();
+            },
+            validation = {
+                assert_eq!(activation.buffer_dims(), output.buffer_dims());
+                assert_eq!(n % unit_width, 0);
+            },
+            inputs = [activation],
+            outputs = [output],
+            kernel_args = [
+                activation.buffer(),
+                output.buffer(),
+            ],
+            global_dims = [n / unit_width],
+        },
+        softplus_error {
+            call_params = (
+                activation: &OclTensor2<T>,
+                error: &OclTensor2<T>,
+                result: &mut OclTensor2<T>,
+            ),
+            pre = {
+                let unit_width = *vec_width as usize * *vec_per_thread;
+                let n = activation.buffer_len();
+            },
+            validation = {
+                assert_eq!(activation.buffer_dims(), error.buffer_dims());
+                assert_eq!(activation.buffer_dims(), result.buffer_dims());
+                assert_eq!(n % unit_width, 0);
+            },
+            inputs = [activation, error],
+            outputs = [result],
+            kernel_args = [
+                activation.buffer(),
+                error.buffer(),
+                result.buffer(),
+            ],
+            global_dims = [n / unit_width],
+        },
+        identity {
+            call_params = (
+                activation: &OclTensor2<T>,
+                output: &mut OclTensor2<T>,
+            ),
+            pre = {
+                let unit_width = *vec_width as usize * *vec_per_thread;
+                let n = activation.buffer_len();
+            },
+            validation = {
+                assert_eq!(activation.buffer_dims(), output.buffer_dims());
+                assert_eq!(n % unit_width, 0);
+            },
+            inputs = [activation],
+            outputs = [output],
+            kernel_args = [
+                activation.buffer(),
+                output.buffer(),
+            ],
+            global_dims = [n / unit_width],
+        },
         add_assign {
             generic_args = <D: Dims>,
             call_params = (
@@ -96,6 +402,77 @@ ocl_program! {
             ],
             global_dims = [n / unit_width],
         },
+        adaptive_update {
+            generic_args = <D: Dims>,
+            call_params = (
+                learn_rate: T,
+                decay: T,
+                scale: T,
+                epsilon: T,
+                grad: &OclTensor<T, D>,
+                accum: &mut OclTensor<T, D>,
+                params: &mut OclTensor<T, D>,
+            ),
+            pre = {
+                let unit_width = *vec_width as usize * *vec_per_thread;
+                let n = grad.buffer_len();
+            },
+            validation = {
+                assert_eq!(grad.buffer_dims(), accum.buffer_dims());
+                assert_eq!(grad.buffer_dims(), params.buffer_dims());
+                assert_eq!(n % unit_width, 0);
+            },
+            inputs = [grad, accum, params],
+            outputs = [accum, params],
+            kernel_args = [
+                &learn_rate,
+                &decay,
+                &scale,
+                &epsilon,
+                grad.buffer(),
+                accum.buffer(),
+                params.buffer(),
+            ],
+            global_dims = [n / unit_width],
+        },
+        adam_update {
+            generic_args = <D: Dims>,
+            call_params = (
+                step_size: T,
+                beta1: T,
+                beta2: T,
+                epsilon: T,
+                decay: T,
+                grad: &OclTensor<T, D>,
+                m: &mut OclTensor<T, D>,
+                v: &mut OclTensor<T, D>,
+                params: &mut OclTensor<T, D>,
+            ),
+            pre = {
+                let unit_width = *vec_width as usize * *vec_per_thread;
+                let n = grad.buffer_len();
+            },
+            validation = {
+                assert_eq!(grad.buffer_dims(), m.buffer_dims());
+                assert_eq!(grad.buffer_dims(), v.buffer_dims());
+                assert_eq!(grad.buffer_dims(), params.buffer_dims());
+                assert_eq!(n % unit_width, 0);
+            },
+            inputs = [grad, m, v, params],
+            outputs = [m, v, params],
+            kernel_args = [
+                &step_size,
+                &beta1,
+                &beta2,
+                &epsilon,
+                &decay,
+                grad.buffer(),
+                m.buffer(),
+                v.buffer(),
+                params.buffer(),
+            ],
+            global_dims = [n / unit_width],
+        },
         column_sum {
             call_params = (
                 alpha: T,
@@ -125,5 +502,32 @@ ocl_program! {
             ],
             global_dims = [n / *vec_width as usize],
         },
+        add_assign_row_broadcast {
+            call_params = (
+                alpha: T,
+                input: &OclTensor1<T>,
+                beta: T,
+                output: &mut OclTensor2<T>,
+            ),
+            pre = {
+                let n = input.buffer_len();
+                let rows = output.buffer_dims().rows();
+            },
+            validation = {
+                assert_eq!(input.dims().major(), output.dims().cols());
+                assert_eq!(output.buffer_dims().cols(), n);
+                assert_eq!(n % *vec_width as usize, 0);
+            },
+            inputs = [input, output],
+            outputs = [output],
+            kernel_args = [
+                &((n / *vec_width as usize) as u32),
+                &alpha,
+                &beta,
+                input.buffer(),
+                output.buffer(),
+            ],
+            global_dims = [rows, n / *vec_width as usize],
+        },
     },
 }
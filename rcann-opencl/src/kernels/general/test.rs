@@ -12,7 +12,7 @@ macro_rules! impl_tests {
             use rand::SeedableRng;
             use rand_distr::StandardNormal;
             use rcann::backend::{BackendOther, CpuBackend};
-            use rcann::tensor::{Dim1, Dim2, ITensor, Tensor1, Tensor2};
+            use rcann::tensor::{Dim1, Dim2, ITensor, Tensor1, Tensor2, TensorBase};
 
             #[test]
             fn test_sigmoid() -> Result<()> {
@@ -23,11 +23,11 @@ macro_rules! impl_tests {
 
                 let input = Tensor2::from_distribution(&mut rng, StandardNormal, Dim2(30, 30));
                 let mut expected = Tensor2::zeroed(*input.dims());
-                cpu.sigmoid(&input, &mut expected);
+                cpu.sigmoid(input.view(), &mut expected);
 
                 let input_ocl = OclTensor2::from_native(&context, &queue, &input)?;
                 let mut output_ocl = OclTensor2::zeroed(&context, &queue, *input.dims())?;
-                kernel.sigmoid(&queue, &input_ocl, &mut output_ocl);
+                kernel.sigmoid(&queue, &input_ocl, &mut output_ocl)?;
                 let actual = output_ocl.as_native(&queue)?;
 
                 assert_abs_diff_eq!(expected, actual, epsilon = 0.001);
@@ -50,7 +50,51 @@ macro_rules! impl_tests {
                 let output_ocl = OclTensor2::from_native(&context, &queue, &output)?;
                 let error_ocl = OclTensor2::from_native(&context, &queue, &error)?;
                 let mut result_ocl = OclTensor2::zeroed(&context, &queue, *expected.dims())?;
-                kernel.sigmoid_error(&queue, &output_ocl, &error_ocl, &mut result_ocl);
+                kernel.sigmoid_error(&queue, &output_ocl, &error_ocl, &mut result_ocl)?;
+                let actual = result_ocl.as_native(&queue)?;
+
+                assert_abs_diff_eq!(expected, actual, epsilon = 0.001);
+
+                Ok(())
+            }
+
+            #[test]
+            fn test_relu() -> Result<()> {
+                let TestContext { device, context, queue } = util::create_test_context()?;
+                let kernel = GeneralProgram::<$ty>::create(&context, VecWidth::SIXTEEN, 1)?;
+                let cpu = CpuBackend::<$ty>::new(0);
+                let mut rng = StdRng::seed_from_u64(0x3827261);
+
+                let input = Tensor2::from_distribution(&mut rng, StandardNormal, Dim2(30, 30));
+                let mut expected = Tensor2::zeroed(*input.dims());
+                cpu.relu(0.1, input.view(), &mut expected);
+
+                let input_ocl = OclTensor2::from_native(&context, &queue, &input)?;
+                let mut output_ocl = OclTensor2::zeroed(&context, &queue, *input.dims())?;
+                kernel.relu(&queue, 0.1, &input_ocl, &mut output_ocl)?;
+                let actual = output_ocl.as_native(&queue)?;
+
+                assert_abs_diff_eq!(expected, actual, epsilon = 0.001);
+
+                Ok(())
+            }
+
+            #[test]
+            fn test_relu_error() -> Result<()> {
+                let TestContext { device, context, queue } = util::create_test_context()?;
+                let kernel = GeneralProgram::<$ty>::create(&context, VecWidth::SIXTEEN, 1)?;
+                let cpu = CpuBackend::<$ty>::new(0);
+                let mut rng = StdRng::seed_from_u64(0x3827261);
+
+                let activation = Tensor2::from_distribution(&mut rng, StandardNormal, Dim2(30, 30));
+                let error = Tensor2::from_distribution(&mut rng, StandardNormal, Dim2(30, 30));
+                let mut expected = Tensor2::zeroed(*activation.dims());
+                cpu.relu_error(0.1, activation.view(), &error, &mut expected);
+
+                let activation_ocl = OclTensor2::from_native(&context, &queue, &activation)?;
+                let error_ocl = OclTensor2::from_native(&context, &queue, &error)?;
+                let mut result_ocl = OclTensor2::zeroed(&context, &queue, *expected.dims())?;
+                kernel.relu_error(&queue, 0.1, &activation_ocl, &error_ocl, &mut result_ocl)?;
                 let actual = result_ocl.as_native(&queue)?;
 
                 assert_abs_diff_eq!(expected, actual, epsilon = 0.001);
@@ -72,7 +116,7 @@ macro_rules! impl_tests {
 
                 let input_ocl = OclTensor2::from_native(&context, &queue, &input)?;
                 let mut output_ocl = OclTensor2::from_native(&context, &queue, &output)?;
-                kernel.add_assign(&queue, 0.75, &input_ocl, 0.25, &mut output_ocl);
+                kernel.add_assign(&queue, 0.75, &input_ocl, 0.25, &mut output_ocl)?;
                 let actual = output_ocl.as_native(&queue)?;
 
                 assert_abs_diff_eq!(expected, actual, epsilon = 0.001);
@@ -80,6 +124,82 @@ macro_rules! impl_tests {
                 Ok(())
             }
 
+            #[test]
+            fn test_adaptive_update() -> Result<()> {
+                let TestContext { device, context, queue } = util::create_test_context()?;
+                let kernel = GeneralProgram::<$ty>::create(&context, VecWidth::SIXTEEN, 1)?;
+                let cpu = CpuBackend::<$ty>::new(0);
+                let mut rng = StdRng::seed_from_u64(0x3827261);
+
+                let grad = Tensor2::from_distribution(&mut rng, StandardNormal, Dim2(10, 10));
+                let accum = grad.iter().map(|g| g * g).collect::<Vec<_>>();
+                let accum = Tensor2::from_vec(accum, *grad.dims());
+                let params = Tensor2::from_distribution(&mut rng, StandardNormal, *grad.dims());
+                let mut expected_accum = accum.clone();
+                let mut expected_params = params.clone();
+                cpu.adaptive_update(0.01, 0.9, 0.1, 1e-6, &grad, &mut expected_accum, &mut expected_params);
+
+                let grad_ocl = OclTensor2::from_native(&context, &queue, &grad)?;
+                let mut accum_ocl = OclTensor2::from_native(&context, &queue, &accum)?;
+                let mut params_ocl = OclTensor2::from_native(&context, &queue, &params)?;
+                kernel.adaptive_update(&queue, 0.01, 0.9, 0.1, 1e-6, &grad_ocl, &mut accum_ocl, &mut params_ocl)?;
+
+                assert_abs_diff_eq!(expected_accum, accum_ocl.as_native(&queue)?, epsilon = 0.001);
+                assert_abs_diff_eq!(expected_params, params_ocl.as_native(&queue)?, epsilon = 0.001);
+
+                Ok(())
+            }
+
+            #[test]
+            fn test_adam_update() -> Result<()> {
+                let TestContext { device, context, queue } = util::create_test_context()?;
+                let kernel = GeneralProgram::<$ty>::create(&context, VecWidth::SIXTEEN, 1)?;
+                let cpu = CpuBackend::<$ty>::new(0);
+                let mut rng = StdRng::seed_from_u64(0x3827261);
+
+                let grad = Tensor1::from_distribution(&mut rng, StandardNormal, Dim1(30));
+                let m = Tensor1::from_distribution(&mut rng, StandardNormal, *grad.dims());
+                let v = Tensor1::from_vec(m.iter().map(|m| m * m).collect(), *grad.dims());
+                let params = Tensor1::from_distribution(&mut rng, StandardNormal, *grad.dims());
+                let mut expected_m = m.clone();
+                let mut expected_v = v.clone();
+                let mut expected_params = params.clone();
+                cpu.adam_update(
+                    0.01,
+                    0.9,
+                    0.999,
+                    1e-6,
+                    0.001,
+                    &grad,
+                    &mut expected_m,
+                    &mut expected_v,
+                    &mut expected_params,
+                );
+
+                let grad_ocl = OclTensor1::from_native(&context, &queue, &grad)?;
+                let mut m_ocl = OclTensor1::from_native(&context, &queue, &m)?;
+                let mut v_ocl = OclTensor1::from_native(&context, &queue, &v)?;
+                let mut params_ocl = OclTensor1::from_native(&context, &queue, &params)?;
+                kernel.adam_update(
+                    &queue,
+                    0.01,
+                    0.9,
+                    0.999,
+                    1e-6,
+                    0.001,
+                    &grad_ocl,
+                    &mut m_ocl,
+                    &mut v_ocl,
+                    &mut params_ocl,
+                )?;
+
+                assert_abs_diff_eq!(expected_m, m_ocl.as_native(&queue)?, epsilon = 0.001);
+                assert_abs_diff_eq!(expected_v, v_ocl.as_native(&queue)?, epsilon = 0.001);
+                assert_abs_diff_eq!(expected_params, params_ocl.as_native(&queue)?, epsilon = 0.001);
+
+                Ok(())
+            }
+
             #[test]
             fn test_column_sum() -> Result<()> {
                 let TestContext { device, context, queue } = util::create_test_context()?;
@@ -94,7 +214,29 @@ macro_rules! impl_tests {
 
                 let input_ocl = OclTensor2::from_native(&context, &queue, &input)?;
                 let mut output_ocl = OclTensor1::from_native(&context, &queue, &output)?;
-                kernel.column_sum(&queue, 0.75, &input_ocl, 0.25, &mut output_ocl);
+                kernel.column_sum(&queue, 0.75, &input_ocl, 0.25, &mut output_ocl)?;
+                let actual = output_ocl.as_native(&queue)?;
+
+                assert_abs_diff_eq!(expected, actual, epsilon = 0.001);
+
+                Ok(())
+            }
+
+            #[test]
+            fn test_add_assign_row_broadcast() -> Result<()> {
+                let TestContext { device, context, queue } = util::create_test_context()?;
+                let kernel = GeneralProgram::<$ty>::create(&context, VecWidth::SIXTEEN, 1)?;
+                let cpu = CpuBackend::<$ty>::new(0);
+                let mut rng = StdRng::seed_from_u64(0x3827261);
+
+                let input = Tensor1::from_distribution(&mut rng, StandardNormal, Dim1(30));
+                let output = Tensor2::from_distribution(&mut rng, StandardNormal, Dim2(20, 30));
+                let mut expected = output.clone();
+                cpu.add_assign_row_broadcast(0.75, &input, 0.25, &mut expected);
+
+                let input_ocl = OclTensor1::from_native(&context, &queue, &input)?;
+                let mut output_ocl = OclTensor2::from_native(&context, &queue, &output)?;
+                kernel.add_assign_row_broadcast(&queue, 0.75, &input_ocl, 0.25, &mut output_ocl)?;
                 let actual = output_ocl.as_native(&queue)?;
 
                 assert_abs_diff_eq!(expected, actual, epsilon = 0.001);
@@ -137,7 +279,7 @@ fn test_add_assign_f16() -> crate::util::Result<()> {
 
     let input_ocl = OclTensor2::from_native(&context, &queue, &input)?;
     let mut output_ocl = OclTensor2::from_native(&context, &queue, &output)?;
-    kernel.add_assign(&queue, f16::from_f32(0.75), &input_ocl, f16::from_f32(0.25), &mut output_ocl);
+    kernel.add_assign(&queue, f16::from_f32(0.75), &input_ocl, f16::from_f32(0.25), &mut output_ocl)?;
     let actual = output_ocl.as_native(&queue)?;
 
     //assert_abs_diff_eq!(expected, actual, epsilon = 0.001);
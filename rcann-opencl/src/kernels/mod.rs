@@ -1,6 +1,11 @@
+pub mod conv;
+pub mod cross_entropy;
+pub mod dropout;
 pub mod gemm;
 pub mod general;
 pub mod mse;
+pub mod norm;
+pub mod pool;
 pub mod scoring;
 pub mod softmax;
 pub mod transpose;
@@ -49,7 +49,7 @@ macro_rules! impl_tests {
                     &expected_vals_ocl,
                     &mut actual_result,
                     &mut actual_result_deriv,
-                );
+                )?;
 
                 assert_abs_diff_eq!(expected_result, actual_result.as_native(&queue)?, epsilon = 0.001);
                 assert_abs_diff_eq!(
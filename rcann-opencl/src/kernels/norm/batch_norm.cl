@@ -0,0 +1,91 @@
+// one work item per column
+__kernel void column_mean_var(
+        const uint ROWS,
+        const __global real* input,
+        __global real* mean,
+        __global real* var
+) {
+    const uint col = get_global_id(0);
+    if (col >= COLS) {
+        return;
+    }
+    real sum = (real)0.0;
+    for (uint row = 0; row < ROWS; row++) {
+        sum += input[row * ROW_STRIDE + col];
+    }
+    const real m = sum / (real)ROWS;
+    real sq_sum = (real)0.0;
+    for (uint row = 0; row < ROWS; row++) {
+        const real d = input[row * ROW_STRIDE + col] - m;
+        sq_sum += d * d;
+    }
+    mean[col] = m;
+    var[col] = sq_sum / (real)ROWS;
+}
+
+__kernel void batch_norm(
+        const uint ROWS,
+        const real epsilon,
+        const __global real* input,
+        const __global real* mean,
+        const __global real* var,
+        const __global real* gamma,
+        const __global real* beta,
+        __global real* normalized,
+        __global real* output
+) {
+    const uint row = get_global_id(0);
+    const uint col = get_global_id(1);
+    if (row >= ROWS || col >= COLS) {
+        return;
+    }
+    const uint idx = row * ROW_STRIDE + col;
+    const real x_hat = (input[idx] - mean[col]) * rsqrt(var[col] + epsilon);
+    normalized[idx] = x_hat;
+    output[idx] = gamma[col] * x_hat + beta[col];
+}
+
+// one work item per column
+__kernel void batch_norm_param_grad(
+        const uint ROWS,
+        const __global real* normalized,
+        const __global real* out_error,
+        __global real* gamma_grad,
+        __global real* beta_grad
+) {
+    const uint col = get_global_id(0);
+    if (col >= COLS) {
+        return;
+    }
+    real gamma_sum = (real)0.0;
+    real beta_sum = (real)0.0;
+    for (uint row = 0; row < ROWS; row++) {
+        const uint idx = row * ROW_STRIDE + col;
+        gamma_sum += out_error[idx] * normalized[idx];
+        beta_sum += out_error[idx];
+    }
+    gamma_grad[col] = gamma_sum;
+    beta_grad[col] = beta_sum;
+}
+
+__kernel void batch_norm_input_error(
+        const uint ROWS,
+        const real epsilon,
+        const __global real* normalized,
+        const __global real* out_error,
+        const __global real* var,
+        const __global real* gamma,
+        const __global real* gamma_grad,
+        const __global real* beta_grad,
+        __global real* input_error
+) {
+    const uint row = get_global_id(0);
+    const uint col = get_global_id(1);
+    if (row >= ROWS || col >= COLS) {
+        return;
+    }
+    const uint idx = row * ROW_STRIDE + col;
+    const real scale = gamma[col] * rsqrt(var[col] + epsilon);
+    const real n = (real)ROWS;
+    input_error[idx] = scale * (out_error[idx] - (beta_grad[col] + normalized[idx] * gamma_grad[col]) / n);
+}
@@ -0,0 +1,78 @@
+// one work item per row, like softmax
+__kernel void row_mean_var(
+        const uint ROWS,
+        const __global real* input,
+        __global real* mean,
+        __global real* var
+) {
+    const uint row = get_global_id(0);
+    if (row >= ROWS) {
+        return;
+    }
+    const __global real* in_row = input + row * ROW_STRIDE;
+    real sum = (real)0.0;
+    for (uint i = 0; i < COLS; i++) {
+        sum += in_row[i];
+    }
+    const real m = sum / (real)COLS;
+    real sq_sum = (real)0.0;
+    for (uint i = 0; i < COLS; i++) {
+        const real d = in_row[i] - m;
+        sq_sum += d * d;
+    }
+    mean[row] = m;
+    var[row] = sq_sum / (real)COLS;
+}
+
+__kernel void layer_norm(
+        const uint ROWS,
+        const real epsilon,
+        const __global real* input,
+        const __global real* mean,
+        const __global real* var,
+        const __global real* gain,
+        const __global real* bias,
+        __global real* normalized,
+        __global real* output
+) {
+    const uint row = get_global_id(0);
+    const uint col = get_global_id(1);
+    if (row >= ROWS || col >= COLS) {
+        return;
+    }
+    const uint idx = row * ROW_STRIDE + col;
+    const real x_hat = (input[idx] - mean[row]) * rsqrt(var[row] + epsilon);
+    normalized[idx] = x_hat;
+    output[idx] = gain[col] * x_hat + bias[col];
+}
+
+// one work item per row
+__kernel void layer_norm_input_error(
+        const uint ROWS,
+        const real epsilon,
+        const __global real* normalized,
+        const __global real* out_error,
+        const __global real* var,
+        const __global real* gain,
+        __global real* input_error
+) {
+    const uint row = get_global_id(0);
+    if (row >= ROWS) {
+        return;
+    }
+    const uint offset = row * ROW_STRIDE;
+    // the error of the normalized values is out_error * gain
+    real err_sum = (real)0.0;
+    real err_dot = (real)0.0;
+    for (uint i = 0; i < COLS; i++) {
+        const real err = out_error[offset + i] * gain[i];
+        err_sum += err;
+        err_dot += err * normalized[offset + i];
+    }
+    const real inv_std = rsqrt(var[row] + epsilon);
+    const real n = (real)COLS;
+    for (uint i = 0; i < COLS; i++) {
+        const real err = out_error[offset + i] * gain[i];
+        input_error[offset + i] = inv_std * (err - (err_sum + normalized[offset + i] * err_dot) / n);
+    }
+}
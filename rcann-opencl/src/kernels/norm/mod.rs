@@ -0,0 +1,255 @@
+#[cfg(test)]
+mod test;
+
+use crate::tensor::{OclFloat, OclTensor1, OclTensor2};
+use crate::util::*;
+
+ocl_program! {
+    name = BatchNormProgram,
+    source = "batch_norm.cl",
+    generic_args = <T: OclFloat>,
+    compile_params = (
+        cols: usize,
+        row_stride: usize,
+    ),
+    validation = {
+        validate!(*cols > 0, "cols must be positive");
+        validate!(*row_stride >= *cols, "Row stride too small");
+    },
+    defines = {
+        FLOAT_BITS = T::BITS,
+        COLS = *cols,
+        ROW_STRIDE = *row_stride,
+    },
+    kernels = {
+        column_mean_var {
+            call_params = (
+                input: &OclTensor2<T>,
+                mean: &mut OclTensor1<T>,
+                var: &mut OclTensor1<T>,
+            ),
+            validation = {
+                assert_eq!(input.buffer_dims().cols(), *row_stride);
+                assert_eq!(mean.dims(), &Dim1(*cols));
+                assert_eq!(var.dims(), &Dim1(*cols));
+            },
+            inputs = [input],
+            outputs = [mean, var],
+            kernel_args = [
+                &(input.dims().rows() as u32),
+                input.buffer(),
+                mean.buffer(),
+                var.buffer(),
+            ],
+            global_dims = [*row_stride],
+        },
+        batch_norm {
+            call_params = (
+                epsilon: T,
+                input: &OclTensor2<T>,
+                mean: &OclTensor1<T>,
+                var: &OclTensor1<T>,
+                gamma: &OclTensor1<T>,
+                beta: &OclTensor1<T>,
+                normalized: &mut OclTensor2<T>,
+                output: &mut OclTensor2<T>,
+            ),
+            validation = {
+                assert_eq!(input.dims(), normalized.dims());
+                assert_eq!(input.dims(), output.dims());
+                assert_eq!(input.buffer_dims().cols(), *row_stride);
+                assert_eq!(normalized.buffer_dims().cols(), *row_stride);
+                assert_eq!(output.buffer_dims().cols(), *row_stride);
+                for t in [mean, var, gamma, beta] {
+                    assert_eq!(t.dims(), &Dim1(*cols));
+                }
+            },
+            inputs = [input, mean, var, gamma, beta],
+            outputs = [normalized, output],
+            kernel_args = [
+                &(input.dims().rows() as u32),
+                &epsilon,
+                input.buffer(),
+                mean.buffer(),
+                var.buffer(),
+                gamma.buffer(),
+                beta.buffer(),
+                normalized.buffer(),
+                output.buffer(),
+            ],
+            global_dims = [next_multiple(input.dims().rows(), 16), *row_stride],
+        },
+        batch_norm_param_grad {
+            call_params = (
+                normalized: &OclTensor2<T>,
+                out_error: &OclTensor2<T>,
+                gamma_grad: &mut OclTensor1<T>,
+                beta_grad: &mut OclTensor1<T>,
+            ),
+            validation = {
+                assert_eq!(normalized.dims(), out_error.dims());
+                assert_eq!(normalized.buffer_dims().cols(), *row_stride);
+                assert_eq!(out_error.buffer_dims().cols(), *row_stride);
+                assert_eq!(gamma_grad.dims(), &Dim1(*cols));
+                assert_eq!(beta_grad.dims(), &Dim1(*cols));
+            },
+            inputs = [normalized, out_error],
+            outputs = [gamma_grad, beta_grad],
+            kernel_args = [
+                &(normalized.dims().rows() as u32),
+                normalized.buffer(),
+                out_error.buffer(),
+                gamma_grad.buffer(),
+                beta_grad.buffer(),
+            ],
+            global_dims = [*row_stride],
+        },
+        batch_norm_input_error {
+            call_params = (
+                epsilon: T,
+                normalized: &OclTensor2<T>,
+                out_error: &OclTensor2<T>,
+                var: &OclTensor1<T>,
+                gamma: &OclTensor1<T>,
+                gamma_grad: &OclTensor1<T>,
+                beta_grad: &OclTensor1<T>,
+                input_error: &mut OclTensor2<T>,
+            ),
+            validation = {
+                assert_eq!(normalized.dims(), out_error.dims());
+                assert_eq!(normalized.dims(), input_error.dims());
+                assert_eq!(normalized.buffer_dims().cols(), *row_stride);
+                assert_eq!(out_error.buffer_dims().cols(), *row_stride);
+                assert_eq!(input_error.buffer_dims().cols(), *row_stride);
+                for t in [var, gamma, gamma_grad, beta_grad] {
+                    assert_eq!(t.dims(), &Dim1(*cols));
+                }
+            },
+            inputs = [normalized, out_error, var, gamma, gamma_grad, beta_grad],
+            outputs = [input_error],
+            kernel_args = [
+                &(normalized.dims().rows() as u32),
+                &epsilon,
+                normalized.buffer(),
+                out_error.buffer(),
+                var.buffer(),
+                gamma.buffer(),
+                gamma_grad.buffer(),
+                beta_grad.buffer(),
+                input_error.buffer(),
+            ],
+            global_dims = [next_multiple(normalized.dims().rows(), 16), *row_stride],
+        },
+    },
+}
+
+ocl_program! {
+    name = LayerNormProgram,
+    source = "layer_norm.cl",
+    generic_args = <T: OclFloat>,
+    compile_params = (
+        cols: usize,
+        row_stride: usize,
+    ),
+    validation = {
+        validate!(*cols > 0, "cols must be positive");
+        validate!(*row_stride >= *cols, "Row stride too small");
+    },
+    defines = {
+        FLOAT_BITS = T::BITS,
+        COLS = *cols,
+        ROW_STRIDE = *row_stride,
+    },
+    kernels = {
+        row_mean_var {
+            call_params = (
+                input: &OclTensor2<T>,
+                mean: &mut OclTensor1<T>,
+                var: &mut OclTensor1<T>,
+            ),
+            validation = {
+                assert_eq!(input.dims().cols(), *cols);
+                assert_eq!(input.buffer_dims().cols(), *row_stride);
+                assert_eq!(mean.dims(), &Dim1(input.dims().rows()));
+                assert_eq!(var.dims(), &Dim1(input.dims().rows()));
+            },
+            inputs = [input],
+            outputs = [mean, var],
+            kernel_args = [
+                &(input.dims().rows() as u32),
+                input.buffer(),
+                mean.buffer(),
+                var.buffer(),
+            ],
+            global_dims = [next_multiple(input.dims().rows(), 16)],
+        },
+        layer_norm {
+            call_params = (
+                epsilon: T,
+                input: &OclTensor2<T>,
+                mean: &OclTensor1<T>,
+                var: &OclTensor1<T>,
+                gain: &OclTensor1<T>,
+                bias: &OclTensor1<T>,
+                normalized: &mut OclTensor2<T>,
+                output: &mut OclTensor2<T>,
+            ),
+            validation = {
+                assert_eq!(input.dims(), normalized.dims());
+                assert_eq!(input.dims(), output.dims());
+                assert_eq!(input.buffer_dims().cols(), *row_stride);
+                assert_eq!(normalized.buffer_dims().cols(), *row_stride);
+                assert_eq!(output.buffer_dims().cols(), *row_stride);
+                assert_eq!(mean.dims(), &Dim1(input.dims().rows()));
+                assert_eq!(var.dims(), &Dim1(input.dims().rows()));
+                assert_eq!(gain.dims(), &Dim1(*cols));
+                assert_eq!(bias.dims(), &Dim1(*cols));
+            },
+            inputs = [input, mean, var, gain, bias],
+            outputs = [normalized, output],
+            kernel_args = [
+                &(input.dims().rows() as u32),
+                &epsilon,
+                input.buffer(),
+                mean.buffer(),
+                var.buffer(),
+                gain.buffer(),
+                bias.buffer(),
+                normalized.buffer(),
+                output.buffer(),
+            ],
+            global_dims = [next_multiple(input.dims().rows(), 16), *row_stride],
+        },
+        layer_norm_input_error {
+            call_params = (
+                epsilon: T,
+                normalized: &OclTensor2<T>,
+                out_error: &OclTensor2<T>,
+                var: &OclTensor1<T>,
+                gain: &OclTensor1<T>,
+                input_error: &mut OclTensor2<T>,
+            ),
+            validation = {
+                assert_eq!(normalized.dims(), out_error.dims());
+                assert_eq!(normalized.dims(), input_error.dims());
+                assert_eq!(normalized.buffer_dims().cols(), *row_stride);
+                assert_eq!(out_error.buffer_dims().cols(), *row_stride);
+                assert_eq!(input_error.buffer_dims().cols(), *row_stride);
+                assert_eq!(var.dims(), &Dim1(normalized.dims().rows()));
+                assert_eq!(gain.dims(), &Dim1(*cols));
+            },
+            inputs = [normalized, out_error, var, gain],
+            outputs = [input_error],
+            kernel_args = [
+                &(normalized.dims().rows() as u32),
+                &epsilon,
+                normalized.buffer(),
+                out_error.buffer(),
+                var.buffer(),
+                gain.buffer(),
+                input_error.buffer(),
+            ],
+            global_dims = [next_multiple(normalized.dims().rows(), 16)],
+        },
+    },
+}
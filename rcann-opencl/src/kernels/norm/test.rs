@@ -0,0 +1,169 @@
+use super::{BatchNormProgram, LayerNormProgram};
+use crate::tensor::{OclTensor1, OclTensor2};
+use crate::util::*;
+use approx::assert_abs_diff_eq;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rand_distr::StandardNormal;
+use rcann::backend::{BackendOther, CpuBackend};
+use rcann::tensor::{Dim1, Dim2, Tensor1, Tensor2, TensorBase};
+
+#[test]
+fn test_batch_norm() -> Result<()> {
+    let TestContext { context, queue, .. } = create_test_context()?;
+    let cpu = CpuBackend::<f32>::new(0);
+    let mut rng = StdRng::seed_from_u64(0xb47c);
+    let dims = Dim2(23, 37);
+    let epsilon = 1e-5;
+
+    let input = Tensor2::from_distribution(&mut rng, StandardNormal, dims);
+    let gamma = Tensor1::from_distribution(&mut rng, StandardNormal, Dim1(dims.cols()));
+    let beta = Tensor1::from_distribution(&mut rng, StandardNormal, Dim1(dims.cols()));
+    let out_error = Tensor2::from_distribution(&mut rng, StandardNormal, dims);
+
+    let mut mean = Tensor1::zeroed(Dim1(dims.cols()));
+    let mut var = Tensor1::zeroed(Dim1(dims.cols()));
+    let mut normalized = Tensor2::zeroed(dims);
+    let mut output = Tensor2::zeroed(dims);
+    let mut gamma_grad = Tensor1::zeroed(Dim1(dims.cols()));
+    let mut beta_grad = Tensor1::zeroed(Dim1(dims.cols()));
+    let mut input_error = Tensor2::zeroed(dims);
+    cpu.column_mean_var(input.view(), &mut mean, &mut var);
+    cpu.batch_norm(
+        epsilon,
+        input.view(),
+        &mean,
+        &var,
+        &gamma,
+        &beta,
+        &mut normalized,
+        &mut output,
+    );
+    cpu.batch_norm_param_grad(&normalized, &out_error, &mut gamma_grad, &mut beta_grad);
+    cpu.batch_norm_input_error(
+        epsilon,
+        &normalized,
+        &out_error,
+        &var,
+        &gamma,
+        &gamma_grad,
+        &beta_grad,
+        &mut input_error,
+    );
+
+    let input_ocl = OclTensor2::from_native(&context, &queue, &input)?;
+    let gamma_ocl = OclTensor1::from_native(&context, &queue, &gamma)?;
+    let beta_ocl = OclTensor1::from_native(&context, &queue, &beta)?;
+    let out_error_ocl = OclTensor2::from_native(&context, &queue, &out_error)?;
+    let mut mean_ocl = OclTensor1::zeroed(&context, &queue, Dim1(dims.cols()))?;
+    let mut var_ocl = OclTensor1::zeroed(&context, &queue, Dim1(dims.cols()))?;
+    let mut normalized_ocl = OclTensor2::zeroed(&context, &queue, dims)?;
+    let mut output_ocl = OclTensor2::zeroed(&context, &queue, dims)?;
+    let mut gamma_grad_ocl = OclTensor1::zeroed(&context, &queue, Dim1(dims.cols()))?;
+    let mut beta_grad_ocl = OclTensor1::zeroed(&context, &queue, Dim1(dims.cols()))?;
+    let mut input_error_ocl = OclTensor2::zeroed(&context, &queue, dims)?;
+
+    let program = BatchNormProgram::<f32>::create(&context, dims.cols(), input_ocl.buffer_dims().cols())?;
+    program.column_mean_var(&queue, &input_ocl, &mut mean_ocl, &mut var_ocl)?;
+    program.batch_norm(
+        &queue,
+        epsilon,
+        &input_ocl,
+        &mean_ocl,
+        &var_ocl,
+        &gamma_ocl,
+        &beta_ocl,
+        &mut normalized_ocl,
+        &mut output_ocl,
+    )?;
+    program.batch_norm_param_grad(
+        &queue,
+        &normalized_ocl,
+        &out_error_ocl,
+        &mut gamma_grad_ocl,
+        &mut beta_grad_ocl,
+    )?;
+    program.batch_norm_input_error(
+        &queue,
+        epsilon,
+        &normalized_ocl,
+        &out_error_ocl,
+        &var_ocl,
+        &gamma_ocl,
+        &gamma_grad_ocl,
+        &beta_grad_ocl,
+        &mut input_error_ocl,
+    )?;
+
+    assert_abs_diff_eq!(mean, mean_ocl.as_native(&queue)?, epsilon = 0.0001);
+    assert_abs_diff_eq!(var, var_ocl.as_native(&queue)?, epsilon = 0.0001);
+    assert_abs_diff_eq!(normalized, normalized_ocl.as_native(&queue)?, epsilon = 0.0001);
+    assert_abs_diff_eq!(output, output_ocl.as_native(&queue)?, epsilon = 0.0001);
+    assert_abs_diff_eq!(gamma_grad, gamma_grad_ocl.as_native(&queue)?, epsilon = 0.001);
+    assert_abs_diff_eq!(beta_grad, beta_grad_ocl.as_native(&queue)?, epsilon = 0.001);
+    assert_abs_diff_eq!(input_error, input_error_ocl.as_native(&queue)?, epsilon = 0.0001);
+    Ok(())
+}
+
+#[test]
+fn test_layer_norm() -> Result<()> {
+    let TestContext { context, queue, .. } = create_test_context()?;
+    let cpu = CpuBackend::<f32>::new(0);
+    let mut rng = StdRng::seed_from_u64(0x1a7e);
+    let dims = Dim2(29, 41);
+    let epsilon = 1e-5;
+
+    let input = Tensor2::from_distribution(&mut rng, StandardNormal, dims);
+    let gain = Tensor1::from_distribution(&mut rng, StandardNormal, Dim1(dims.cols()));
+    let bias = Tensor1::from_distribution(&mut rng, StandardNormal, Dim1(dims.cols()));
+    let out_error = Tensor2::from_distribution(&mut rng, StandardNormal, dims);
+
+    let mut mean = Tensor1::zeroed(Dim1(dims.rows()));
+    let mut var = Tensor1::zeroed(Dim1(dims.rows()));
+    let mut normalized = Tensor2::zeroed(dims);
+    let mut output = Tensor2::zeroed(dims);
+    let mut input_error = Tensor2::zeroed(dims);
+    cpu.row_mean_var(input.view(), &mut mean, &mut var);
+    cpu.layer_norm(epsilon, input.view(), &mean, &var, &gain, &bias, &mut normalized, &mut output);
+    cpu.layer_norm_input_error(epsilon, &normalized, &out_error, &var, &gain, &mut input_error);
+
+    let input_ocl = OclTensor2::from_native(&context, &queue, &input)?;
+    let gain_ocl = OclTensor1::from_native(&context, &queue, &gain)?;
+    let bias_ocl = OclTensor1::from_native(&context, &queue, &bias)?;
+    let out_error_ocl = OclTensor2::from_native(&context, &queue, &out_error)?;
+    let mut mean_ocl = OclTensor1::zeroed(&context, &queue, Dim1(dims.rows()))?;
+    let mut var_ocl = OclTensor1::zeroed(&context, &queue, Dim1(dims.rows()))?;
+    let mut normalized_ocl = OclTensor2::zeroed(&context, &queue, dims)?;
+    let mut output_ocl = OclTensor2::zeroed(&context, &queue, dims)?;
+    let mut input_error_ocl = OclTensor2::zeroed(&context, &queue, dims)?;
+
+    let program = LayerNormProgram::<f32>::create(&context, dims.cols(), input_ocl.buffer_dims().cols())?;
+    program.row_mean_var(&queue, &input_ocl, &mut mean_ocl, &mut var_ocl)?;
+    program.layer_norm(
+        &queue,
+        epsilon,
+        &input_ocl,
+        &mean_ocl,
+        &var_ocl,
+        &gain_ocl,
+        &bias_ocl,
+        &mut normalized_ocl,
+        &mut output_ocl,
+    )?;
+    program.layer_norm_input_error(
+        &queue,
+        epsilon,
+        &normalized_ocl,
+        &out_error_ocl,
+        &var_ocl,
+        &gain_ocl,
+        &mut input_error_ocl,
+    )?;
+
+    assert_abs_diff_eq!(mean, mean_ocl.as_native(&queue)?, epsilon = 0.0001);
+    assert_abs_diff_eq!(var, var_ocl.as_native(&queue)?, epsilon = 0.0001);
+    assert_abs_diff_eq!(normalized, normalized_ocl.as_native(&queue)?, epsilon = 0.0001);
+    assert_abs_diff_eq!(output, output_ocl.as_native(&queue)?, epsilon = 0.0001);
+    assert_abs_diff_eq!(input_error, input_error_ocl.as_native(&queue)?, epsilon = 0.0001);
+    Ok(())
+}
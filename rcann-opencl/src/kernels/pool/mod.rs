@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod test;
+
+use crate::tensor::{OclFloat, OclTensor2};
+use crate::util::*;
+use rcann::backend::Window2d;
+use rcann::tensor::Dims;
+
+ocl_program! {
+    name = Pool2dProgram,
+    source = "pool2d.cl",
+    generic_args = <T: OclFloat>,
+    compile_params = (
+        window: Window2d,
+        input_stride: usize,
+        output_stride: usize,
+    ),
+    validation = {
+        validate!(window.is_valid(), "Invalid pooling window");
+        validate!(*input_stride >= window.input_dims.tensor_len(), "Input stride too small");
+        validate!(*output_stride >= window.output_dims(window.channels()).tensor_len(), "Output stride too small");
+    },
+    defines = {
+        FLOAT_BITS = T::BITS,
+        IN_C = window.input_dims.0,
+        IN_H = window.input_dims.1,
+        IN_W = window.input_dims.2,
+        KSIZE = window.size,
+        STRIDE = window.stride,
+        PAD = window.padding,
+        OUT_H = window.output_height(),
+        OUT_W = window.output_width(),
+        IN_STRIDE = *input_stride,
+        OUT_STRIDE = *output_stride,
+    },
+    kernels = {
+        max_pool2d {
+            call_params = (
+                input: &OclTensor2<T>,
+                output: &mut OclTensor2<T>,
+                indices: &mut OclTensor2<T>,
+            ),
+            validation = {
+                assert_eq!(input.buffer_dims().cols(), *input_stride);
+                assert_eq!(output.dims(), &Dim2(input.dims().rows(), window.output_dims(window.channels()).tensor_len()));
+                assert_eq!(output.buffer_dims().cols(), *output_stride);
+                assert_eq!(output.dims(), indices.dims());
+                assert_eq!(indices.buffer_dims().cols(), *output_stride);
+            },
+            inputs = [input],
+            outputs = [output, indices],
+            kernel_args = [
+                &(input.dims().rows() as u32),
+                input.buffer(),
+                output.buffer(),
+                indices.buffer(),
+            ],
+            global_dims = [next_multiple(input.dims().rows(), 16), output.buffer_dims().cols()],
+        },
+        max_pool2d_error {
+            call_params = (
+                indices: &OclTensor2<T>,
+                out_error: &OclTensor2<T>,
+                input_error: &mut OclTensor2<T>,
+            ),
+            validation = {
+                assert_eq!(out_error.dims(), &Dim2(input_error.dims().rows(), window.output_dims(window.channels()).tensor_len()));
+                assert_eq!(out_error.buffer_dims().cols(), *output_stride);
+                assert_eq!(out_error.dims(), indices.dims());
+                assert_eq!(indices.buffer_dims().cols(), *output_stride);
+                assert_eq!(input_error.dims().cols(), window.input_dims.tensor_len());
+                assert_eq!(input_error.buffer_dims().cols(), *input_stride);
+            },
+            inputs = [indices, out_error],
+            outputs = [input_error],
+            kernel_args = [
+                &(input_error.dims().rows() as u32),
+                indices.buffer(),
+                out_error.buffer(),
+                input_error.buffer(),
+            ],
+            global_dims = [next_multiple(input_error.dims().rows(), 16), input_error.buffer_dims().cols()],
+        },
+        avg_pool2d {
+            call_params = (
+                input: &OclTensor2<T>,
+                output: &mut OclTensor2<T>,
+            ),
+            validation = {
+                assert_eq!(input.buffer_dims().cols(), *input_stride);
+                assert_eq!(output.dims(), &Dim2(input.dims().rows(), window.output_dims(window.channels()).tensor_len()));
+                assert_eq!(output.buffer_dims().cols(), *output_stride);
+            },
+            inputs = [input],
+            outputs = [output],
+            kernel_args = [
+                &(input.dims().rows() as u32),
+                input.buffer(),
+                output.buffer(),
+            ],
+            global_dims = [next_multiple(input.dims().rows(), 16), output.buffer_dims().cols()],
+        },
+        avg_pool2d_error {
+            call_params = (
+                out_error: &OclTensor2<T>,
+                input_error: &mut OclTensor2<T>,
+            ),
+            validation = {
+                assert_eq!(out_error.dims(), &Dim2(input_error.dims().rows(), window.output_dims(window.channels()).tensor_len()));
+                assert_eq!(out_error.buffer_dims().cols(), *output_stride);
+                assert_eq!(input_error.dims().cols(), window.input_dims.tensor_len());
+                assert_eq!(input_error.buffer_dims().cols(), *input_stride);
+            },
+            inputs = [out_error],
+            outputs = [input_error],
+            kernel_args = [
+                &(input_error.dims().rows() as u32),
+                out_error.buffer(),
+                input_error.buffer(),
+            ],
+            global_dims = [next_multiple(input_error.dims().rows(), 16), input_error.buffer_dims().cols()],
+        },
+    },
+}
@@ -0,0 +1,156 @@
+#define IN_PLANE (IN_H * IN_W)
+#define OUT_PLANE (OUT_H * OUT_W)
+#define IN_LEN (IN_C * IN_PLANE)
+#define OUT_LEN (IN_C * OUT_PLANE)
+
+// the number of input values covered by the window at output position (oy, ox), excluding padding
+inline uint window_count(const uint oy, const uint ox) {
+    const int y0 = (int)(oy * STRIDE) - PAD;
+    const int x0 = (int)(ox * STRIDE) - PAD;
+    const int h = min(y0 + KSIZE, IN_H) - max(y0, 0);
+    const int w = min(x0 + KSIZE, IN_W) - max(x0, 0);
+    return (uint)(h * w);
+}
+
+__kernel void max_pool2d(
+        const uint ROWS,
+        const __global real* input,
+        __global real* output,
+        __global real* indices
+) {
+    const uint row = get_global_id(0);
+    const uint idx = get_global_id(1);
+    if (row >= ROWS || idx >= OUT_LEN) {
+        return;
+    }
+    const uint c = idx / OUT_PLANE;
+    const int y0 = (int)(((idx % OUT_PLANE) / OUT_W) * STRIDE) - PAD;
+    const int x0 = (int)((idx % OUT_W) * STRIDE) - PAD;
+    const __global real* in_plane = input + row * IN_STRIDE + c * IN_PLANE;
+    bool found = false;
+    real max_val = (real)0.0;
+    uint max_k = 0;
+    for (uint ky = 0; ky < KSIZE; ky++) {
+        const int y = y0 + (int)ky;
+        if (y < 0 || y >= IN_H) {
+            continue;
+        }
+        for (uint kx = 0; kx < KSIZE; kx++) {
+            const int x = x0 + (int)kx;
+            if (x < 0 || x >= IN_W) {
+                continue;
+            }
+            const real val = in_plane[y * IN_W + x];
+            if (!found || val > max_val) {
+                found = true;
+                max_val = val;
+                max_k = ky * KSIZE + kx;
+            }
+        }
+    }
+    output[row * OUT_STRIDE + idx] = max_val;
+    indices[row * OUT_STRIDE + idx] = (real)max_k;
+}
+
+// one work item per input value, gathering the error of every window whose max it is
+__kernel void max_pool2d_error(
+        const uint ROWS,
+        const __global real* indices,
+        const __global real* out_error,
+        __global real* input_error
+) {
+    const uint row = get_global_id(0);
+    const uint idx = get_global_id(1);
+    if (row >= ROWS || idx >= IN_LEN) {
+        return;
+    }
+    const uint c = idx / IN_PLANE;
+    const int y = (int)((idx % IN_PLANE) / IN_W) + PAD;
+    const int x = (int)(idx % IN_W) + PAD;
+    const uint out_offset = row * OUT_STRIDE + c * OUT_PLANE;
+    real sum = (real)0.0;
+    for (uint ky = 0; ky < KSIZE; ky++) {
+        const int ty = y - (int)ky;
+        if (ty < 0 || ty % STRIDE != 0 || ty / STRIDE >= OUT_H) {
+            continue;
+        }
+        for (uint kx = 0; kx < KSIZE; kx++) {
+            const int tx = x - (int)kx;
+            if (tx < 0 || tx % STRIDE != 0 || tx / STRIDE >= OUT_W) {
+                continue;
+            }
+            const uint o = out_offset + (ty / STRIDE) * OUT_W + tx / STRIDE;
+            if ((uint)indices[o] == ky * KSIZE + kx) {
+                sum += out_error[o];
+            }
+        }
+    }
+    input_error[row * IN_STRIDE + idx] = sum;
+}
+
+__kernel void avg_pool2d(
+        const uint ROWS,
+        const __global real* input,
+        __global real* output
+) {
+    const uint row = get_global_id(0);
+    const uint idx = get_global_id(1);
+    if (row >= ROWS || idx >= OUT_LEN) {
+        return;
+    }
+    const uint c = idx / OUT_PLANE;
+    const uint oy = (idx % OUT_PLANE) / OUT_W;
+    const uint ox = idx % OUT_W;
+    const int y0 = (int)(oy * STRIDE) - PAD;
+    const int x0 = (int)(ox * STRIDE) - PAD;
+    const __global real* in_plane = input + row * IN_STRIDE + c * IN_PLANE;
+    real sum = (real)0.0;
+    for (uint ky = 0; ky < KSIZE; ky++) {
+        const int y = y0 + (int)ky;
+        if (y < 0 || y >= IN_H) {
+            continue;
+        }
+        for (uint kx = 0; kx < KSIZE; kx++) {
+            const int x = x0 + (int)kx;
+            if (x < 0 || x >= IN_W) {
+                continue;
+            }
+            sum += in_plane[y * IN_W + x];
+        }
+    }
+    output[row * OUT_STRIDE + idx] = sum / (real)window_count(oy, ox);
+}
+
+// one work item per input value, gathering the error of every window that covers it
+__kernel void avg_pool2d_error(
+        const uint ROWS,
+        const __global real* out_error,
+        __global real* input_error
+) {
+    const uint row = get_global_id(0);
+    const uint idx = get_global_id(1);
+    if (row >= ROWS || idx >= IN_LEN) {
+        return;
+    }
+    const uint c = idx / IN_PLANE;
+    const int y = (int)((idx % IN_PLANE) / IN_W) + PAD;
+    const int x = (int)(idx % IN_W) + PAD;
+    const uint out_offset = row * OUT_STRIDE + c * OUT_PLANE;
+    real sum = (real)0.0;
+    for (uint ky = 0; ky < KSIZE; ky++) {
+        const int ty = y - (int)ky;
+        if (ty < 0 || ty % STRIDE != 0 || ty / STRIDE >= OUT_H) {
+            continue;
+        }
+        for (uint kx = 0; kx < KSIZE; kx++) {
+            const int tx = x - (int)kx;
+            if (tx < 0 || tx % STRIDE != 0 || tx / STRIDE >= OUT_W) {
+                continue;
+            }
+            const uint oy = ty / STRIDE;
+            const uint ox = tx / STRIDE;
+            sum += out_error[out_offset + oy * OUT_W + ox] / (real)window_count(oy, ox);
+        }
+    }
+    input_error[row * IN_STRIDE + idx] = sum;
+}
@@ -0,0 +1,76 @@
+use super::Pool2dProgram;
+use crate::tensor::OclTensor2;
+use crate::util::*;
+use approx::assert_abs_diff_eq;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rand_distr::StandardNormal;
+use rcann::backend::{BackendOther, CpuBackend, Window2d};
+use rcann::tensor::{Dim2, Dim3, Dims, ITensor, Tensor2, TensorBase};
+
+fn test_pool2d(window: Window2d) -> Result<()> {
+    let TestContext { context, queue, .. } = create_test_context()?;
+    let cpu = CpuBackend::<f32>::new(0);
+    let mut rng = StdRng::seed_from_u64(0x9001);
+    let rows = 19;
+    let out_dims = Dim2(rows, window.output_dims(window.channels()).tensor_len());
+
+    let input = Tensor2::from_distribution(&mut rng, StandardNormal, Dim2(rows, window.input_dims.tensor_len()));
+    let out_error = Tensor2::from_distribution(&mut rng, StandardNormal, out_dims);
+
+    let mut max_output = Tensor2::zeroed(out_dims);
+    let mut indices = Tensor2::zeroed(out_dims);
+    let mut max_input_error = Tensor2::zeroed(*input.dims());
+    let mut avg_output = Tensor2::zeroed(out_dims);
+    let mut avg_input_error = Tensor2::zeroed(*input.dims());
+    cpu.max_pool2d(&window, input.view(), &mut max_output, &mut indices);
+    cpu.max_pool2d_error(&window, &indices, &out_error, &mut max_input_error);
+    cpu.avg_pool2d(&window, input.view(), &mut avg_output);
+    cpu.avg_pool2d_error(&window, &out_error, &mut avg_input_error);
+
+    let input_ocl = OclTensor2::from_native(&context, &queue, &input)?;
+    let out_error_ocl = OclTensor2::from_native(&context, &queue, &out_error)?;
+    let mut max_output_ocl = OclTensor2::zeroed(&context, &queue, out_dims)?;
+    let mut indices_ocl = OclTensor2::zeroed(&context, &queue, out_dims)?;
+    let mut max_input_error_ocl = OclTensor2::zeroed(&context, &queue, *input.dims())?;
+    let mut avg_output_ocl = OclTensor2::zeroed(&context, &queue, out_dims)?;
+    let mut avg_input_error_ocl = OclTensor2::zeroed(&context, &queue, *input.dims())?;
+
+    let program = Pool2dProgram::<f32>::create(
+        &context,
+        window,
+        input_ocl.buffer_dims().cols(),
+        max_output_ocl.buffer_dims().cols(),
+    )?;
+    program.max_pool2d(&queue, &input_ocl, &mut max_output_ocl, &mut indices_ocl)?;
+    program.max_pool2d_error(&queue, &indices_ocl, &out_error_ocl, &mut max_input_error_ocl)?;
+    program.avg_pool2d(&queue, &input_ocl, &mut avg_output_ocl)?;
+    program.avg_pool2d_error(&queue, &out_error_ocl, &mut avg_input_error_ocl)?;
+
+    assert_abs_diff_eq!(max_output, max_output_ocl.as_native(&queue)?, epsilon = 0.0001);
+    assert_abs_diff_eq!(indices, indices_ocl.as_native(&queue)?, epsilon = 0.0001);
+    assert_abs_diff_eq!(max_input_error, max_input_error_ocl.as_native(&queue)?, epsilon = 0.0001);
+    assert_abs_diff_eq!(avg_output, avg_output_ocl.as_native(&queue)?, epsilon = 0.0001);
+    assert_abs_diff_eq!(avg_input_error, avg_input_error_ocl.as_native(&queue)?, epsilon = 0.0001);
+    Ok(())
+}
+
+#[test]
+fn test_pool2d_non_overlapping() -> Result<()> {
+    test_pool2d(Window2d {
+        input_dims: Dim3(6, 24, 24),
+        size: 2,
+        stride: 2,
+        padding: 0,
+    })
+}
+
+#[test]
+fn test_pool2d_overlapping_padded() -> Result<()> {
+    test_pool2d(Window2d {
+        input_dims: Dim3(3, 11, 13),
+        size: 3,
+        stride: 2,
+        padding: 1,
+    })
+}
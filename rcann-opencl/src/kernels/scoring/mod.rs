@@ -3,7 +3,7 @@ mod test;
 
 use crate::tensor::event_list::EventList;
 use crate::tensor::{OclFloat, OclTensor1, OclTensor2};
-use crate::util::{next_multiple, ocl_program};
+use crate::util::{next_multiple, ocl_program, Result};
 use opencl3::command_queue::CommandQueue;
 use opencl3::context::Context;
 use opencl3::kernel::ExecuteKernel;
@@ -79,13 +79,13 @@ impl<T: OclFloat> ScoringProgram<T> {
         matrix: &mut OclTensor2<T>,
         output: &OclTensor2<T>,
         expected: &OclTensor2<T>,
-    ) {
+    ) -> Result<()> {
         let &Dim2(rows, n) = output.dims();
         assert_eq!(expected.dims(), output.dims());
         assert_eq!(matrix.dims(), &Dim2(n, n));
 
-        let mut index_buffer = unsafe { OclTensor1::uninit(context, Dim1(rows * 2)).unwrap() };
-        self.compute_confusion_matrix_indices(queue, output, expected, &mut index_buffer);
-        self.inc_by_indices(queue, matrix, &index_buffer);
+        let mut index_buffer = unsafe { OclTensor1::uninit(context, Dim1(rows * 2))? };
+        self.compute_confusion_matrix_indices(queue, output, expected, &mut index_buffer)?;
+        self.inc_by_indices(queue, matrix, &index_buffer)
     }
 }
\ No newline at end of file
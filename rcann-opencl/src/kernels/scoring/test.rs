@@ -31,8 +31,20 @@ macro_rules! impl_tests {
                 let ocl_expected = OclTensor2::from_native(&context, &queue, &expected_vals)?;
                 let mut matrix_actual = OclTensor2::zeroed(&context, &queue, *matrix_expected.dims())?;
 
-                kernel.accum_multiclass_confusion_matrix(&context, &queue, &mut matrix_actual, &ocl_output, &ocl_expected);
-                kernel.accum_multiclass_confusion_matrix(&context, &queue, &mut matrix_actual, &ocl_output, &ocl_expected);
+                kernel.accum_multiclass_confusion_matrix(
+                    &context,
+                    &queue,
+                    &mut matrix_actual,
+                    &ocl_output,
+                    &ocl_expected,
+                )?;
+                kernel.accum_multiclass_confusion_matrix(
+                    &context,
+                    &queue,
+                    &mut matrix_actual,
+                    &ocl_output,
+                    &ocl_expected,
+                )?;
 
                 assert_abs_diff_eq!(matrix_expected, matrix_actual.as_native(&queue)?);
 
@@ -46,5 +46,28 @@ ocl_program! {
             global_dims = [next_multiple(activation.dims().rows(), 16)],
 
         },
+        softmax_error {
+            call_params = (
+                output: &OclTensor2<T>,
+                error: &OclTensor2<T>,
+                result: &mut OclTensor2<T>,
+            ),
+            validation = {
+                assert_eq!(output.dims(), error.dims());
+                assert_eq!(output.dims(), result.dims());
+                assert_eq!(output.buffer_dims().cols(), *row_stride);
+                assert_eq!(error.buffer_dims().cols(), *row_stride);
+                assert_eq!(result.buffer_dims().cols(), *row_stride);
+            },
+            inputs = [output, error],
+            outputs = [result],
+            kernel_args = [
+                &(output.dims().rows() as u32),
+                output.buffer(),
+                error.buffer(),
+                result.buffer(),
+            ],
+            global_dims = [next_multiple(output.dims().rows(), 16)],
+        },
     },
 }
@@ -60,4 +60,50 @@ __kernel void softmax(
         VEC_IDX(output[r_vec_offset + VEC_COLS], i) /= sum;
     }*/
 
-}
\ No newline at end of file
+}
+
+/**
+ * Computes the vector-Jacobian product of the softmax function for each row, which simplifies to
+ * result = output * (error - dot(output, error))
+ */
+__kernel void softmax_error(
+    const uint ROWS,
+    const __global realX* output,
+    const __global realX* error,
+    __global realX* result
+) {
+    const uint row = get_global_id(0);
+    if (row >= ROWS) {
+        return;
+    }
+    const uint r_vec_offset = row * ROW_STRIDE / VEC_WIDTH;
+
+    // compute dot(output, error)
+    realX temp_v = (realX)(0.0);
+    for (uint i = 0; i < VEC_COLS; i++) {
+        temp_v += output[r_vec_offset + i] * error[r_vec_offset + i];
+    }
+    real dot_err = VEC_DOT_SCALAR(temp_v, (real)1.0);
+    if (VEC_COLS_REM > 0) {
+        const realX out_rem_v = output[r_vec_offset + VEC_COLS];
+        const realX err_rem_v = error[r_vec_offset + VEC_COLS];
+        #pragma unroll
+        for (uint i = 0; i < VEC_COLS_REM; i++) {
+            dot_err += VEC_IDX(out_rem_v, i) * VEC_IDX(err_rem_v, i);
+        }
+    }
+    const realX dot_err_v = (realX)dot_err;
+
+    // apply the product
+    for (uint i = 0; i < VEC_COLS; i++) {
+        result[r_vec_offset + i] = output[r_vec_offset + i] * (error[r_vec_offset + i] - dot_err_v);
+    }
+    if (VEC_COLS_REM > 0) {
+        const realX out_rem_v = output[r_vec_offset + VEC_COLS];
+        const realX err_rem_v = error[r_vec_offset + VEC_COLS];
+        #pragma unroll
+        for (uint i = 0; i < VEC_COLS_REM; i++) {
+            VEC_IDX(result[r_vec_offset + VEC_COLS], i) = VEC_IDX(out_rem_v, i) * (VEC_IDX(err_rem_v, i) - dot_err);
+        }
+    }
+}
@@ -6,7 +6,7 @@ use rand::rngs::StdRng;
 use rand::SeedableRng;
 use rand_distr::StandardNormal;
 use rcann::backend::{BackendOther, CpuBackend};
-use rcann::tensor::{Dim2, ITensor, Tensor2};
+use rcann::tensor::{Dim2, ITensor, Tensor2, TensorBase};
 
 #[test]
 fn test_softmax() {
@@ -19,7 +19,7 @@ fn test_softmax() {
     let activation_ocl = OclTensor2::from_native(&context, &queue, &activation).unwrap();
     let mut output_ocl = OclTensor2::zeroed(&context, &queue, activation.dims().clone()).unwrap();
 
-    cpu.softmax(&activation, &mut output_expected);
+    cpu.softmax(activation.view(), &mut output_expected);
 
     let kernel = Softmax::create(
         &context,
@@ -28,7 +28,36 @@ fn test_softmax() {
         activation_ocl.buffer_dims().cols(),
     )
     .unwrap();
-    kernel.softmax(&queue, &activation_ocl, &mut output_ocl);
+    kernel.softmax(&queue, &activation_ocl, &mut output_ocl).unwrap();
 
     assert_abs_diff_eq!(output_expected, output_ocl.as_native(&queue).unwrap(), epsilon = 0.001);
 }
+
+#[test]
+fn test_softmax_error() {
+    let TestContext { device, context, queue } = create_test_context().unwrap();
+    let cpu = CpuBackend::<f32>::new(0);
+    let mut rng = StdRng::seed_from_u64(0x93827291);
+
+    let activation = Tensor2::from_distribution(&mut rng, StandardNormal, Dim2(42, 50));
+    let error = Tensor2::from_distribution(&mut rng, StandardNormal, *activation.dims());
+    let mut output = Tensor2::zeroed(*activation.dims());
+    cpu.softmax(activation.view(), &mut output);
+    let mut result_expected = Tensor2::zeroed(*activation.dims());
+    cpu.softmax_error(&output, &error, &mut result_expected);
+
+    let output_ocl = OclTensor2::from_native(&context, &queue, &output).unwrap();
+    let error_ocl = OclTensor2::from_native(&context, &queue, &error).unwrap();
+    let mut result_ocl = OclTensor2::zeroed(&context, &queue, *activation.dims()).unwrap();
+
+    let kernel = Softmax::create(
+        &context,
+        VecWidth::SIXTEEN,
+        output.dims().cols(),
+        output_ocl.buffer_dims().cols(),
+    )
+    .unwrap();
+    kernel.softmax_error(&queue, &output_ocl, &error_ocl, &mut result_ocl).unwrap();
+
+    assert_abs_diff_eq!(result_expected, result_ocl.as_native(&queue).unwrap(), epsilon = 0.001);
+}
@@ -16,7 +16,7 @@ fn test_transpose_small_square() -> Result<()> {
     let input = tensor![[1., 2., 3.], [4., 5., 6.], [7., 8., 9.]];
     let input_ocl = OclTensor::from_native(&context, &queue, &input)?;
     let mut output_ocl = OclTensor::zeroed(&context, &queue, input.dims().transposed())?;
-    kernel.transpose(&queue, &input_ocl, &mut output_ocl);
+    kernel.transpose(&queue, &input_ocl, &mut output_ocl)?;
     let output = output_ocl.as_native(&queue)?;
     assert_eq!(output, tensor![[1., 4., 7.], [2., 5., 8.], [3., 6., 9.]]);
 
@@ -31,7 +31,7 @@ fn test_transpose_small_rectangle() -> Result<()> {
     let input = tensor![[1., 2., 3.], [4., 5., 6.]];
     let input_ocl = OclTensor::from_native(&context, &queue, &input)?;
     let mut output_ocl = OclTensor::zeroed(&context, &queue, input.dims().transposed())?;
-    kernel.transpose(&queue, &input_ocl, &mut output_ocl);
+    kernel.transpose(&queue, &input_ocl, &mut output_ocl)?;
     let output = output_ocl.as_native(&queue)?;
     assert_eq!(output, tensor![[1., 4.], [2., 5.], [3., 6.]]);
 
@@ -49,8 +49,8 @@ fn test_transpose_large() -> Result<()> {
     let input = Tensor::from_distribution(&mut rng, StandardNormal, Dim2(m, n));
     let mut input_ocl = OclTensor::from_native(&context, &queue, &input)?;
     let mut output_ocl = OclTensor::zeroed(&context, &queue, input.dims().transposed())?;
-    kernel.transpose(&queue, &input_ocl, &mut output_ocl);
-    kernel.transpose(&queue, &output_ocl, &mut input_ocl);
+    kernel.transpose(&queue, &input_ocl, &mut output_ocl)?;
+    kernel.transpose(&queue, &output_ocl, &mut input_ocl)?;
     let output = input_ocl.as_native(&queue)?;
     assert_abs_diff_eq!(output, input);
 
@@ -0,0 +1,73 @@
+use crate::backend::OpenCLBackend;
+use crate::kernels::BUFFER_BLOCK_SIZE;
+use crate::kernels::gemm::GeMMProgram;
+use crate::kernels::transpose::TransposeProgram;
+use crate::kernels::zero_padding::ZeroPadProgram;
+use crate::tensor::{OclFloat, OclTensor2};
+use crate::util::{ProgramCache, Result};
+use rcann::tensor::ITensor;
+
+/// Multiplies matrices by transposing the transposed operands into temporaries before the product, the way
+/// [OpenCLBackend] did before the GeMM kernels could read them directly. Used to compare the two in benchmarks.
+pub struct TransposeGemm<F: OclFloat> {
+    gemm_program: GeMMProgram<F>,
+    transpose_program: TransposeProgram<F>,
+    zero_pad_program: ZeroPadProgram<F>,
+}
+
+impl<F: OclFloat> TransposeGemm<F> {
+    /// compiles the programs with the GeMM configuration of the backend
+    pub fn new(backend: &OpenCLBackend<F>) -> Result<Self> {
+        let context = backend.context();
+        let cache = ProgramCache::new(None, None);
+        let config = backend.gemm_config();
+        Ok(TransposeGemm {
+            gemm_program: GeMMProgram::get_or_create(context, &cache, config.vec_width, config.tile_size)?,
+            transpose_program: TransposeProgram::get_or_create(context, &cache, BUFFER_BLOCK_SIZE)?,
+            zero_pad_program: ZeroPadProgram::get_or_create(context, &cache, BUFFER_BLOCK_SIZE)?,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn matmul(
+        &self,
+        backend: &OpenCLBackend<F>,
+        alpha: F,
+        a: &OclTensor2<F>,
+        ta: bool,
+        b: &OclTensor2<F>,
+        tb: bool,
+        beta: F,
+        c: &mut OclTensor2<F>,
+    ) -> Result<()> {
+        let context = backend.context();
+        let queue = backend.queue();
+        let a_transpose = if ta {
+            let mut temp = unsafe { OclTensor2::uninit(context, a.dims().transposed())? };
+            self.transpose_program.transpose(queue, a, &mut temp)?;
+            Some(temp)
+        } else {
+            self.zero_pad_program.zero_padding(queue, a)?;
+            None
+        };
+        let b_transpose = if tb {
+            let mut temp = unsafe { OclTensor2::uninit(context, b.dims().transposed())? };
+            self.transpose_program.transpose(queue, b, &mut temp)?;
+            Some(temp)
+        } else {
+            self.zero_pad_program.zero_padding(queue, b)?;
+            None
+        };
+        if beta != F::ZERO {
+            self.zero_pad_program.zero_padding(queue, c)?;
+        }
+        self.gemm_program.gemm(
+            queue,
+            alpha,
+            a_transpose.as_ref().unwrap_or(a),
+            b_transpose.as_ref().unwrap_or(b),
+            beta,
+            c,
+        )
+    }
+}
@@ -1,19 +1,41 @@
+use crate::util::{create_program, Profiler, Result};
+use crate::wrap_cl_error;
+use opencl3::context::Context;
+use opencl3::device::Device;
 use opencl3::program::Program;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
+/// Enables the on-disk program cache of [crate::backend::OpenCLBackend::from_device] when set to a directory,
+/// see [crate::backend::OpenCLBackendOptions::cache_dir]
+pub const PROGRAM_CACHE_DIR_ENV_VAR: &str = "RCANN_OPENCL_CACHE_DIR";
+
+const BINARY_MAGIC: &[u8; 8] = b"RCANNPB\x01";
+
 #[derive(Debug)]
 pub(crate) struct ProgramCache {
     programs: RefCell<HashMap<String, Rc<Program>>>,
+    /// stores the program binaries, so they are only compiled once across processes
+    binary_dir: Option<PathBuf>,
+    /// records the kernel events of the programs created through the cache
+    profiler: Option<Rc<Profiler>>,
 }
 
 impl ProgramCache {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(binary_dir: Option<PathBuf>, profiler: Option<Rc<Profiler>>) -> Self {
         ProgramCache {
             programs: RefCell::new(HashMap::new()),
+            binary_dir,
+            profiler,
         }
     }
+    pub(crate) fn profiler(&self) -> Option<Rc<Profiler>> {
+        self.profiler.clone()
+    }
     pub(crate) fn get(&self, key: &str) -> Option<Rc<Program>> {
         let programs = self.programs.borrow();
         match programs.get(key) {
@@ -28,4 +50,145 @@ impl ProgramCache {
     pub(crate) fn clear(&self) {
         self.programs.borrow_mut().clear();
     }
+
+    /// builds the program from a binary stored by a previous build of the same source on the same devices
+    /// and drivers, or compiles and stores it if there is none
+    pub(crate) fn build_program(&self, context: &Context, source: &str) -> Result<Program> {
+        let Some(dir) = &self.binary_dir else {
+            return create_program(context, source, "");
+        };
+        let devices = describe_devices(context)?;
+        let path = binary_path(dir, &devices, source);
+        if let Ok(binaries) = read_binaries(&path, &devices, source) {
+            let binaries: Vec<&[u8]> = binaries.iter().map(Vec::as_slice).collect();
+            // the driver may still reject the binary, in which case it is rebuilt from source below
+            if let Ok(program) = Program::create_and_build_from_binary(context, &binaries, "") {
+                return Ok(program);
+            }
+        }
+        let program = create_program(context, source, "")?;
+        let binaries = wrap_cl_error!(program.get_binaries(), "Failed to get program binaries")?;
+        // the cache is only an optimization, so failing to write it must not fail the build
+        let _ = write_binaries(dir, &path, &devices, source, &binaries);
+        Ok(program)
+    }
+}
+
+/// identifies the devices of the context and their drivers, since binaries only load on the same ones
+fn describe_devices(context: &Context) -> Result<String> {
+    let mut description = String::new();
+    for &device_id in context.devices() {
+        description.push_str(&describe_device(&Device::new(device_id))?);
+        description.push('\n');
+    }
+    Ok(description)
+}
+
+/// the name, OpenCL version and driver version of the device
+pub(crate) fn describe_device(device: &Device) -> Result<String> {
+    let name = wrap_cl_error!(device.name(), "Failed to get device name")?;
+    let version = wrap_cl_error!(device.version(), "Failed to get device version")?;
+    let driver_version = wrap_cl_error!(device.driver_version(), "Failed to get driver version")?;
+    Ok(format!("{name};{version};{driver_version}"))
+}
+
+/// FNV-1a, since the hash of the file name has to be the same in every build
+fn stable_hash(parts: &[&[u8]]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for &byte in parts.iter().flat_map(|part| part.iter()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn binary_path(dir: &Path, devices: &str, source: &str) -> PathBuf {
+    dir.join(format!(
+        "{:016x}.bin",
+        stable_hash(&[devices.as_bytes(), source.as_bytes()])
+    ))
+}
+
+/// The file stores the device description and source along with the binaries, so a hash collision or
+/// an edited file can't load the wrong program.
+fn write_binaries(dir: &Path, path: &Path, devices: &str, source: &str, binaries: &[Vec<u8>]) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    // write to a temporary file first, so other processes never read a partially written file
+    let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+    let mut w = BufWriter::new(File::create(&temp_path)?);
+    w.write_all(BINARY_MAGIC)?;
+    write_bytes(&mut w, devices.as_bytes())?;
+    write_bytes(&mut w, source.as_bytes())?;
+    w.write_all(&(binaries.len() as u64).to_le_bytes())?;
+    for binary in binaries {
+        write_bytes(&mut w, binary)?;
+    }
+    w.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+    fs::rename(&temp_path, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp_path);
+    })
+}
+
+fn read_binaries(path: &Path, devices: &str, source: &str) -> io::Result<Vec<Vec<u8>>> {
+    let mut r = BufReader::new(File::open(path)?);
+    let mut magic = [0u8; BINARY_MAGIC.len()];
+    r.read_exact(&mut magic)?;
+    let is_match =
+        &magic == BINARY_MAGIC && read_bytes(&mut r)? == devices.as_bytes() && read_bytes(&mut r)? == source.as_bytes();
+    if !is_match {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Stale program binary"));
+    }
+    let count = read_u64(&mut r)?;
+    (0..count).map(|_| read_bytes(&mut r)).collect()
+}
+
+fn write_bytes<W: Write>(w: &mut W, bytes: &[u8]) -> io::Result<()> {
+    w.write_all(&(bytes.len() as u64).to_le_bytes())?;
+    w.write_all(bytes)
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_bytes<R: Read>(r: &mut R) -> io::Result<Vec<u8>> {
+    let len = read_u64(r)?;
+    let mut bytes = Vec::new();
+    r.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{binary_path, read_binaries, write_binaries};
+    use std::fs;
+
+    #[test]
+    fn test_binary_file() {
+        let dir = std::env::temp_dir().join(format!("rcann-program-cache-{}", std::process::id()));
+        let devices = "Device;OpenCL 3.0;1.2.3\n";
+        let source = "__kernel void foo() {}";
+        let binaries = vec![vec![1, 2, 3], vec![], vec![4; 1000]];
+        let path = binary_path(&dir, devices, source);
+        write_binaries(&dir, &path, devices, source, &binaries).unwrap();
+
+        assert_eq!(read_binaries(&path, devices, source).unwrap(), binaries);
+        // a different source or driver must not load the binaries
+        assert!(read_binaries(&path, devices, "__kernel void bar() {}").is_err());
+        assert!(read_binaries(&path, "Device;OpenCL 3.0;1.2.4\n", source).is_err());
+        assert_ne!(path, binary_path(&dir, devices, "__kernel void bar() {}"));
+        assert_ne!(path, binary_path(&dir, "Device;OpenCL 3.0;1.2.4\n", source));
+
+        // a truncated file is rejected
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(read_binaries(&path, devices, source).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
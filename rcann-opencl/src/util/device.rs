@@ -0,0 +1,238 @@
+use crate::error::Error;
+use crate::util::Result;
+use opencl3::device::{Device, CL_DEVICE_TYPE_ACCELERATOR, CL_DEVICE_TYPE_ALL, CL_DEVICE_TYPE_CPU, CL_DEVICE_TYPE_GPU};
+use opencl3::error_codes::CL_DEVICE_NOT_FOUND;
+use opencl3::platform::get_platforms;
+use opencl3::types::cl_device_type;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Overrides the device selected by [DeviceSelector::from_env], e.g. `type=cpu,platform=pocl` or `gpu,1`.
+/// The format is described on the [FromStr] impl of [DeviceSelector].
+pub const DEVICE_ENV_VAR: &str = "RCANN_OPENCL_DEVICE";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DeviceType {
+    #[default]
+    Gpu,
+    Cpu,
+    Accelerator,
+    All,
+}
+
+impl DeviceType {
+    fn cl_device_type(&self) -> cl_device_type {
+        match self {
+            DeviceType::Gpu => CL_DEVICE_TYPE_GPU,
+            DeviceType::Cpu => CL_DEVICE_TYPE_CPU,
+            DeviceType::Accelerator => CL_DEVICE_TYPE_ACCELERATOR,
+            DeviceType::All => CL_DEVICE_TYPE_ALL,
+        }
+    }
+}
+
+impl Display for DeviceType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DeviceType::Gpu => "gpu",
+            DeviceType::Cpu => "cpu",
+            DeviceType::Accelerator => "accelerator",
+            DeviceType::All => "all",
+        })
+    }
+}
+
+impl FromStr for DeviceType {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "gpu" => Ok(DeviceType::Gpu),
+            "cpu" => Ok(DeviceType::Cpu),
+            "accelerator" | "acc" => Ok(DeviceType::Accelerator),
+            "all" | "any" => Ok(DeviceType::All),
+            _ => Err(Error::ConversionError(format!("Invalid device type: {s}"))),
+        }
+    }
+}
+
+/// Selects an OpenCL device by type, platform name, device name and index.
+/// Names match any platform or device containing them, ignoring case.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DeviceSelector {
+    pub device_type: DeviceType,
+    pub platform: Option<String>,
+    pub name: Option<String>,
+    /// the index of the device among all matching devices, ordered by platform
+    pub index: usize,
+}
+
+impl DeviceSelector {
+    pub fn new(device_type: DeviceType) -> Self {
+        DeviceSelector {
+            device_type,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_platform<S: Into<String>>(mut self, platform: S) -> Self {
+        self.platform = Some(platform.into());
+        self
+    }
+
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn with_index(mut self, index: usize) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// the selector given by [DEVICE_ENV_VAR], or the first GPU if it isn't set
+    pub fn from_env() -> Result<Self> {
+        match std::env::var(DEVICE_ENV_VAR) {
+            Ok(value) => value
+                .parse()
+                .map_err(|err| Error::ConversionError(format!("Invalid value of {DEVICE_ENV_VAR}: {err}"))),
+            Err(_) => Ok(DeviceSelector::default()),
+        }
+    }
+
+    /// lists the devices matching the selector, ignoring its index
+    pub fn matching_devices(&self) -> Result<Vec<Device>> {
+        let platform_filter = self.platform.as_deref().map(str::to_lowercase);
+        let name_filter = self.name.as_deref().map(str::to_lowercase);
+        let mut devices = Vec::new();
+        for platform in get_platforms().map_err(|err| Error::from_cl_err(err, "Failed to enumerate platforms"))? {
+            if let Some(filter) = &platform_filter {
+                let name = platform
+                    .name()
+                    .map_err(|err| Error::from_cl_err(err, "Failed to get platform name"))?;
+                if !name.to_lowercase().contains(filter) {
+                    continue;
+                }
+            }
+            let device_ids = match platform.get_devices(self.device_type.cl_device_type()) {
+                Ok(device_ids) => device_ids,
+                // the platform has no devices of this type
+                Err(err) if err.0 == CL_DEVICE_NOT_FOUND => continue,
+                Err(err) => return Err(Error::from_cl_err(err, "Failed to enumerate devices")),
+            };
+            for device_id in device_ids {
+                let device = Device::new(device_id);
+                if let Some(filter) = &name_filter {
+                    let name = device
+                        .name()
+                        .map_err(|err| Error::from_cl_err(err, "Failed to get device name"))?;
+                    if !name.to_lowercase().contains(filter) {
+                        continue;
+                    }
+                }
+                devices.push(device);
+            }
+        }
+        Ok(devices)
+    }
+
+    /// returns [Error::NoDevicesFound] if there are no OpenCL devices at all, or [Error::DeviceNotFound] if
+    /// none of them match
+    pub fn select(&self) -> Result<Device> {
+        let mut devices = self.matching_devices()?;
+        if self.index < devices.len() {
+            return Ok(devices.swap_remove(self.index));
+        }
+        let available = match DeviceSelector::new(DeviceType::All).matching_devices() {
+            Ok(all) if all.is_empty() => return Err(Error::NoDevicesFound),
+            Ok(all) => describe_devices(&all),
+            Err(err) => err.to_string(),
+        };
+        Err(Error::DeviceNotFound(format!(
+            "No OpenCL device matches {self} ({} matching devices). Available devices: {available}",
+            devices.len()
+        )))
+    }
+}
+
+impl Display for DeviceSelector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "type={}", self.device_type)?;
+        if let Some(platform) = &self.platform {
+            write!(f, ",platform={platform}")?;
+        }
+        if let Some(name) = &self.name {
+            write!(f, ",name={name}")?;
+        }
+        write!(f, ",index={}", self.index)
+    }
+}
+
+/// Parses a comma separated list of `type=<gpu|cpu|accelerator|all>`, `platform=<name>`, `name=<name>` and
+/// `index=<n>`, where the keys may be left out for a device type or index, e.g. `cpu,1`.
+impl FromStr for DeviceSelector {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let mut selector = DeviceSelector::default();
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let parse_index = |value: &str| {
+                value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| Error::ConversionError(format!("Invalid device index: {value}")))
+            };
+            match part.split_once('=') {
+                Some((key, value)) => match key.trim().to_ascii_lowercase().as_str() {
+                    "type" => selector.device_type = value.parse()?,
+                    "platform" => selector.platform = Some(value.trim().to_string()),
+                    "name" => selector.name = Some(value.trim().to_string()),
+                    "index" => selector.index = parse_index(value)?,
+                    _ => return Err(Error::ConversionError(format!("Invalid device selector key: {key}"))),
+                },
+                None if part.chars().all(|c| c.is_ascii_digit()) => selector.index = parse_index(part)?,
+                None => selector.device_type = part.parse()?,
+            }
+        }
+        Ok(selector)
+    }
+}
+
+/// lists every device of every platform for error messages
+fn describe_devices(devices: &[Device]) -> String {
+    devices
+        .iter()
+        .map(|device| device.name().unwrap_or_else(|_| "<unknown>".to_string()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DeviceSelector, DeviceType};
+
+    #[test]
+    fn test_parse_selector() {
+        assert_eq!("".parse::<DeviceSelector>().unwrap(), DeviceSelector::default());
+        assert_eq!(
+            "cpu".parse::<DeviceSelector>().unwrap(),
+            DeviceSelector::new(DeviceType::Cpu)
+        );
+        assert_eq!(
+            "all, 2".parse::<DeviceSelector>().unwrap(),
+            DeviceSelector::new(DeviceType::All).with_index(2)
+        );
+        let selector = DeviceSelector::new(DeviceType::Accelerator)
+            .with_platform("Portable Computing")
+            .with_name("pthread")
+            .with_index(1);
+        assert_eq!(
+            "type=accelerator,platform=Portable Computing,name=pthread,index=1"
+                .parse::<DeviceSelector>()
+                .unwrap(),
+            selector
+        );
+        assert_eq!(selector.to_string().parse::<DeviceSelector>().unwrap(), selector);
+        assert!("type=tpu".parse::<DeviceSelector>().is_err());
+        assert!("index=-1".parse::<DeviceSelector>().is_err());
+        assert!("vendor=amd".parse::<DeviceSelector>().is_err());
+    }
+}
@@ -123,6 +123,7 @@ macro_rules! ocl_program {
         #[derive(Debug)]
         pub(crate) struct $type_name$(<$( $gen_ident: $gen_constraint ),+>)? {
             program: std::rc::Rc<opencl3::program::Program>,
+            profiler: Option<std::rc::Rc<$crate::util::Profiler>>,
             $( $( $compile_param_name: $compile_param_ty, )* )?
             $( $kernel_name: opencl3::kernel::Kernel, )+
             $(_marker: std::marker::PhantomData<$( $gen_ident ),+>)?,
@@ -131,8 +132,10 @@ macro_rules! ocl_program {
         #[allow(unused)]
         impl$(<$( $gen_ident: $gen_constraint ),+>)? $type_name$(<$( $gen_ident ),+>)? {
 
+            /// compiles the program, or loads its binary from the disk cache of `cache` if given
             fn compile_program(
                 context: &opencl3::context::Context,
+                cache: Option<&$crate::util::ProgramCache>,
                 $( $( $compile_param_name: &$compile_param_ty, )* )?
             ) -> $crate::util::Result<std::rc::Rc<opencl3::program::Program>> {
                 use $crate::util::*;
@@ -142,9 +145,10 @@ macro_rules! ocl_program {
                 $(push_c_defines!(code, $( $defines )*);)?
                 code.push_str(KERNEL_HEADER);
                 code.push_str(concat!("\n", include_str!($source_file)));
-                Ok(std::rc::Rc::new(
-                    create_program(context, code.as_str(), "")?
-                ))
+                Ok(std::rc::Rc::new(match cache {
+                    Some(cache) => cache.build_program(context, code.as_str())?,
+                    None => create_program(context, code.as_str(), "")?,
+                }))
             }
 
             fn get_or_compile_program(
@@ -160,6 +164,7 @@ macro_rules! ocl_program {
                     None => {
                         let program = Self::compile_program(
                             context,
+                            Some(cache),
                             $( $( $compile_param_name, )* )?
                         )?;
                         cache.insert(key, program.clone());
@@ -170,6 +175,7 @@ macro_rules! ocl_program {
 
             fn new(
                 program: std::rc::Rc<opencl3::program::Program>,
+                profiler: Option<std::rc::Rc<$crate::util::Profiler>>,
                 $( $( $compile_param_name: $compile_param_ty, )* )?
             ) -> $crate::util::Result<Self> {
                 $(
@@ -177,6 +183,7 @@ macro_rules! ocl_program {
                 )+
                 Ok(Self {
                     program,
+                    profiler,
                     $( $( $compile_param_name, )* )?
                     $( $kernel_name,)+
                     _marker: std::marker::PhantomData,
@@ -190,8 +197,10 @@ macro_rules! ocl_program {
                 Self::new(
                     Self::compile_program(
                         context,
+                        None,
                         $( $( &$compile_param_name, )* )?
                     )?,
+                    None,
                     $( $( $compile_param_name, )* )?
                 )
             }
@@ -207,12 +216,14 @@ macro_rules! ocl_program {
                         cache,
                         $( $( &$compile_param_name, )* )?
                     )?,
+                    cache.profiler(),
                     $( $( $compile_param_name, )* )?
                 )
             }
 
             ocl_program!(
                 @impl_kernel_fn
+                program = $type_name,
                 fields = [$( $( $compile_param_name, )* )?],
                 $($kernel_name { $($kernels_tt)+ },)+
             );
@@ -230,11 +241,13 @@ macro_rules! ocl_program {
 
     (
         @impl_kernel_fn
+        program = $type_name:ident,
         fields = [$($field:ident),* $(,)?],
     ) => {};
 
     (
         @impl_kernel_fn
+        program = $type_name:ident,
         fields = [$($field:ident),* $(,)?],
         $kernel_name:ident {
             $(generic_args = <$( $gen_ident:ident : $gen_constraint:path ),+ $(,)?>,)?
@@ -256,7 +269,7 @@ macro_rules! ocl_program {
             $(
             $param: $param_ty,
             )+
-        ) {
+        ) -> $crate::util::Result<()> {
             use opencl3::kernel::ExecuteKernel;
             use $crate::tensor::event_list::EventList;
             use rcann::tensor::*;
@@ -280,16 +293,22 @@ macro_rules! ocl_program {
                 );
                 exec.set_event_wait_list(deps.as_slice());
             }
-            let event = EventList::from_event(unsafe {
-                exec.enqueue_nd_range(queue)
-                    .expect(concat!("Failed to enqueue ", stringify!($kernel_name), " kernel"))
-            });
+            let event = wrap_cl_error!(
+                unsafe { exec.enqueue_nd_range(queue) },
+                concat!("Failed to enqueue ", stringify!($kernel_name), " kernel")
+            )?;
+            if let Some(profiler) = &self.profiler {
+                profiler.record(concat!(stringify!($type_name), "::", stringify!($kernel_name)), &event)?;
+            }
+            let event = EventList::from_event(event);
             $(
             $output.set_deps(event.clone());
             )+
+            Ok(())
         }
         ocl_program!(
             @impl_kernel_fn
+            program = $type_name,
             fields = [$($field),*],
             $( $rest )*
         );
@@ -1,12 +1,18 @@
+pub mod bench;
 mod cache;
+mod device;
 mod kernel_macros;
+mod profile;
 
 use crate::error::Error;
 pub(crate) use cache::*;
+pub use cache::PROGRAM_CACHE_DIR_ENV_VAR;
+pub use device::*;
 pub(crate) use kernel_macros::*;
-use opencl3::command_queue::{CommandQueue, CL_QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE};
+pub use profile::*;
+use opencl3::command_queue::{CommandQueue, CL_QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE, CL_QUEUE_PROFILING_ENABLE};
 use opencl3::context::Context;
-use opencl3::device::{get_all_devices, Device, CL_DEVICE_TYPE_GPU};
+use opencl3::device::Device;
 use opencl3::kernel::Kernel;
 use opencl3::program::Program;
 use opencl3::types::cl_event;
@@ -103,12 +109,9 @@ pub fn hash_value<T: Hash>(value: T) -> u64 {
     h.finish()
 }
 
+/// selects the device given by [DEVICE_ENV_VAR], or the first GPU if it isn't set
 pub fn get_default_device() -> Result<Device> {
-    let device_id = *get_all_devices(CL_DEVICE_TYPE_GPU)
-        .map_err(|err| Error::from_cl_err(err, "Failed to enumerate devices"))?
-        .first()
-        .ok_or(Error::NoDevicesFound)?;
-    Ok(Device::new(device_id))
+    DeviceSelector::from_env()?.select()
 }
 
 pub fn get_context(device: &Device) -> Result<Context> {
@@ -130,6 +133,18 @@ pub fn create_queue(context: &Context) -> Result<CommandQueue> {
     )
 }
 
+/// creates a queue which records the times of its commands, see [Profiler]
+pub fn create_profiling_queue(context: &Context) -> Result<CommandQueue> {
+    wrap_cl_error!(
+        CommandQueue::create_default_with_properties(
+            context,
+            CL_QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE | CL_QUEUE_PROFILING_ENABLE,
+            0
+        ),
+        "Failed to create profiling queue"
+    )
+}
+
 #[cfg(test)]
 #[allow(unused)]
 pub struct TestContext {
@@ -0,0 +1,174 @@
+use crate::util::Result;
+use crate::wrap_cl_error;
+use opencl3::event::{retain_event, wait_for_events, Event};
+use opencl3::types::cl_event;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::mem;
+use std::time::Duration;
+
+/// the number of events kept before waiting for them, to bound the memory used by long runs
+const MAX_PENDING_EVENTS: usize = 4096;
+
+/// Collects the execution times of the kernels enqueued on a queue with profiling enabled.
+#[derive(Debug, Default)]
+pub struct Profiler {
+    /// events of kernels which may not have completed yet
+    pending: RefCell<Vec<(&'static str, Event)>>,
+    kernels: RefCell<HashMap<&'static str, KernelProfile>>,
+}
+
+impl Profiler {
+    pub(crate) fn record(&self, kernel: &'static str, event: &Event) -> Result<()> {
+        // the profiler holds its own reference, since the event is released once the tensors don't depend on it
+        wrap_cl_error!(unsafe { retain_event(event.get()) }, "Failed to retain event")?;
+        let mut pending = self.pending.borrow_mut();
+        pending.push((kernel, Event::new(event.get())));
+        if pending.len() >= MAX_PENDING_EVENTS {
+            drop(pending);
+            self.collect()?;
+        }
+        Ok(())
+    }
+
+    /// waits for the pending kernels and adds their times to the profiles
+    fn collect(&self) -> Result<()> {
+        let pending = mem::take(&mut *self.pending.borrow_mut());
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let events: Vec<cl_event> = pending.iter().map(|(_, event)| event.get()).collect();
+        wrap_cl_error!(wait_for_events(&events), "Failed to wait for profiled events")?;
+        let mut kernels = self.kernels.borrow_mut();
+        for (name, event) in pending {
+            let start = wrap_cl_error!(event.profiling_command_start(), "Failed to get start of {name}")?;
+            let end = wrap_cl_error!(event.profiling_command_end(), "Failed to get end of {name}")?;
+            kernels
+                .entry(name)
+                .or_insert_with(|| KernelProfile::new(name))
+                .add(Duration::from_nanos(end.saturating_sub(start)));
+        }
+        Ok(())
+    }
+
+    /// waits for all enqueued kernels and reports their times since creation or the last [Profiler::reset]
+    pub fn report(&self) -> Result<ProfileReport> {
+        self.collect()?;
+        let mut kernels: Vec<KernelProfile> = self.kernels.borrow().values().cloned().collect();
+        kernels.sort_by(|a, b| b.total.cmp(&a.total).then(a.name.cmp(b.name)));
+        Ok(ProfileReport { kernels })
+    }
+
+    /// discards the times recorded so far
+    pub fn reset(&self) {
+        self.pending.borrow_mut().clear();
+        self.kernels.borrow_mut().clear();
+    }
+}
+
+/// The times of a kernel, measured from the start to the end of its execution on the device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelProfile {
+    /// the program and kernel, e.g. `GeMMProgram::gemm`
+    pub name: &'static str,
+    pub count: usize,
+    pub total: Duration,
+    pub mean: Duration,
+}
+
+impl KernelProfile {
+    fn new(name: &'static str) -> Self {
+        KernelProfile {
+            name,
+            count: 0,
+            total: Duration::ZERO,
+            mean: Duration::ZERO,
+        }
+    }
+
+    fn add(&mut self, time: Duration) {
+        self.count += 1;
+        self.total += time;
+        self.mean = self.total / self.count as u32;
+    }
+}
+
+/// The times of each kernel, with the kernels taking the most time in total first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileReport {
+    pub kernels: Vec<KernelProfile>,
+}
+
+impl ProfileReport {
+    /// the total time of all kernels
+    pub fn total(&self) -> Duration {
+        self.kernels.iter().map(|kernel| kernel.total).sum()
+    }
+}
+
+impl Display for ProfileReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let width = self
+            .kernels
+            .iter()
+            .map(|kernel| kernel.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(6);
+        writeln!(f, "{:<width$} {:>10} {:>14} {:>12}", "kernel", "count", "total", "mean")?;
+        for kernel in &self.kernels {
+            writeln!(
+                f,
+                "{:<width$} {:>10} {:>14} {:>12}",
+                kernel.name,
+                kernel.count,
+                format!("{:.3?}", kernel.total),
+                format!("{:.3?}", kernel.mean)
+            )?;
+        }
+        write!(
+            f,
+            "{:<width$} {:>10} {:>14}",
+            "total",
+            "",
+            format!("{:.3?}", self.total())
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::kernels::transpose::TransposeProgram;
+    use crate::kernels::BUFFER_BLOCK_SIZE;
+    use crate::tensor::OclTensor2;
+    use crate::util::{self, ProgramCache, Profiler, Result};
+    use rcann::tensor::{Dim2, Tensor2};
+    use std::rc::Rc;
+
+    #[test]
+    fn test_profile_kernels() -> Result<()> {
+        let device = util::get_default_device()?;
+        let context = util::get_context(&device)?;
+        let queue = util::create_profiling_queue(&context)?;
+        let profiler = Rc::new(Profiler::default());
+        let cache = ProgramCache::new(None, Some(profiler.clone()));
+        let program = TransposeProgram::<f32>::get_or_create(&context, &cache, BUFFER_BLOCK_SIZE)?;
+
+        let input = OclTensor2::from_native(&context, &queue, &Tensor2::<f32>::zeroed(Dim2(50, 30)))?;
+        let mut output = OclTensor2::zeroed(&context, &queue, Dim2(30, 50))?;
+        for _ in 0..3 {
+            program.transpose(&queue, &input, &mut output)?;
+        }
+        let report = profiler.report()?;
+        assert_eq!(report.kernels.len(), 1);
+        assert_eq!(report.kernels[0].name, "TransposeProgram::transpose");
+        assert_eq!(report.kernels[0].count, 3);
+        assert_eq!(report.kernels[0].mean, report.kernels[0].total / 3);
+        assert_eq!(report.total(), report.kernels[0].total);
+
+        profiler.reset();
+        assert_eq!(profiler.report()?.kernels, vec![]);
+        Ok(())
+    }
+}
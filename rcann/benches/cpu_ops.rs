@@ -0,0 +1,140 @@
+#[macro_use]
+extern crate bencher;
+
+use bencher::Bencher;
+use rcann::backend::{BackendOther, CpuBackend};
+use rcann::tensor::{Dim1, Tensor1, TensorBase};
+use rcann::util::bench::*;
+use std::iter::zip;
+
+// each op is compared against the equivalent scalar loop, the backend is single threaded so only
+// the vectorized kernels are measured
+
+macro_rules! impl_benches {
+    ($group:ident, $ty:ty) => {
+        mod $group {
+            use super::*;
+
+            pub fn sigmoid(bench: &mut Bencher) {
+                let backend = CpuBackend::<$ty>::new(0);
+                let [a, _, mut c] = get_square_matrices::<$ty>(SIZE_MD);
+                bench.iter(|| backend.sigmoid(a.view(), &mut c))
+            }
+
+            pub fn sigmoid_scalar(bench: &mut Bencher) {
+                let [a, _, mut c] = get_square_matrices::<$ty>(SIZE_MD);
+                bench.iter(|| {
+                    for (o, &a) in zip(c.as_mut(), a.as_ref()) {
+                        *o = 1.0 / (1.0 + (-a).exp());
+                    }
+                })
+            }
+
+            pub fn softmax(bench: &mut Bencher) {
+                let backend = CpuBackend::<$ty>::new(0);
+                let [a, _, mut c] = get_square_matrices::<$ty>(SIZE_MD);
+                bench.iter(|| backend.softmax(a.view(), &mut c))
+            }
+
+            pub fn softmax_scalar(bench: &mut Bencher) {
+                let [a, _, mut c] = get_square_matrices::<$ty>(SIZE_MD);
+                bench.iter(|| {
+                    for (o_row, a_row) in zip(
+                        c.as_mut().chunks_exact_mut(SIZE_MD),
+                        a.as_ref().chunks_exact(SIZE_MD),
+                    ) {
+                        let max = a_row.iter().copied().fold(<$ty>::NEG_INFINITY, <$ty>::max);
+                        let mut sum = 0.0;
+                        for (o, &a) in zip(o_row.iter_mut(), a_row) {
+                            *o = (a - max).exp();
+                            sum += *o;
+                        }
+                        for o in o_row {
+                            *o /= sum;
+                        }
+                    }
+                })
+            }
+
+            pub fn add_assign(bench: &mut Bencher) {
+                let backend = CpuBackend::<$ty>::new(0);
+                let [a, _, mut c] = get_square_matrices::<$ty>(SIZE_MD);
+                bench.iter(|| backend.add_assign(0.5, &a, 0.5, &mut c))
+            }
+
+            pub fn add_assign_scalar(bench: &mut Bencher) {
+                let [a, _, mut c] = get_square_matrices::<$ty>(SIZE_MD);
+                bench.iter(|| {
+                    for (&ai, ci) in zip(a.as_ref(), c.as_mut()) {
+                        *ci = 0.5 * ai + 0.5 * *ci;
+                    }
+                })
+            }
+
+            pub fn column_sum(bench: &mut Bencher) {
+                let backend = CpuBackend::<$ty>::new(0);
+                let [a, _, _] = get_square_matrices::<$ty>(SIZE_MD);
+                let mut sums = Tensor1::zeroed(Dim1(SIZE_MD));
+                bench.iter(|| backend.column_sum(1.0, &a, 0.0, &mut sums))
+            }
+
+            pub fn column_sum_scalar(bench: &mut Bencher) {
+                let [a, _, _] = get_square_matrices::<$ty>(SIZE_MD);
+                let mut sums = Tensor1::<$ty>::zeroed(Dim1(SIZE_MD));
+                bench.iter(|| {
+                    sums.as_mut().fill(0.0);
+                    for row in a.as_ref().chunks_exact(SIZE_MD) {
+                        for (s, &ai) in zip(sums.as_mut(), row) {
+                            *s += ai;
+                        }
+                    }
+                })
+            }
+
+            pub fn mean_squared_error(bench: &mut Bencher) {
+                let backend = CpuBackend::<$ty>::new(0);
+                let [a, b, mut c] = get_square_matrices::<$ty>(SIZE_MD);
+                let mut result = Tensor1::zeroed(Dim1(SIZE_MD));
+                bench.iter(|| backend.mean_squared_error(&a, b.view(), &mut result, &mut c))
+            }
+
+            pub fn mean_squared_error_scalar(bench: &mut Bencher) {
+                let [a, b, mut c] = get_square_matrices::<$ty>(SIZE_MD);
+                let mut result = Tensor1::<$ty>::zeroed(Dim1(SIZE_MD));
+                bench.iter(|| {
+                    let rows = zip(
+                        c.as_mut().chunks_exact_mut(SIZE_MD),
+                        zip(a.as_ref().chunks_exact(SIZE_MD), b.as_ref().chunks_exact(SIZE_MD)),
+                    );
+                    for (r, (c_row, (a_row, b_row))) in zip(result.as_mut(), rows) {
+                        let mut sum = 0.0;
+                        for (ci, (&ai, &bi)) in zip(c_row, zip(a_row, b_row)) {
+                            *ci = ai - bi;
+                            sum += *ci * *ci;
+                        }
+                        *r = sum / SIZE_MD as $ty;
+                    }
+                })
+            }
+        }
+
+        benchmark_group!(
+            $group,
+            $group::sigmoid,
+            $group::sigmoid_scalar,
+            $group::softmax,
+            $group::softmax_scalar,
+            $group::add_assign,
+            $group::add_assign_scalar,
+            $group::column_sum,
+            $group::column_sum_scalar,
+            $group::mean_squared_error,
+            $group::mean_squared_error_scalar
+        );
+    };
+}
+
+impl_benches!(cpu_ops_f32, f32);
+impl_benches!(cpu_ops_f64, f64);
+
+benchmark_main!(cpu_ops_f32, cpu_ops_f64);
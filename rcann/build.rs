@@ -0,0 +1,8 @@
+fn main() {
+    // the `blas` feature links a system CBLAS library, OpenBLAS unless another is named
+    println!("cargo:rerun-if-env-changed=RCANN_BLAS_LIB");
+    if std::env::var_os("CARGO_FEATURE_BLAS").is_some() {
+        let lib = std::env::var("RCANN_BLAS_LIB").unwrap_or_else(|_| "openblas".to_string());
+        println!("cargo:rustc-link-lib={lib}");
+    }
+}
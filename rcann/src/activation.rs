@@ -4,6 +4,15 @@ use crate::tensor::Dim2;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+/// `sqrt(2 / pi)`, used by the tanh approximation of GELU
+pub const GELU_C: f64 = 0.7978845608028654;
+/// the coefficient of the cubic term in the tanh approximation of GELU
+pub const GELU_K: f64 = 0.044715;
+/// the fixed `alpha` of SELU, which makes it self-normalizing
+pub const SELU_ALPHA: f64 = 1.6732632423543772;
+/// the fixed `scale` of SELU, which makes it self-normalizing
+pub const SELU_SCALE: f64 = 1.0507009873554805;
+
 #[derive(Copy, Clone, Debug, Default, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ActivationFn {
@@ -13,21 +22,45 @@ pub enum ActivationFn {
         leak: f64,
     },
     Softmax,
+    Tanh,
+    ELU {
+        alpha: f64,
+    },
+    SELU,
+    /// the tanh approximation of GELU
+    GELU,
+    /// `x * sigmoid(x)`, also known as SiLU
+    Swish,
+    Softplus,
+    /// passes the activation through unchanged, for linear outputs such as regression targets or logits
+    Identity,
 }
 
 impl ActivationFn {
-    pub fn compute<B: Backend>(&self, backend: &B, activation: &B::Tensor<Dim2>, output: &mut B::Tensor<Dim2>) {
+    pub fn compute<B: Backend>(&self, backend: &B, activation: B::TensorRef<'_, Dim2>, output: &mut B::Tensor<Dim2>) {
         match self {
             ActivationFn::Sigmoid => backend.sigmoid(activation, output),
             &ActivationFn::ReLU { leak } => backend.relu(B::Float::from_f64(leak), activation, output),
             ActivationFn::Softmax => backend.softmax(activation, output),
+            ActivationFn::Tanh => backend.tanh(activation, output),
+            &ActivationFn::ELU { alpha } => backend.elu(B::Float::from_f64(alpha), B::Float::ONE, activation, output),
+            ActivationFn::SELU => backend.elu(
+                B::Float::from_f64(SELU_ALPHA),
+                B::Float::from_f64(SELU_SCALE),
+                activation,
+                output,
+            ),
+            ActivationFn::GELU => backend.gelu(activation, output),
+            ActivationFn::Swish => backend.swish(activation, output),
+            ActivationFn::Softplus => backend.softplus(activation, output),
+            ActivationFn::Identity => backend.identity(activation, output),
         }
     }
 
     pub fn compute_error<B: Backend>(
         &self,
         backend: &B,
-        activation: &B::Tensor<Dim2>,
+        activation: B::TensorRef<'_, Dim2>,
         output: &B::Tensor<Dim2>,
         out_error: &B::Tensor<Dim2>,
         result: &mut B::Tensor<Dim2>,
@@ -36,6 +69,82 @@ impl ActivationFn {
             ActivationFn::Sigmoid => backend.sigmoid_error(output, out_error, result),
             &ActivationFn::ReLU { leak } => backend.relu_error(B::Float::from_f64(leak), activation, out_error, result),
             ActivationFn::Softmax => backend.softmax_error(output, out_error, result),
+            ActivationFn::Tanh => backend.tanh_error(output, out_error, result),
+            &ActivationFn::ELU { alpha } => {
+                backend.elu_error(B::Float::from_f64(alpha), B::Float::ONE, activation, out_error, result)
+            }
+            ActivationFn::SELU => backend.elu_error(
+                B::Float::from_f64(SELU_ALPHA),
+                B::Float::from_f64(SELU_SCALE),
+                activation,
+                out_error,
+                result,
+            ),
+            ActivationFn::GELU => backend.gelu_error(activation, out_error, result),
+            ActivationFn::Swish => backend.swish_error(activation, out_error, result),
+            ActivationFn::Softplus => backend.softplus_error(activation, out_error, result),
+            ActivationFn::Identity => backend.identity_error(out_error, result),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::ActivationFn;
+    use crate::backend::CpuBackend;
+    use crate::tensor::{Dim2, ITensor, Tensor2, TensorBase};
+
+    const ACTIVATION_FNS: [ActivationFn; 10] = [
+        ActivationFn::Sigmoid,
+        ActivationFn::ReLU { leak: 0.1 },
+        ActivationFn::Tanh,
+        ActivationFn::ELU { alpha: 0.8 },
+        ActivationFn::SELU,
+        ActivationFn::GELU,
+        ActivationFn::Swish,
+        ActivationFn::Softplus,
+        ActivationFn::Identity,
+        ActivationFn::Softmax,
+    ];
+
+    /// checks each derivative against finite differences of `sum(f(activation) * out_error)`
+    #[test]
+    fn test_activation_gradients() {
+        let backend = CpuBackend::<f64>::new(0);
+        let dims = Dim2(2, 5);
+        let activation = Tensor2::from_vec(vec![-3.0, -1.2, -0.3, 0.4, 2.5, -0.7, 0.1, 0.9, 1.7, -2.2], dims);
+        let out_error = Tensor2::from_vec(vec![0.5, -1.0, 0.25, 2.0, -0.75, 1.5, -0.5, 0.3, -1.2, 0.8], dims);
+        for activation_fn in ACTIVATION_FNS {
+            let loss = |activation: &Tensor2<f64>| {
+                let mut output = Tensor2::zeroed(dims);
+                activation_fn.compute(&backend, activation.view(), &mut output);
+                output.iter().zip(out_error.iter()).map(|(o, e)| o * e).sum::<f64>()
+            };
+            let mut output = Tensor2::zeroed(dims);
+            let mut result = Tensor2::zeroed(dims);
+            activation_fn.compute(&backend, activation.view(), &mut output);
+            activation_fn.compute_error(&backend, activation.view(), &output, &out_error, &mut result);
+
+            const H: f64 = 1e-6;
+            for i in 0..activation.len() {
+                let (mut plus, mut minus) = (activation.clone(), activation.clone());
+                plus[i] += H;
+                minus[i] -= H;
+                let expected = (loss(&plus) - loss(&minus)) / (2.0 * H);
+                assert!(
+                    (expected - result[i]).abs() < 1e-5,
+                    "{activation_fn:?} gradient {i}: {expected} != {}",
+                    result[i]
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let json = serde_json::to_string(&ACTIVATION_FNS).unwrap();
+        let parsed: Vec<ActivationFn> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, ACTIVATION_FNS);
+    }
+}
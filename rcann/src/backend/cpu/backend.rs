@@ -1,31 +1,88 @@
-use super::math::{compute_jacobian_matrix, DTypeOps};
+use super::math::{for_each_channel_window_index, for_each_window_index, DTypeOps};
+use crate::activation::{GELU_C, GELU_K};
 use crate::backend::cpu::math::argmax;
-use crate::backend::{Backend, BackendOther, MatrixMultiplication, TensorOps, TensorTyped};
+use crate::backend::{Backend, BackendOther, DropoutMask, MatrixMultiplication, TensorOps, TensorTyped, Window2d};
+use crate::dtype::DType;
+use crate::loss::CROSS_ENTROPY_EPSILON;
 use crate::tensor::{
     Dim2, Dims, DimsMore, ITensor, Tensor, Tensor1, Tensor2, TensorBase, TensorBaseMut, TensorView, TensorView2,
 };
-use std::cell::RefCell;
-use std::cmp::Ordering;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::convert::Infallible;
 use std::fmt::{Debug, Formatter, Write};
 use std::iter::zip;
-use std::ops::{Deref, DerefMut};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// The number of elements below which ops run on the calling thread, since splitting up small tensors
+/// costs more than it saves
+const MIN_PARALLEL_LEN: usize = 1 << 14;
 
 pub struct CpuBackend<DT: DTypeOps> {
     max_batch_size: usize,
-    temp_matrix: RefCell<Tensor2<DT>>,
+    /// used by the element-wise and reduction ops when set, matmul is threaded separately by matrixmultiply
+    thread_pool: Option<Arc<ThreadPool>>,
+    _dtype: PhantomData<DT>,
 }
 
 impl<DT: DTypeOps> CpuBackend<DT> {
+    /// creates a backend which runs all ops other than matmul on the calling thread, see [CpuBackend::with_threads]
     pub fn new(max_batch_size: usize) -> Self {
         CpuBackend {
             max_batch_size,
-            temp_matrix: RefCell::new(Tensor2::empty()),
+            thread_pool: None,
+            _dtype: PhantomData,
+        }
+    }
+
+    /// runs the element-wise and reduction ops of this backend on a pool of `num_threads` threads, or one thread
+    /// per core if `num_threads` is zero. A single thread runs the ops on the calling thread, and so does the
+    /// backend if the threads can't be spawned, which [CpuBackend::num_threads] then reports as 1.
+    pub fn with_threads(self, num_threads: usize) -> Self {
+        let thread_pool = (num_threads != 1)
+            .then(|| {
+                ThreadPoolBuilder::new()
+                    .num_threads(num_threads)
+                    .thread_name(|idx| format!("rcann-cpu-{idx}"))
+                    .build()
+                    .ok()
+            })
+            .flatten()
+            .map(Arc::new);
+        CpuBackend { thread_pool, ..self }
+    }
+
+    /// the number of threads used by the element-wise and reduction ops
+    pub fn num_threads(&self) -> usize {
+        self.thread_pool.as_ref().map_or(1, |pool| pool.current_num_threads())
+    }
+
+    /// calls `f(offset, chunk)` for contiguous chunks of `output`, in parallel when a thread pool is configured.
+    /// The length of each chunk is a multiple of `align`, so row-wise ops can pass the row length.
+    fn for_each_chunk<F>(&self, output: &mut [DT], align: usize, f: F)
+    where
+        F: Fn(usize, &mut [DT]) + Send + Sync,
+    {
+        match &self.thread_pool {
+            Some(pool) if output.len() >= MIN_PARALLEL_LEN => {
+                let align = align.max(1);
+                let chunk_len = output.len().div_ceil(align).div_ceil(pool.current_num_threads()) * align;
+                pool.install(|| {
+                    output
+                        .par_chunks_mut(chunk_len)
+                        .enumerate()
+                        .for_each(|(idx, chunk)| f(idx * chunk_len, chunk))
+                });
+            }
+            _ => f(0, output),
         }
     }
 }
 
 impl<DT: DTypeOps> TensorTyped for CpuBackend<DT> {
     type Float = DT;
+    type Error = Infallible;
     type TensorRef<'a, D: Dims> = TensorView<'a, DT, D>;
     type Tensor<D: Dims> = Tensor<DT, D>;
     type InputAdaptionBuff<D: Dims> = ();
@@ -34,12 +91,12 @@ impl<DT: DTypeOps> TensorTyped for CpuBackend<DT> {
 
 impl<DT: DTypeOps> TensorOps for CpuBackend<DT> {
     #[inline]
-    fn new_tensor_exact<D: Dims>(&self, dim: D) -> Tensor<DT, D> {
-        Tensor::zeroed(dim)
+    fn try_new_tensor_exact<D: Dims>(&self, dim: D) -> Result<Tensor<DT, D>, Infallible> {
+        Ok(Tensor::zeroed(dim))
     }
 
-    fn new_tensor_batch_sized<D: DimsMore>(&self, inner_dims: D) -> Tensor<DT, D::More> {
-        Tensor::zeroed(inner_dims.insert_major(self.max_batch_size))
+    fn try_new_tensor_batch_sized<D: DimsMore>(&self, inner_dims: D) -> Result<Tensor<DT, D::More>, Infallible> {
+        Ok(Tensor::zeroed(inner_dims.insert_major(self.max_batch_size)))
     }
 
     #[inline]
@@ -47,45 +104,63 @@ impl<DT: DTypeOps> TensorOps for CpuBackend<DT> {
         tensor.resize_within_capacity(DT::ZERO, dims)
     }
 
-    fn write_tensor<T, D>(&self, tensor: &mut Tensor<DT, D>, native_src: &T)
+    fn try_write_tensor<T, D>(&self, tensor: &mut Tensor<DT, D>, native_src: &T) -> Result<(), Infallible>
     where
         T: TensorBase<Self::Float, D>,
         D: Dims,
     {
         assert_eq!(tensor.dims(), native_src.dims());
         tensor.as_mut().copy_from_slice(native_src.as_ref());
+        Ok(())
     }
-    fn read_tensor<T, D>(&self, tensor: &Tensor<DT, D>, native_dst: &mut T)
+    fn try_read_tensor<T, D>(&self, tensor: &Tensor<DT, D>, native_dst: &mut T) -> Result<(), Infallible>
     where
         T: TensorBaseMut<Self::Float, D>,
         D: Dims,
     {
         assert_eq!(tensor.dims(), native_dst.dims());
         native_dst.as_mut().copy_from_slice(tensor.as_ref());
+        Ok(())
+    }
+
+    #[inline]
+    fn take_error(&self) -> Result<(), Infallible> {
+        Ok(())
     }
+
     #[inline]
-    fn new_tensor_from_native<T, D>(&self, native: T) -> Tensor<DT, D>
+    fn try_new_tensor_from_native<T, D>(&self, native: T) -> Result<Tensor<DT, D>, Infallible>
     where
         T: TensorBase<Self::Float, D>,
         D: Dims,
     {
-        native.into_owned()
+        Ok(native.into_owned())
     }
 
     #[inline]
-    fn new_input_adaption_buff<D: DimsMore>(&self, _inner_dims: D) -> () {}
+    fn try_new_input_adaption_buff<D: DimsMore>(&self, _inner_dims: D) -> Result<(), Infallible> {
+        Ok(())
+    }
 
     #[inline]
     fn new_output_adaption_buff<D: DimsMore>(&self, _inner_dims: D) -> () {}
 
     #[inline]
-    fn adapt_input<'a, D: Dims>(&self, _buff: &'a mut (), input: TensorView<'a, DT, D>) -> TensorView<'a, DT, D> {
-        input
+    fn try_adapt_input<'a, D: Dims>(
+        &self,
+        _buff: &'a mut (),
+        input: TensorView<'a, DT, D>,
+    ) -> Result<TensorView<'a, DT, D>, Infallible> {
+        Ok(input)
     }
 
     #[inline]
-    fn adapt_output<'a, D: Dims>(&self, _buff: &'a mut (), output: &'a Tensor<DT, D>) -> &'a Tensor<DT, D> {
-        output
+    fn try_adapt_output<'a, D: Dims>(
+        &self,
+        _buff: &'a mut (),
+        output: &'a Tensor<DT, D>,
+    ) -> Result<&'a Tensor<DT, D>, Infallible> {
+        Ok(output)
     }
 
     fn debug_tensor<D: Dims>(&self, tensor: &Self::Tensor<D>) {
@@ -118,19 +193,28 @@ impl<DT: DTypeOps> BackendOther for CpuBackend<DT> {
     fn column_sum(&self, alpha: DT, a: &Tensor2<DT>, beta: DT, b: &mut Tensor1<DT>) {
         let &Dim2(rows, cols) = a.dims();
         assert_eq!(b.len(), cols);
-        let pa = a.as_ref().as_ptr();
-        let pb = b.as_mut().as_mut_ptr();
-        let r = rows as isize;
-        let c = cols as isize;
-        for i in 0..c {
-            let mut sum = DT::ZERO;
-            for j in 0..r {
-                sum += unsafe { *pa.offset(j * c + i) };
-            }
-            unsafe {
-                let p = pb.offset(i);
-                *p = sum * alpha + *p * beta;
+        if cols == 0 {
+            return;
+        }
+        let a = a.as_ref();
+        // each thread sums a block of rows, then the partial sums are added up
+        let sums = match &self.thread_pool {
+            Some(pool) if a.len() >= MIN_PARALLEL_LEN => {
+                let chunk_rows = rows.div_ceil(pool.current_num_threads());
+                pool.install(|| {
+                    a.par_chunks(chunk_rows * cols)
+                        .map(|chunk| column_sums(chunk, cols))
+                        .reduce_with(|mut sums, other| {
+                            DT::add_slice(&other, &mut sums);
+                            sums
+                        })
+                        .unwrap()
+                })
             }
+            _ => column_sums(a, cols),
+        };
+        for (bi, sum) in zip(b.as_mut(), sums) {
+            *bi = sum * alpha + *bi * beta;
         }
     }
 
@@ -140,18 +224,69 @@ impl<DT: DTypeOps> BackendOther for CpuBackend<DT> {
         D: Dims,
     {
         assert_eq!(a.dims(), b.dims());
-        for (&ai, bi) in zip(a, b) {
-            *bi = alpha * ai + beta * *bi;
+        let a = a.as_ref();
+        self.for_each_chunk(b.as_mut(), 1, |offset, b_chunk| {
+            DT::scale_add_slice(alpha, &a[offset..offset + b_chunk.len()], beta, b_chunk)
+        });
+    }
+
+    fn add_assign_row_broadcast(&self, alpha: DT, a: &Tensor1<DT>, beta: DT, b: &mut Tensor2<DT>) {
+        assert_eq!(a.len(), b.dims().cols());
+        for b_row in b.iter_major_axis_mut() {
+            for (&ai, bi) in zip(a, b_row) {
+                *bi = alpha * ai + beta * *bi;
+            }
         }
     }
 
-    fn sigmoid(&self, activation: &Tensor2<DT>, output: &mut Tensor2<DT>) {
-        assert_eq!(activation.dims(), output.dims());
-        for (o, &a) in zip(output, activation) {
-            *o = DT::ONE / (DT::ONE + (-a).exp());
+    fn adaptive_update<D: Dims>(
+        &self,
+        learn_rate: DT,
+        decay: DT,
+        scale: DT,
+        epsilon: DT,
+        grad: &Tensor<DT, D>,
+        accum: &mut Tensor<DT, D>,
+        params: &mut Tensor<DT, D>,
+    ) {
+        assert_eq!(grad.dims(), accum.dims());
+        assert_eq!(grad.dims(), params.dims());
+        for (p, (a, &g)) in zip(params, zip(accum, grad)) {
+            *a = decay * *a + scale * g * g;
+            *p -= learn_rate * g / (a.sqrt() + epsilon);
+        }
+    }
+
+    fn adam_update<D: Dims>(
+        &self,
+        step_size: DT,
+        beta1: DT,
+        beta2: DT,
+        epsilon: DT,
+        decay: DT,
+        grad: &Tensor<DT, D>,
+        m: &mut Tensor<DT, D>,
+        v: &mut Tensor<DT, D>,
+        params: &mut Tensor<DT, D>,
+    ) {
+        assert_eq!(grad.dims(), m.dims());
+        assert_eq!(grad.dims(), v.dims());
+        assert_eq!(grad.dims(), params.dims());
+        for (p, ((mi, vi), &g)) in zip(params, zip(zip(m, v), grad)) {
+            *mi = beta1 * *mi + (DT::ONE - beta1) * g;
+            *vi = beta2 * *vi + (DT::ONE - beta2) * g * g;
+            *p = (DT::ONE - decay) * *p - step_size * *mi / (vi.sqrt() + epsilon);
         }
     }
 
+    fn sigmoid(&self, activation: TensorView2<DT>, output: &mut Tensor2<DT>) {
+        assert_eq!(activation.dims(), output.dims());
+        let activation = activation.as_ref();
+        self.for_each_chunk(output.as_mut(), 1, |offset, output| {
+            DT::sigmoid_slice(&activation[offset..offset + output.len()], output)
+        });
+    }
+
     fn sigmoid_error(&self, output: &Tensor2<DT>, out_error: &Tensor2<DT>, result: &mut Tensor2<DT>) {
         assert_eq!(output.dims(), result.dims());
         assert_eq!(output.dims(), out_error.dims());
@@ -160,60 +295,154 @@ impl<DT: DTypeOps> BackendOther for CpuBackend<DT> {
         }
     }
 
-    fn relu(&self, leak: DT, activation: &Tensor2<DT>, output: &mut Tensor2<DT>) {
+    fn relu(&self, leak: DT, activation: TensorView2<DT>, output: &mut Tensor2<DT>) {
+        assert_eq!(activation.dims(), output.dims());
+        let activation = activation.as_ref();
+        self.for_each_chunk(output.as_mut(), 1, |offset, output| {
+            for (o, &a) in zip(output, &activation[offset..]) {
+                *o = if a < DT::ZERO { a * leak } else { a }
+            }
+        });
+    }
+
+    fn relu_error(
+        &self,
+        leak: DT,
+        activation: TensorView2<DT>,
+        out_error: &Tensor2<DT>,
+        result: &mut Tensor2<DT>,
+    ) {
+        assert_eq!(activation.dims(), result.dims());
+        assert_eq!(activation.dims(), out_error.dims());
+        for ((r, &act), &err) in zip(zip(result, activation), out_error) {
+            *r = if act < DT::ZERO { leak * err } else { err };
+        }
+    }
+
+    fn tanh(&self, activation: TensorView2<DT>, output: &mut Tensor2<DT>) {
+        assert_eq!(activation.dims(), output.dims());
+        for (o, &a) in zip(output, activation) {
+            *o = a.tanh();
+        }
+    }
+
+    fn tanh_error(&self, output: &Tensor2<DT>, out_error: &Tensor2<DT>, result: &mut Tensor2<DT>) {
+        assert_eq!(output.dims(), result.dims());
+        assert_eq!(output.dims(), out_error.dims());
+        for ((r, &out), &err) in zip(zip(result, output), out_error) {
+            *r = err * (DT::ONE - out * out);
+        }
+    }
+
+    fn elu(&self, alpha: DT, scale: DT, activation: TensorView2<DT>, output: &mut Tensor2<DT>) {
         assert_eq!(activation.dims(), output.dims());
         for (o, &a) in zip(output, activation) {
-            *o = if a < DT::ZERO { a * leak } else { a }
+            *o = scale * if a < DT::ZERO { alpha * a.exp_m1() } else { a };
         }
     }
 
-    fn relu_error(&self, leak: DT, activation: &Tensor2<DT>, out_error: &Tensor2<DT>, result: &mut Tensor2<DT>) {
+    fn elu_error(
+        &self,
+        alpha: DT,
+        scale: DT,
+        activation: TensorView2<DT>,
+        out_error: &Tensor2<DT>,
+        result: &mut Tensor2<DT>,
+    ) {
         assert_eq!(activation.dims(), result.dims());
         assert_eq!(activation.dims(), out_error.dims());
         for ((r, &act), &err) in zip(zip(result, activation), out_error) {
-            *r = if act < DT::ZERO { leak * err } else { err };
+            *r = err * scale * if act < DT::ZERO { alpha * act.exp() } else { DT::ONE };
         }
     }
 
-    fn softmax(&self, activation: &Tensor2<DT>, output: &mut Tensor2<DT>) {
+    fn gelu(&self, activation: TensorView2<DT>, output: &mut Tensor2<DT>) {
         assert_eq!(activation.dims(), output.dims());
-        for (mut output_row, activation_row) in zip(output.iter_major_axis_mut(), activation.iter_major_axis()) {
-            // shift the values by -max(inputs) to prevent overflow (does not affect derivative)
-            let max = *activation_row
-                .iter()
-                .max_by(|&a, &b| if a > b { Ordering::Greater } else { Ordering::Less })
-                .unwrap();
-            let mut sum = DT::ZERO;
-            for (t, &a) in zip(output_row.iter_mut(), activation_row) {
-                let x = (a - max).exp();
-                sum += x;
-                *t = x;
-            }
-            for t in output_row.iter_mut() {
-                *t /= sum
-            }
+        let (c, k, half) = (DT::from_f64(GELU_C), DT::from_f64(GELU_K), DT::from_f64(0.5));
+        for (o, &a) in zip(output, activation) {
+            *o = half * a * (DT::ONE + (c * (a + k * a * a * a)).tanh());
+        }
+    }
+
+    fn gelu_error(&self, activation: TensorView2<DT>, out_error: &Tensor2<DT>, result: &mut Tensor2<DT>) {
+        assert_eq!(activation.dims(), result.dims());
+        assert_eq!(activation.dims(), out_error.dims());
+        let (c, k, half) = (DT::from_f64(GELU_C), DT::from_f64(GELU_K), DT::from_f64(0.5));
+        let k3 = DT::from_f64(3.0 * GELU_K);
+        for ((r, &act), &err) in zip(zip(result, activation), out_error) {
+            let t = (c * (act + k * act * act * act)).tanh();
+            *r = err * half * (DT::ONE + t + act * (DT::ONE - t * t) * c * (DT::ONE + k3 * act * act));
+        }
+    }
+
+    fn swish(&self, activation: TensorView2<DT>, output: &mut Tensor2<DT>) {
+        assert_eq!(activation.dims(), output.dims());
+        let activation = activation.as_ref();
+        self.for_each_chunk(output.as_mut(), 1, |offset, output| {
+            DT::swish_slice(&activation[offset..offset + output.len()], output)
+        });
+    }
+
+    fn swish_error(&self, activation: TensorView2<DT>, out_error: &Tensor2<DT>, result: &mut Tensor2<DT>) {
+        assert_eq!(activation.dims(), result.dims());
+        assert_eq!(activation.dims(), out_error.dims());
+        for ((r, &act), &err) in zip(zip(result, activation), out_error) {
+            let s = DT::ONE / (DT::ONE + (-act).exp());
+            *r = err * s * (DT::ONE + act * (DT::ONE - s));
+        }
+    }
+
+    fn softplus(&self, activation: TensorView2<DT>, output: &mut Tensor2<DT>) {
+        assert_eq!(activation.dims(), output.dims());
+        for (o, &a) in zip(output, activation) {
+            // rearranged to avoid overflowing exp for large inputs
+            *o = a.max(DT::ZERO) + (-a.abs()).exp().ln_1p();
         }
     }
 
+    fn softplus_error(&self, activation: TensorView2<DT>, out_error: &Tensor2<DT>, result: &mut Tensor2<DT>) {
+        assert_eq!(activation.dims(), result.dims());
+        assert_eq!(activation.dims(), out_error.dims());
+        for ((r, &act), &err) in zip(zip(result, activation), out_error) {
+            *r = err / (DT::ONE + (-act).exp());
+        }
+    }
+
+    fn identity(&self, activation: TensorView2<DT>, output: &mut Tensor2<DT>) {
+        assert_eq!(activation.dims(), output.dims());
+        output.as_mut().copy_from_slice(activation.as_ref());
+    }
+
+    fn identity_error(&self, out_error: &Tensor2<DT>, result: &mut Tensor2<DT>) {
+        assert_eq!(out_error.dims(), result.dims());
+        result.as_mut().copy_from_slice(out_error.as_ref());
+    }
+
+    fn softmax(&self, activation: TensorView2<DT>, output: &mut Tensor2<DT>) {
+        assert_eq!(activation.dims(), output.dims());
+        let cols = activation.dims().cols();
+        let activation = activation.as_ref();
+        self.for_each_chunk(output.as_mut(), cols, |offset, output| {
+            let activation = &activation[offset..];
+            for (output_row, activation_row) in zip(output.chunks_exact_mut(cols), activation.chunks_exact(cols)) {
+                DT::softmax_slice(activation_row, output_row);
+            }
+        });
+    }
+
     fn softmax_error(&self, output: &Tensor2<DT>, out_error: &Tensor2<DT>, result: &mut Tensor2<DT>) {
-        let size = output.dims().cols();
         assert_eq!(output.dims(), result.dims());
-        let mut temp = self.temp_matrix.borrow_mut();
-        temp.resize(DT::ZERO, Dim2(size, size));
+        assert_eq!(output.dims(), out_error.dims());
+        // the vector-Jacobian product of softmax simplifies to output * (out_error - dot(output, out_error)),
+        // which avoids building the full Jacobian of each row
         for (mut result_row, (output_row, out_err_row)) in zip(
             result.iter_major_axis_mut(),
             zip(output.iter_major_axis(), out_error.iter_major_axis()),
         ) {
-            compute_jacobian_matrix(output_row.as_ref(), temp.deref_mut());
-            DT::matrix_multiply(
-                DT::ONE,
-                &out_err_row.as_row_matrix(),
-                false,
-                temp.deref(),
-                false,
-                DT::ZERO,
-                &mut result_row.as_row_matrix_mut(),
-            );
+            let dot = zip(output_row.iter(), out_err_row.iter()).fold(DT::ZERO, |acc, (&o, &e)| acc + o * e);
+            for (r, (&o, &e)) in zip(result_row.iter_mut(), zip(output_row.iter(), out_err_row.iter())) {
+                *r = o * (e - dot);
+            }
         }
     }
 
@@ -227,6 +456,79 @@ impl<DT: DTypeOps> BackendOther for CpuBackend<DT> {
         debug_assert_eq!(output.dims().rows(), result.len());
         debug_assert_eq!(output.dims(), expected.dims());
         debug_assert_eq!(output.dims(), result_deriv.dims());
+        let cols = output.dims().cols();
+        if cols == 0 {
+            return;
+        }
+        let (output, expected) = (output.as_ref(), expected.as_ref());
+        let count = DT::from_usize(cols);
+        let mse_rows = |row_offset: usize, result: &mut [DT], result_deriv: &mut [DT]| {
+            let offset = row_offset * cols;
+            for (r, (rd_row, (o_row, e_row))) in zip(
+                result,
+                zip(
+                    result_deriv.chunks_exact_mut(cols),
+                    zip(output[offset..].chunks_exact(cols), expected[offset..].chunks_exact(cols)),
+                ),
+            ) {
+                *r = DT::squared_error_slice(o_row, e_row, rd_row) / count;
+            }
+        };
+        match &self.thread_pool {
+            Some(pool) if output.len() >= MIN_PARALLEL_LEN => {
+                let chunk_rows = result.len().div_ceil(pool.current_num_threads());
+                pool.install(|| {
+                    result
+                        .as_mut()
+                        .par_chunks_mut(chunk_rows)
+                        .zip(result_deriv.as_mut().par_chunks_mut(chunk_rows * cols))
+                        .enumerate()
+                        .for_each(|(idx, (result, result_deriv))| mse_rows(idx * chunk_rows, result, result_deriv))
+                });
+            }
+            _ => mse_rows(0, result.as_mut(), result_deriv.as_mut()),
+        }
+    }
+
+    fn categorical_cross_entropy(
+        &self,
+        output: &Tensor2<DT>,
+        expected: TensorView2<DT>,
+        result: &mut Tensor1<DT>,
+        result_deriv: &mut Tensor2<DT>,
+    ) {
+        debug_assert_eq!(output.dims().rows(), result.len());
+        debug_assert_eq!(output.dims(), expected.dims());
+        debug_assert_eq!(output.dims(), result_deriv.dims());
+        let epsilon = DT::from_f64(CROSS_ENTROPY_EPSILON);
+        for (r, (rd_row, (o_row, e_row))) in zip(
+            result,
+            zip(
+                result_deriv.iter_major_axis_mut(),
+                zip(output.iter_major_axis(), expected.iter_major_axis()),
+            ),
+        ) {
+            let mut sum_error = DT::ZERO;
+            for (rd, (&o, &e)) in zip(rd_row, zip(o_row, e_row)) {
+                let o = o.max(epsilon);
+                *rd = -e / o;
+                sum_error -= e * o.ln();
+            }
+            *r = sum_error;
+        }
+    }
+
+    fn binary_cross_entropy(
+        &self,
+        output: &Tensor2<DT>,
+        expected: TensorView2<DT>,
+        result: &mut Tensor1<DT>,
+        result_deriv: &mut Tensor2<DT>,
+    ) {
+        debug_assert_eq!(output.dims().rows(), result.len());
+        debug_assert_eq!(output.dims(), expected.dims());
+        debug_assert_eq!(output.dims(), result_deriv.dims());
+        let epsilon = DT::from_f64(CROSS_ENTROPY_EPSILON);
         for (r, (rd_row, (o_row, e_row))) in zip(
             result,
             zip(
@@ -237,15 +539,439 @@ impl<DT: DTypeOps> BackendOther for CpuBackend<DT> {
             let mut sum_error = DT::ZERO;
             let count = DT::from_usize(rd_row.len());
             for (rd, (&o, &e)) in zip(rd_row, zip(o_row, e_row)) {
-                let diff = o - e;
-                *rd = diff;
-                sum_error += diff * diff;
+                let o = o.max(epsilon).min(DT::ONE - epsilon);
+                *rd = (o - e) / (o * (DT::ONE - o));
+                sum_error -= e * o.ln() + (DT::ONE - e) * (DT::ONE - o).ln();
             }
             *r = sum_error / count;
         }
     }
 
+    fn softmax_cross_entropy(
+        &self,
+        output: &Tensor2<DT>,
+        expected: TensorView2<DT>,
+        result: &mut Tensor1<DT>,
+        result_deriv: &mut Tensor2<DT>,
+    ) {
+        debug_assert_eq!(output.dims().rows(), result.len());
+        debug_assert_eq!(output.dims(), expected.dims());
+        debug_assert_eq!(output.dims(), result_deriv.dims());
+        for (r, (rd_row, (o_row, e_row))) in zip(
+            result,
+            zip(
+                result_deriv.iter_major_axis_mut(),
+                zip(output.iter_major_axis(), expected.iter_major_axis()),
+            ),
+        ) {
+            // log(sum(exp(o))), shifted by max(o) to prevent overflow
+            let max = o_row.iter().fold(DT::neg_infinity(), |max, &o| max.max(o));
+            let mut sum_exp = DT::ZERO;
+            let mut sum_expected = DT::ZERO;
+            for (&o, &e) in zip(o_row.iter(), e_row.iter()) {
+                sum_exp += (o - max).exp();
+                sum_expected += e;
+            }
+            let log_sum_exp = max + sum_exp.ln();
+            let mut sum_error = DT::ZERO;
+            for (rd, (&o, &e)) in zip(rd_row, zip(o_row, e_row)) {
+                *rd = (o - log_sum_exp).exp() * sum_expected - e;
+                sum_error += e * (log_sum_exp - o);
+            }
+            *r = sum_error;
+        }
+    }
+
+    fn conv2d(
+        &self,
+        window: &Window2d,
+        input: TensorView2<DT>,
+        kernel: &Tensor2<DT>,
+        bias: Option<&Tensor1<DT>>,
+        output: &mut Tensor2<DT>,
+    ) {
+        let filters = kernel.dims().rows();
+        let out_len = window.output_height() * window.output_width();
+        let out_w = window.output_width();
+        assert_eq!(input.dims().cols(), window.input_dims.tensor_len());
+        assert_eq!(kernel.dims().cols(), window.patch_len());
+        assert_eq!(output.dims(), &Dim2(input.dims().rows(), filters * out_len));
+        if let Some(bias) = bias {
+            assert_eq!(bias.len(), filters);
+        }
+        for (in_row, mut out_row) in zip(input.iter_major_axis(), output.iter_major_axis_mut()) {
+            for (f, out_plane) in out_row.as_mut().chunks_exact_mut(out_len).enumerate() {
+                let kernel_row = &kernel.as_ref()[f * window.patch_len()..(f + 1) * window.patch_len()];
+                let b = bias.map_or(DT::ZERO, |b| b[f]);
+                for (i, o) in out_plane.iter_mut().enumerate() {
+                    let mut sum = b;
+                    for_each_window_index(window, i / out_w, i % out_w, |k, x| sum += kernel_row[k] * in_row[x]);
+                    *o = sum;
+                }
+            }
+        }
+    }
+
+    fn conv2d_input_error(
+        &self,
+        window: &Window2d,
+        out_error: &Tensor2<DT>,
+        kernel: &Tensor2<DT>,
+        input_error: &mut Tensor2<DT>,
+    ) {
+        let filters = kernel.dims().rows();
+        let out_len = window.output_height() * window.output_width();
+        let out_w = window.output_width();
+        assert_eq!(kernel.dims().cols(), window.patch_len());
+        assert_eq!(out_error.dims(), &Dim2(input_error.dims().rows(), filters * out_len));
+        assert_eq!(input_error.dims().cols(), window.input_dims.tensor_len());
+        input_error.iter_mut().for_each(|v| *v = DT::ZERO);
+        for (err_row, mut in_err_row) in zip(out_error.iter_major_axis(), input_error.iter_major_axis_mut()) {
+            let in_err_row = in_err_row.as_mut();
+            for (f, err_plane) in err_row.as_ref().chunks_exact(out_len).enumerate() {
+                let kernel_row = &kernel.as_ref()[f * window.patch_len()..(f + 1) * window.patch_len()];
+                for (i, &e) in err_plane.iter().enumerate() {
+                    for_each_window_index(window, i / out_w, i % out_w, |k, x| in_err_row[x] += kernel_row[k] * e);
+                }
+            }
+        }
+    }
+
+    fn conv2d_param_grad(
+        &self,
+        window: &Window2d,
+        input: TensorView2<DT>,
+        out_error: &Tensor2<DT>,
+        kernel_grad: &mut Tensor2<DT>,
+        mut bias_grad: Option<&mut Tensor1<DT>>,
+    ) {
+        let filters = kernel_grad.dims().rows();
+        let out_len = window.output_height() * window.output_width();
+        let out_w = window.output_width();
+        assert_eq!(input.dims().cols(), window.input_dims.tensor_len());
+        assert_eq!(kernel_grad.dims().cols(), window.patch_len());
+        assert_eq!(out_error.dims(), &Dim2(input.dims().rows(), filters * out_len));
+        kernel_grad.iter_mut().for_each(|v| *v = DT::ZERO);
+        if let Some(bias_grad) = &mut bias_grad {
+            assert_eq!(bias_grad.len(), filters);
+            bias_grad.iter_mut().for_each(|v| *v = DT::ZERO);
+        }
+        let patch_len = window.patch_len();
+        for (in_row, err_row) in zip(input.iter_major_axis(), out_error.iter_major_axis()) {
+            for (f, err_plane) in err_row.as_ref().chunks_exact(out_len).enumerate() {
+                let grad_row = &mut kernel_grad.as_mut()[f * patch_len..(f + 1) * patch_len];
+                for (i, &e) in err_plane.iter().enumerate() {
+                    for_each_window_index(window, i / out_w, i % out_w, |k, x| grad_row[k] += in_row[x] * e);
+                }
+                if let Some(bias_grad) = &mut bias_grad {
+                    bias_grad[f] += err_plane.iter().fold(DT::ZERO, |acc, &e| acc + e);
+                }
+            }
+        }
+    }
+
+    fn max_pool2d(
+        &self,
+        window: &Window2d,
+        input: TensorView2<DT>,
+        output: &mut Tensor2<DT>,
+        indices: &mut Tensor2<DT>,
+    ) {
+        let out_dims = window.output_dims(window.channels());
+        let out_w = out_dims.2;
+        assert_eq!(input.dims().cols(), window.input_dims.tensor_len());
+        assert_eq!(output.dims(), &Dim2(input.dims().rows(), out_dims.tensor_len()));
+        assert_eq!(output.dims(), indices.dims());
+        let out_plane = out_w * out_dims.1;
+        for (in_row, (mut out_row, mut idx_row)) in zip(
+            input.iter_major_axis(),
+            zip(output.iter_major_axis_mut(), indices.iter_major_axis_mut()),
+        ) {
+            for (i, (o, idx)) in zip(out_row.iter_mut(), idx_row.iter_mut()).enumerate() {
+                let (c, j) = (i / out_plane, i % out_plane);
+                let mut max: Option<(usize, DT)> = None;
+                for_each_channel_window_index(window, c, j / out_w, j % out_w, |k, x| {
+                    if max.is_none_or(|(_, m)| in_row[x] > m) {
+                        max = Some((k, in_row[x]));
+                    }
+                });
+                let (k, m) = max.expect("window must cover at least one input");
+                *o = m;
+                *idx = DT::from_usize(k);
+            }
+        }
+    }
+
+    fn max_pool2d_error(
+        &self,
+        window: &Window2d,
+        indices: &Tensor2<DT>,
+        out_error: &Tensor2<DT>,
+        input_error: &mut Tensor2<DT>,
+    ) {
+        let out_dims = window.output_dims(window.channels());
+        let out_w = out_dims.2;
+        assert_eq!(out_error.dims(), &Dim2(input_error.dims().rows(), out_dims.tensor_len()));
+        assert_eq!(out_error.dims(), indices.dims());
+        assert_eq!(input_error.dims().cols(), window.input_dims.tensor_len());
+        input_error.iter_mut().for_each(|v| *v = DT::ZERO);
+        let out_plane = out_w * out_dims.1;
+        for (mut in_err_row, (err_row, idx_row)) in zip(
+            input_error.iter_major_axis_mut(),
+            zip(out_error.iter_major_axis(), indices.iter_major_axis()),
+        ) {
+            for (i, (&e, &idx)) in zip(err_row.iter(), idx_row.iter()).enumerate() {
+                let (c, j) = (i / out_plane, i % out_plane);
+                let max_k = DType::to_usize(&idx);
+                for_each_channel_window_index(window, c, j / out_w, j % out_w, |k, x| {
+                    if k == max_k {
+                        in_err_row[x] += e;
+                    }
+                });
+            }
+        }
+    }
+
+    fn avg_pool2d(&self, window: &Window2d, input: TensorView2<DT>, output: &mut Tensor2<DT>) {
+        let out_dims = window.output_dims(window.channels());
+        let out_w = out_dims.2;
+        assert_eq!(input.dims().cols(), window.input_dims.tensor_len());
+        assert_eq!(output.dims(), &Dim2(input.dims().rows(), out_dims.tensor_len()));
+        let out_plane = out_w * out_dims.1;
+        for (in_row, mut out_row) in zip(input.iter_major_axis(), output.iter_major_axis_mut()) {
+            for (i, o) in out_row.iter_mut().enumerate() {
+                let (c, j) = (i / out_plane, i % out_plane);
+                let (mut sum, mut count) = (DT::ZERO, 0);
+                for_each_channel_window_index(window, c, j / out_w, j % out_w, |_, x| {
+                    sum += in_row[x];
+                    count += 1;
+                });
+                *o = sum / DT::from_usize(count);
+            }
+        }
+    }
+
+    fn avg_pool2d_error(&self, window: &Window2d, out_error: &Tensor2<DT>, input_error: &mut Tensor2<DT>) {
+        let out_dims = window.output_dims(window.channels());
+        let out_w = out_dims.2;
+        assert_eq!(out_error.dims(), &Dim2(input_error.dims().rows(), out_dims.tensor_len()));
+        assert_eq!(input_error.dims().cols(), window.input_dims.tensor_len());
+        input_error.iter_mut().for_each(|v| *v = DT::ZERO);
+        let out_plane = out_w * out_dims.1;
+        for (mut in_err_row, err_row) in zip(input_error.iter_major_axis_mut(), out_error.iter_major_axis()) {
+            for (i, &e) in err_row.iter().enumerate() {
+                let (c, j) = (i / out_plane, i % out_plane);
+                let mut count = 0;
+                for_each_channel_window_index(window, c, j / out_w, j % out_w, |_, _| count += 1);
+                let e = e / DT::from_usize(count);
+                for_each_channel_window_index(window, c, j / out_w, j % out_w, |_, x| in_err_row[x] += e);
+            }
+        }
+    }
+
     #[inline]
+    fn column_mean_var(&self, a: TensorView2<DT>, mean: &mut Tensor1<DT>, var: &mut Tensor1<DT>) {
+        let &Dim2(rows, cols) = a.dims();
+        assert_eq!(mean.len(), cols);
+        assert_eq!(var.len(), cols);
+        let n = DT::from_usize(rows);
+        mean.fill(DT::ZERO);
+        for row in a.iter_major_axis() {
+            for (m, &x) in zip(mean.iter_mut(), row) {
+                *m += x;
+            }
+        }
+        for m in mean.iter_mut() {
+            *m /= n;
+        }
+        var.fill(DT::ZERO);
+        for row in a.iter_major_axis() {
+            for ((v, &m), &x) in zip(zip(var.iter_mut(), mean.iter()), row) {
+                *v += (x - m) * (x - m);
+            }
+        }
+        for v in var.iter_mut() {
+            *v /= n;
+        }
+    }
+
+    fn batch_norm(
+        &self,
+        epsilon: DT,
+        input: TensorView2<DT>,
+        mean: &Tensor1<DT>,
+        var: &Tensor1<DT>,
+        gamma: &Tensor1<DT>,
+        beta: &Tensor1<DT>,
+        normalized: &mut Tensor2<DT>,
+        output: &mut Tensor2<DT>,
+    ) {
+        let cols = input.dims().cols();
+        assert_eq!(input.dims(), normalized.dims());
+        assert_eq!(input.dims(), output.dims());
+        for t in [mean, var, gamma, beta] {
+            assert_eq!(t.len(), cols);
+        }
+        let inv_std: Vec<DT> = var.iter().map(|&v| DT::ONE / (v + epsilon).sqrt()).collect();
+        for ((in_row, mut norm_row), mut out_row) in zip(
+            zip(input.iter_major_axis(), normalized.iter_major_axis_mut()),
+            output.iter_major_axis_mut(),
+        ) {
+            for (i, &x) in in_row.iter().enumerate() {
+                let x_hat = (x - mean[i]) * inv_std[i];
+                norm_row[i] = x_hat;
+                out_row[i] = gamma[i] * x_hat + beta[i];
+            }
+        }
+    }
+
+    fn batch_norm_param_grad(
+        &self,
+        normalized: &Tensor2<DT>,
+        out_error: &Tensor2<DT>,
+        gamma_grad: &mut Tensor1<DT>,
+        beta_grad: &mut Tensor1<DT>,
+    ) {
+        assert_eq!(normalized.dims(), out_error.dims());
+        assert_eq!(gamma_grad.len(), normalized.dims().cols());
+        assert_eq!(beta_grad.len(), normalized.dims().cols());
+        gamma_grad.fill(DT::ZERO);
+        beta_grad.fill(DT::ZERO);
+        for (norm_row, err_row) in zip(normalized.iter_major_axis(), out_error.iter_major_axis()) {
+            for (i, (&x_hat, &err)) in zip(norm_row, err_row).enumerate() {
+                gamma_grad[i] += err * x_hat;
+                beta_grad[i] += err;
+            }
+        }
+    }
+
+    fn batch_norm_input_error(
+        &self,
+        epsilon: DT,
+        normalized: &Tensor2<DT>,
+        out_error: &Tensor2<DT>,
+        var: &Tensor1<DT>,
+        gamma: &Tensor1<DT>,
+        gamma_grad: &Tensor1<DT>,
+        beta_grad: &Tensor1<DT>,
+        input_error: &mut Tensor2<DT>,
+    ) {
+        let &Dim2(rows, cols) = normalized.dims();
+        assert_eq!(normalized.dims(), out_error.dims());
+        assert_eq!(normalized.dims(), input_error.dims());
+        for t in [var, gamma, gamma_grad, beta_grad] {
+            assert_eq!(t.len(), cols);
+        }
+        let n = DT::from_usize(rows);
+        let scale: Vec<DT> = zip(var, gamma).map(|(&v, &g)| g / (v + epsilon).sqrt()).collect();
+        for ((norm_row, err_row), mut result_row) in zip(
+            zip(normalized.iter_major_axis(), out_error.iter_major_axis()),
+            input_error.iter_major_axis_mut(),
+        ) {
+            for i in 0..cols {
+                result_row[i] = scale[i] * (err_row[i] - (beta_grad[i] + norm_row[i] * gamma_grad[i]) / n);
+            }
+        }
+    }
+
+    fn row_mean_var(&self, a: TensorView2<DT>, mean: &mut Tensor1<DT>, var: &mut Tensor1<DT>) {
+        let &Dim2(rows, cols) = a.dims();
+        assert_eq!(mean.len(), rows);
+        assert_eq!(var.len(), rows);
+        let n = DT::from_usize(cols);
+        for ((row, m), v) in zip(zip(a.iter_major_axis(), mean.iter_mut()), var.iter_mut()) {
+            *m = row.iter().fold(DT::ZERO, |sum, &x| sum + x) / n;
+            *v = row.iter().fold(DT::ZERO, |sum, &x| sum + (x - *m) * (x - *m)) / n;
+        }
+    }
+
+    fn layer_norm(
+        &self,
+        epsilon: DT,
+        input: TensorView2<DT>,
+        mean: &Tensor1<DT>,
+        var: &Tensor1<DT>,
+        gain: &Tensor1<DT>,
+        bias: &Tensor1<DT>,
+        normalized: &mut Tensor2<DT>,
+        output: &mut Tensor2<DT>,
+    ) {
+        let &Dim2(rows, cols) = input.dims();
+        assert_eq!(input.dims(), normalized.dims());
+        assert_eq!(input.dims(), output.dims());
+        assert_eq!(mean.len(), rows);
+        assert_eq!(var.len(), rows);
+        assert_eq!(gain.len(), cols);
+        assert_eq!(bias.len(), cols);
+        for (r, ((in_row, mut norm_row), mut out_row)) in zip(
+            zip(input.iter_major_axis(), normalized.iter_major_axis_mut()),
+            output.iter_major_axis_mut(),
+        )
+        .enumerate()
+        {
+            let inv_std = DT::ONE / (var[r] + epsilon).sqrt();
+            for (i, &x) in in_row.iter().enumerate() {
+                let x_hat = (x - mean[r]) * inv_std;
+                norm_row[i] = x_hat;
+                out_row[i] = gain[i] * x_hat + bias[i];
+            }
+        }
+    }
+
+    fn layer_norm_input_error(
+        &self,
+        epsilon: DT,
+        normalized: &Tensor2<DT>,
+        out_error: &Tensor2<DT>,
+        var: &Tensor1<DT>,
+        gain: &Tensor1<DT>,
+        input_error: &mut Tensor2<DT>,
+    ) {
+        let &Dim2(rows, cols) = normalized.dims();
+        assert_eq!(normalized.dims(), out_error.dims());
+        assert_eq!(normalized.dims(), input_error.dims());
+        assert_eq!(var.len(), rows);
+        assert_eq!(gain.len(), cols);
+        let n = DT::from_usize(cols);
+        for (r, ((norm_row, err_row), mut result_row)) in zip(
+            zip(normalized.iter_major_axis(), out_error.iter_major_axis()),
+            input_error.iter_major_axis_mut(),
+        )
+        .enumerate()
+        {
+            // the error of the normalized values is `out_error * gain`
+            let mut err_sum = DT::ZERO;
+            let mut err_dot = DT::ZERO;
+            for i in 0..cols {
+                let err = err_row[i] * gain[i];
+                err_sum += err;
+                err_dot += err * norm_row[i];
+            }
+            let inv_std = DT::ONE / (var[r] + epsilon).sqrt();
+            for i in 0..cols {
+                result_row[i] = inv_std * (err_row[i] * gain[i] - (err_sum + norm_row[i] * err_dot) / n);
+            }
+        }
+    }
+
+    fn dropout(&self, mask: &DropoutMask, input: TensorView2<DT>, output: &mut Tensor2<DT>, scale: &mut Tensor2<DT>) {
+        assert_eq!(input.dims(), output.dims());
+        assert_eq!(input.dims(), scale.dims());
+        let keep_scale = DT::from_f64(mask.scale());
+        for (idx, ((o, s), &x)) in zip(zip(output.iter_mut(), scale.iter_mut()), input.iter()).enumerate() {
+            *s = if mask.keep(idx) { keep_scale } else { DT::ZERO };
+            *o = x * *s;
+        }
+    }
+
+    fn dropout_error(&self, scale: &Tensor2<DT>, out_error: &Tensor2<DT>, input_error: &mut Tensor2<DT>) {
+        assert_eq!(scale.dims(), out_error.dims());
+        assert_eq!(scale.dims(), input_error.dims());
+        for ((r, &s), &err) in zip(zip(input_error, scale), out_error) {
+            *r = s * err;
+        }
+    }
+
     fn flush(&self) {}
     #[inline]
     fn sync(&self) {}
@@ -266,6 +992,15 @@ impl<DT: DTypeOps> BackendOther for CpuBackend<DT> {
 
 impl<DT: DTypeOps> Backend for CpuBackend<DT> {}
 
+/// sums the columns of a row-major matrix with the given number of columns
+fn column_sums<DT: DTypeOps>(a: &[DT], cols: usize) -> Vec<DT> {
+    let mut sums = vec![DT::ZERO; cols];
+    for row in a.chunks_exact(cols) {
+        DT::add_slice(row, &mut sums);
+    }
+    sums
+}
+
 impl<DT: DTypeOps> Debug for CpuBackend<DT> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_str("CpuBackend<")?;
@@ -273,3 +1008,62 @@ impl<DT: DTypeOps> Debug for CpuBackend<DT> {
         f.write_char('>')
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::CpuBackend;
+    use crate::backend::BackendOther;
+    use crate::tensor::{Dim1, Dim2, ITensor, Tensor1, Tensor2, TensorBase};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rand_distr::StandardNormal;
+
+    fn assert_close(threaded: &[f32], serial: &[f32]) {
+        assert_eq!(threaded.len(), serial.len());
+        for (i, (&t, &s)) in threaded.iter().zip(serial).enumerate() {
+            assert!((t - s).abs() <= 1e-5 * s.abs().max(1.0), "{i}: {t} != {s}");
+        }
+    }
+
+    #[test]
+    fn test_threaded_ops() {
+        let serial = CpuBackend::<f32>::new(0);
+        let threaded = CpuBackend::<f32>::new(0).with_threads(4);
+        assert_eq!(threaded.num_threads(), 4);
+        let mut rng = StdRng::seed_from_u64(0x17);
+        // large enough to be split between threads, with a row length that doesn't divide the chunks evenly
+        let dims = Dim2(301, 117);
+        let a = Tensor2::from_distribution(&mut rng, StandardNormal, dims);
+        let b = Tensor2::from_distribution(&mut rng, StandardNormal, dims);
+
+        let run = |f: &dyn Fn(&CpuBackend<f32>, &mut Tensor2<f32>)| {
+            let (mut t, mut s) = (b.clone(), b.clone());
+            f(&threaded, &mut t);
+            f(&serial, &mut s);
+            assert_close(t.as_ref(), s.as_ref());
+        };
+        run(&|backend, out| backend.sigmoid(a.view(), out));
+        run(&|backend, out| backend.relu(0.1, a.view(), out));
+        run(&|backend, out| backend.softmax(a.view(), out));
+        run(&|backend, out| backend.add_assign(0.5, &a, 2.0, out));
+
+        let column_sum = |backend: &CpuBackend<f32>| {
+            let mut sum = Tensor1::filled(1.0, Dim1(dims.cols()));
+            backend.column_sum(2.0, &a, 0.5, &mut sum);
+            sum
+        };
+        assert_close(column_sum(&threaded).as_ref(), column_sum(&serial).as_ref());
+
+        let mse = |backend: &CpuBackend<f32>| {
+            let mut result = Tensor1::zeroed(Dim1(dims.rows()));
+            let mut result_deriv = Tensor2::zeroed(dims);
+            backend.mean_squared_error(&a, b.view(), &mut result, &mut result_deriv);
+            (result, result_deriv)
+        };
+        let (t_result, t_deriv) = mse(&threaded);
+        let (s_result, s_deriv) = mse(&serial);
+        assert_close(t_result.as_ref(), s_result.as_ref());
+        assert_close(t_deriv.as_ref(), s_deriv.as_ref());
+        assert!(t_deriv.len() > super::MIN_PARALLEL_LEN);
+    }
+}
@@ -0,0 +1,146 @@
+//! Bindings to the `gemm` routines of a system CBLAS library, used for matmul with the `blas` feature.
+//! The library is linked by the build script, `openblas` unless the `RCANN_BLAS_LIB` env var names another.
+
+use crate::tensor::{Dim2, TensorBase, TensorBaseMut};
+use std::ffi::c_int;
+
+const CBLAS_ROW_MAJOR: c_int = 101;
+const CBLAS_NO_TRANS: c_int = 111;
+const CBLAS_TRANS: c_int = 112;
+
+unsafe extern "C" {
+    fn cblas_sgemm(
+        layout: c_int,
+        trans_a: c_int,
+        trans_b: c_int,
+        m: c_int,
+        n: c_int,
+        k: c_int,
+        alpha: f32,
+        a: *const f32,
+        lda: c_int,
+        b: *const f32,
+        ldb: c_int,
+        beta: f32,
+        c: *mut f32,
+        ldc: c_int,
+    );
+
+    fn cblas_dgemm(
+        layout: c_int,
+        trans_a: c_int,
+        trans_b: c_int,
+        m: c_int,
+        n: c_int,
+        k: c_int,
+        alpha: f64,
+        a: *const f64,
+        lda: c_int,
+        b: *const f64,
+        ldb: c_int,
+        beta: f64,
+        c: *mut f64,
+        ldc: c_int,
+    );
+}
+
+#[inline]
+fn trans(transpose: bool) -> c_int {
+    if transpose { CBLAS_TRANS } else { CBLAS_NO_TRANS }
+}
+
+#[inline]
+fn blas_int(value: usize) -> c_int {
+    c_int::try_from(value).expect("Matrix dimension exceeds the range of the BLAS integer type")
+}
+
+macro_rules! impl_gemm {
+    ($name:ident, $t:ty, $cblas:ident) => {
+        /// `c = alpha * op(a) * op(b) + beta * c`
+        pub fn $name<A, B, C>(alpha: $t, a: &A, ta: bool, b: &B, tb: bool, beta: $t, c: &mut C)
+        where
+            A: TensorBase<$t, Dim2>,
+            B: TensorBase<$t, Dim2>,
+            C: TensorBaseMut<$t, Dim2>,
+        {
+            let &Dim2(a_rows, a_cols) = a.dims();
+            let &Dim2(b_rows, b_cols) = b.dims();
+            let (m, k) = if ta { (a_cols, a_rows) } else { (a_rows, a_cols) };
+            let n = if tb {
+                assert_eq!(b_cols, k);
+                b_rows
+            } else {
+                assert_eq!(b_rows, k);
+                b_cols
+            };
+            assert_eq!(c.dims(), &Dim2(m, n));
+            if m == 0 || n == 0 {
+                return;
+            }
+            // the leading dimensions must be at least one, even for empty matrices
+            unsafe {
+                $cblas(
+                    CBLAS_ROW_MAJOR,
+                    trans(ta),
+                    trans(tb),
+                    blas_int(m),
+                    blas_int(n),
+                    blas_int(k),
+                    alpha,
+                    a.as_ref().as_ptr(),
+                    blas_int(a_cols.max(1)),
+                    b.as_ref().as_ptr(),
+                    blas_int(b_cols.max(1)),
+                    beta,
+                    c.as_mut().as_mut_ptr(),
+                    blas_int(n),
+                );
+            }
+        }
+    };
+}
+
+impl_gemm!(sgemm, f32, cblas_sgemm);
+impl_gemm!(dgemm, f64, cblas_dgemm);
+
+#[cfg(test)]
+mod test {
+    use super::{dgemm, sgemm};
+    use crate::backend::cpu::math::{matrixmultiply_dgemm, matrixmultiply_sgemm};
+    use crate::tensor::{Dim2, Tensor2, TensorBase};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rand_distr::StandardNormal;
+
+    macro_rules! check_gemm {
+        ($t:ty, $blas:ident, $reference:ident, $tolerance:expr) => {{
+            let mut rng = StdRng::seed_from_u64(0x19);
+            let (m, k, n) = (13, 7, 9);
+            for (ta, tb) in [(false, false), (false, true), (true, false), (true, true)] {
+                let a_dims = if ta { Dim2(k, m) } else { Dim2(m, k) };
+                let b_dims = if tb { Dim2(n, k) } else { Dim2(k, n) };
+                let a = Tensor2::<$t>::from_distribution(&mut rng, StandardNormal, a_dims);
+                let b = Tensor2::<$t>::from_distribution(&mut rng, StandardNormal, b_dims);
+                let c = Tensor2::<$t>::from_distribution(&mut rng, StandardNormal, Dim2(m, n));
+                let (mut actual, mut expected) = (c.clone(), c.clone());
+                $blas(0.5, &a, ta, &b, tb, 2.0, &mut actual);
+                $reference(0.5, &a, ta, &b, tb, 2.0, &mut expected);
+                for (&x, &y) in actual.iter().zip(expected.iter()) {
+                    assert!((x - y).abs() < $tolerance, "ta={ta}, tb={tb}: {x} != {y}");
+                }
+            }
+            // empty inner dimension only scales c
+            let a = Tensor2::<$t>::zeroed(Dim2(3, 0));
+            let b = Tensor2::<$t>::zeroed(Dim2(0, 2));
+            let mut c = Tensor2::<$t>::filled(1.0, Dim2(3, 2));
+            $blas(1.0, &a, false, &b, false, 3.0, &mut c);
+            assert_eq!(c.as_ref(), &[3.0; 6]);
+        }};
+    }
+
+    #[test]
+    fn test_blas_gemm() {
+        check_gemm!(f32, sgemm, matrixmultiply_sgemm, 1e-4);
+        check_gemm!(f64, dgemm, matrixmultiply_dgemm, 1e-12);
+    }
+}
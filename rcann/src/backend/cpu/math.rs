@@ -1,28 +1,13 @@
+#[cfg(feature = "blas")]
+use super::blas;
+use super::simd;
+use crate::backend::Window2d;
 use crate::dtype::DTypeFloat;
-use crate::tensor::{Dim2, ITensor, Tensor2, TensorBase, TensorBaseMut};
+#[cfg(feature = "half")]
+use crate::tensor::Tensor2;
+use crate::tensor::{Dim2, Dim3, TensorBase, TensorBaseMut};
 use std::cmp::Ordering;
 
-pub fn compute_jacobian_matrix<T: DTypeFloat>(a: &[T], b: &mut Tensor2<T>) {
-    let size = a.len();
-    assert_eq!(b.dims(), &Dim2(size, size));
-    let pa = a.as_ptr();
-    let pb = b.as_mut().as_mut_ptr();
-    let sizei = size as isize;
-    unsafe {
-        for i in 0..sizei {
-            let pbi = pb.offset(sizei * i);
-            let ai = *pa.offset(i);
-            for j in 0..sizei {
-                if i == j {
-                    *pbi.offset(j) = ai * (T::ONE - ai);
-                } else {
-                    *pbi.offset(j) = -ai * *pa.offset(j);
-                }
-            }
-        }
-    }
-}
-
 pub fn argmax<T: Copy + PartialOrd>(a: &[T]) -> usize {
     a.iter()
         .enumerate()
@@ -39,16 +24,136 @@ pub fn argmax<T: Copy + PartialOrd>(a: &[T]) -> usize {
         .0
 }
 
-pub trait DTypeOps: DTypeFloat {
+/// calls `f(patch_idx, input_idx)` for every input value covered by the window at output position `(oy, ox)`,
+/// skipping positions that fall within the padding
+#[inline]
+pub fn for_each_window_index<F>(window: &Window2d, oy: usize, ox: usize, mut f: F)
+where
+    F: FnMut(usize, usize),
+{
+    let window_len = window.size * window.size;
+    for c in 0..window.channels() {
+        for_each_channel_window_index(window, c, oy, ox, |k, x| f(c * window_len + k, x));
+    }
+}
+
+/// calls `f(window_idx, input_idx)` for every input value of channel `c` covered by the window at output
+/// position `(oy, ox)`, where `window_idx` is the position within the window
+#[inline]
+pub fn for_each_channel_window_index<F>(window: &Window2d, c: usize, oy: usize, ox: usize, mut f: F)
+where
+    F: FnMut(usize, usize),
+{
+    let Dim3(_, height, width) = window.input_dims;
+    let size = window.size;
+    let y0 = (oy * window.stride) as isize - window.padding as isize;
+    let x0 = (ox * window.stride) as isize - window.padding as isize;
+    for ky in 0..size {
+        let y = y0 + ky as isize;
+        if y < 0 || y >= height as isize {
+            continue;
+        }
+        for kx in 0..size {
+            let x = x0 + kx as isize;
+            if x < 0 || x >= width as isize {
+                continue;
+            }
+            f(ky * size + kx, (c * height + y as usize) * width + x as usize);
+        }
+    }
+}
+
+pub trait DTypeOps: DTypeFloat + Send + Sync {
     fn matrix_multiply<A, B, C>(alpha: Self, a: &A, ta: bool, b: &B, tb: bool, beta: Self, c: &mut C)
     where
         A: TensorBase<Self, Dim2>,
         B: TensorBase<Self, Dim2>,
         C: TensorBaseMut<Self, Dim2>;
+
+    // the slice kernels below are vectorized for f32 and f64, see [simd]
+
+    #[inline]
+    fn sigmoid_slice(activation: &[Self], output: &mut [Self]) {
+        simd::scalar::sigmoid(activation, output)
+    }
+
+    #[inline]
+    fn swish_slice(activation: &[Self], output: &mut [Self]) {
+        simd::scalar::swish(activation, output)
+    }
+
+    /// the softmax of a single row
+    #[inline]
+    fn softmax_slice(activation: &[Self], output: &mut [Self]) {
+        simd::scalar::softmax(activation, output)
+    }
+
+    /// `b = alpha * a + beta * b`
+    #[inline]
+    fn scale_add_slice(alpha: Self, a: &[Self], beta: Self, b: &mut [Self]) {
+        simd::scalar::scale_add(alpha, a, beta, b)
+    }
+
+    /// `sums += a`
+    #[inline]
+    fn add_slice(a: &[Self], sums: &mut [Self]) {
+        simd::scalar::add(a, sums)
+    }
+
+    /// writes `output - expected` to `diff` and returns the sum of its squares
+    #[inline]
+    fn squared_error_slice(output: &[Self], expected: &[Self], diff: &mut [Self]) -> Self {
+        simd::scalar::squared_error(output, expected, diff)
+    }
 }
 
 macro_rules! implement_dtype_ops {
-    ($t: ident, $g: ident) => {
+    ($t: ident, $g: ident, $mm: ident, $blas: ident) => {
+        /// `c = alpha * op(a) * op(b) + beta * c` using matrixmultiply, regardless of the `blas` feature
+        #[cfg_attr(all(feature = "blas", not(test)), allow(dead_code))]
+        pub fn $mm<A, B, C>(alpha: $t, a: &A, ta: bool, b: &B, tb: bool, beta: $t, c: &mut C)
+        where
+            A: TensorBase<$t, Dim2>,
+            B: TensorBase<$t, Dim2>,
+            C: TensorBaseMut<$t, Dim2>,
+        {
+            let &Dim2(a_rows, a_cols) = a.dims();
+            let &Dim2(b_rows, b_cols) = b.dims();
+            let &Dim2(_, c_cols) = c.dims();
+            let (m, k, rsa, csa) = if ta {
+                (a_cols, a_rows, 1, a_cols as isize)
+            } else {
+                (a_rows, a_cols, a_cols as isize, 1)
+            };
+            let (n, rsb, csb) = if tb {
+                assert_eq!(b_cols, k);
+                (b_rows, 1, b_cols as isize)
+            } else {
+                assert_eq!(b_rows, k);
+                (b_cols, b_cols as isize, 1)
+            };
+            assert_eq!(c.dims(), &Dim2(m, n));
+            let (rsc, csc) = (c_cols as isize, 1);
+            unsafe {
+                matrixmultiply::$g(
+                    m,
+                    k,
+                    n,
+                    alpha,
+                    a.as_ref().as_ptr(),
+                    rsa,
+                    csa,
+                    b.as_ref().as_ptr(),
+                    rsb,
+                    csb,
+                    beta,
+                    c.as_mut().as_mut_ptr(),
+                    rsc,
+                    csc,
+                );
+            }
+        }
+
         impl DTypeOps for $t {
             fn matrix_multiply<A, B, C>(alpha: Self, a: &A, ta: bool, b: &B, tb: bool, beta: Self, c: &mut C)
             where
@@ -56,48 +161,47 @@ macro_rules! implement_dtype_ops {
                 B: TensorBase<Self, Dim2>,
                 C: TensorBaseMut<Self, Dim2>,
             {
-                let &Dim2(a_rows, a_cols) = a.dims();
-                let &Dim2(b_rows, b_cols) = b.dims();
-                let &Dim2(_, c_cols) = c.dims();
-                let (m, k, rsa, csa) = if ta {
-                    (a_cols, a_rows, 1, a_cols as isize)
-                } else {
-                    (a_rows, a_cols, a_cols as isize, 1)
-                };
-                let (n, rsb, csb) = if tb {
-                    assert_eq!(b_cols, k);
-                    (b_rows, 1, b_cols as isize)
-                } else {
-                    assert_eq!(b_rows, k);
-                    (b_cols, b_cols as isize, 1)
-                };
-                assert_eq!(c.dims(), &Dim2(m, n));
-                let (rsc, csc) = (c_cols as isize, 1);
-                unsafe {
-                    matrixmultiply::$g(
-                        m,
-                        k,
-                        n,
-                        alpha,
-                        a.as_ref().as_ptr(),
-                        rsa,
-                        csa,
-                        b.as_ref().as_ptr(),
-                        rsb,
-                        csb,
-                        beta,
-                        c.as_mut().as_mut_ptr(),
-                        rsc,
-                        csc,
-                    );
-                }
+                #[cfg(feature = "blas")]
+                blas::$blas(alpha, a, ta, b, tb, beta, c);
+                #[cfg(not(feature = "blas"))]
+                $mm(alpha, a, ta, b, tb, beta, c);
+            }
+
+            #[inline]
+            fn sigmoid_slice(activation: &[Self], output: &mut [Self]) {
+                simd::sigmoid(activation, output)
+            }
+
+            #[inline]
+            fn swish_slice(activation: &[Self], output: &mut [Self]) {
+                simd::swish(activation, output)
+            }
+
+            #[inline]
+            fn softmax_slice(activation: &[Self], output: &mut [Self]) {
+                simd::softmax(activation, output)
+            }
+
+            #[inline]
+            fn scale_add_slice(alpha: Self, a: &[Self], beta: Self, b: &mut [Self]) {
+                simd::scale_add(alpha, a, beta, b)
+            }
+
+            #[inline]
+            fn add_slice(a: &[Self], sums: &mut [Self]) {
+                simd::add(a, sums)
+            }
+
+            #[inline]
+            fn squared_error_slice(output: &[Self], expected: &[Self], diff: &mut [Self]) -> Self {
+                simd::squared_error(output, expected, diff)
             }
         }
     };
 }
 
-implement_dtype_ops!(f32, sgemm);
-implement_dtype_ops!(f64, dgemm);
+implement_dtype_ops!(f32, sgemm, matrixmultiply_sgemm, sgemm);
+implement_dtype_ops!(f64, dgemm, matrixmultiply_dgemm, dgemm);
 
 #[cfg(feature = "half")]
 impl DTypeOps for half::f16 {
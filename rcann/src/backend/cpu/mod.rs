@@ -1,4 +1,7 @@
 mod backend;
+#[cfg(feature = "blas")]
+mod blas;
 mod math;
+mod simd;
 
 pub use backend::CpuBackend;
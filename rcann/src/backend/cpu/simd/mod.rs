@@ -0,0 +1,12 @@
+//! Vectorized versions of the hottest element-wise and reduction loops of the [super::CpuBackend].
+//! On x86 the instruction set is picked at runtime, using AVX2 with FMA where available and SSE2 otherwise.
+//! The [scalar] loops are used on other architectures, for other float types and for the tail of each slice.
+
+pub mod scalar;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86;
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+pub use scalar::{add, scale_add, sigmoid, softmax, squared_error, swish};
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub use x86::{add, scale_add, sigmoid, softmax, squared_error, swish};
@@ -0,0 +1,57 @@
+use crate::dtype::DTypeFloat;
+use std::cmp::Ordering;
+use std::iter::zip;
+
+pub fn sigmoid<DT: DTypeFloat>(activation: &[DT], output: &mut [DT]) {
+    for (o, &a) in zip(output, activation) {
+        *o = DT::ONE / (DT::ONE + (-a).exp());
+    }
+}
+
+pub fn swish<DT: DTypeFloat>(activation: &[DT], output: &mut [DT]) {
+    for (o, &a) in zip(output, activation) {
+        *o = a / (DT::ONE + (-a).exp());
+    }
+}
+
+/// the softmax of a single row
+pub fn softmax<DT: DTypeFloat>(activation: &[DT], output: &mut [DT]) {
+    // shift the values by -max(inputs) to prevent overflow (does not affect derivative)
+    let max = *activation
+        .iter()
+        .max_by(|&a, &b| if a > b { Ordering::Greater } else { Ordering::Less })
+        .unwrap();
+    let mut sum = DT::ZERO;
+    for (o, &a) in zip(output.iter_mut(), activation) {
+        let x = (a - max).exp();
+        sum += x;
+        *o = x;
+    }
+    for o in output.iter_mut() {
+        *o /= sum
+    }
+}
+
+/// `b = alpha * a + beta * b`
+pub fn scale_add<DT: DTypeFloat>(alpha: DT, a: &[DT], beta: DT, b: &mut [DT]) {
+    for (&ai, bi) in zip(a, b) {
+        *bi = alpha * ai + beta * *bi;
+    }
+}
+
+/// `sums += a`
+pub fn add<DT: DTypeFloat>(a: &[DT], sums: &mut [DT]) {
+    for (sum, &ai) in zip(sums, a) {
+        *sum += ai;
+    }
+}
+
+/// writes `output - expected` to `diff` and returns the sum of its squares
+pub fn squared_error<DT: DTypeFloat>(output: &[DT], expected: &[DT], diff: &mut [DT]) -> DT {
+    let mut sum = DT::ZERO;
+    for (d, (&o, &e)) in zip(diff, zip(output, expected)) {
+        *d = o - e;
+        sum += *d * *d;
+    }
+    sum
+}
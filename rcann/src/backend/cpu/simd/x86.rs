@@ -0,0 +1,592 @@
+use super::scalar;
+use crate::dtype::{DType, DTypeFloat};
+use num_traits::Float;
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+use std::iter::zip;
+
+/// The float types with vectorized kernels, along with the constants used to approximate `exp`
+pub trait SimdFloat: DTypeFloat {
+    type Avx2: SimdVec<Elem = Self>;
+    type Sse2: SimdVec<Elem = Self>;
+    /// the range of inputs for which `2^round(x * log2(e))` is a normal float
+    const EXP_MIN: Self;
+    const EXP_MAX: Self;
+    const LOG2_E: Self;
+    /// `ln(2)` split into a high part which can be multiplied by the exponent exactly, and the remainder
+    const LN2_HI: Self;
+    const LN2_LO: Self;
+    /// the taylor series of `exp(r)` for `|r| <= ln(2) / 2`, highest order first
+    const EXP_POLY: &'static [Self];
+}
+
+impl SimdFloat for f32 {
+    type Avx2 = __m256;
+    type Sse2 = __m128;
+    const EXP_MIN: Self = -87.33654;
+    const EXP_MAX: Self = 88.37626;
+    const LOG2_E: Self = std::f32::consts::LOG2_E;
+    const LN2_HI: Self = 355.0 / 512.0;
+    const LN2_LO: Self = -2.1219444e-4;
+    const EXP_POLY: &'static [Self] = &[
+        1.0 / 5040.0,
+        1.0 / 720.0,
+        1.0 / 120.0,
+        1.0 / 24.0,
+        1.0 / 6.0,
+        0.5,
+        1.0,
+        1.0,
+    ];
+}
+
+impl SimdFloat for f64 {
+    type Avx2 = __m256d;
+    type Sse2 = __m128d;
+    const EXP_MIN: Self = -708.3964185322641;
+    const EXP_MAX: Self = 709.4361393031;
+    const LOG2_E: Self = std::f64::consts::LOG2_E;
+    const LN2_HI: Self = 0.693145751953125;
+    const LN2_LO: Self = 1.4286068203094173e-6;
+    const EXP_POLY: &'static [Self] = &[
+        1.0 / 6227020800.0,
+        1.0 / 479001600.0,
+        1.0 / 39916800.0,
+        1.0 / 3628800.0,
+        1.0 / 362880.0,
+        1.0 / 40320.0,
+        1.0 / 5040.0,
+        1.0 / 720.0,
+        1.0 / 120.0,
+        1.0 / 24.0,
+        1.0 / 6.0,
+        0.5,
+        1.0,
+        1.0,
+    ];
+}
+
+/// A vector of floats. The methods are only safe to call when the CPU supports the instruction set of the vector,
+/// and are always inlined so the kernels can be compiled once for each instruction set.
+pub trait SimdVec: Copy {
+    type Elem: SimdFloat;
+    const LANES: usize;
+
+    unsafe fn splat(value: Self::Elem) -> Self;
+    /// loads the first [Self::LANES] values of the slice
+    unsafe fn load(src: &[Self::Elem]) -> Self;
+    /// stores to the first [Self::LANES] values of the slice
+    unsafe fn store(self, dst: &mut [Self::Elem]);
+    unsafe fn add(self, other: Self) -> Self;
+    unsafe fn sub(self, other: Self) -> Self;
+    unsafe fn mul(self, other: Self) -> Self;
+    unsafe fn div(self, other: Self) -> Self;
+    /// returns `other` if either value is NaN
+    unsafe fn max(self, other: Self) -> Self;
+    /// returns `other` if either value is NaN
+    unsafe fn min(self, other: Self) -> Self;
+    /// `self * a + b`, fused where supported
+    unsafe fn mul_add(self, a: Self, b: Self) -> Self;
+    /// rounds to the nearest integer
+    unsafe fn round(self) -> Self;
+    /// `2^self` for integral values within the exponent range of normal floats
+    unsafe fn pow2i(self) -> Self;
+
+    #[inline(always)]
+    unsafe fn reduce<F: Fn(Self::Elem, Self::Elem) -> Self::Elem>(self, f: F) -> Self::Elem {
+        let mut values = [Self::Elem::ZERO; 8];
+        unsafe { self.store(&mut values) };
+        values[..Self::LANES].iter().copied().reduce(f).unwrap()
+    }
+}
+
+const ROUND_NEAREST: i32 = _MM_FROUND_TO_NEAREST_INT | _MM_FROUND_NO_EXC;
+
+macro_rules! impl_simd_vec {
+    (
+        $vec:ty, $elem:ty, $lanes:literal, $set1:ident, $loadu:ident, $storeu:ident,
+        $add:ident, $sub:ident, $mul:ident, $div:ident, $max:ident, $min:ident,
+        |$ma_s:ident, $ma_a:ident, $ma_b:ident| $mul_add:expr,
+        |$r:ident| $round:expr,
+        |$p:ident| $pow2i:expr
+    ) => {
+        impl SimdVec for $vec {
+            type Elem = $elem;
+            const LANES: usize = $lanes;
+
+            #[inline(always)]
+            unsafe fn splat(value: $elem) -> Self {
+                unsafe { $set1(value) }
+            }
+
+            #[inline(always)]
+            unsafe fn load(src: &[$elem]) -> Self {
+                debug_assert!(src.len() >= $lanes);
+                unsafe { $loadu(src.as_ptr()) }
+            }
+
+            #[inline(always)]
+            unsafe fn store(self, dst: &mut [$elem]) {
+                debug_assert!(dst.len() >= $lanes);
+                unsafe { $storeu(dst.as_mut_ptr(), self) }
+            }
+
+            #[inline(always)]
+            unsafe fn add(self, other: Self) -> Self {
+                unsafe { $add(self, other) }
+            }
+
+            #[inline(always)]
+            unsafe fn sub(self, other: Self) -> Self {
+                unsafe { $sub(self, other) }
+            }
+
+            #[inline(always)]
+            unsafe fn mul(self, other: Self) -> Self {
+                unsafe { $mul(self, other) }
+            }
+
+            #[inline(always)]
+            unsafe fn div(self, other: Self) -> Self {
+                unsafe { $div(self, other) }
+            }
+
+            #[inline(always)]
+            unsafe fn max(self, other: Self) -> Self {
+                unsafe { $max(self, other) }
+            }
+
+            #[inline(always)]
+            unsafe fn min(self, other: Self) -> Self {
+                unsafe { $min(self, other) }
+            }
+
+            #[inline(always)]
+            unsafe fn mul_add(self, a: Self, b: Self) -> Self {
+                let ($ma_s, $ma_a, $ma_b) = (self, a, b);
+                unsafe { $mul_add }
+            }
+
+            #[inline(always)]
+            unsafe fn round(self) -> Self {
+                let $r = self;
+                unsafe { $round }
+            }
+
+            #[inline(always)]
+            unsafe fn pow2i(self) -> Self {
+                let $p = self;
+                unsafe { $pow2i }
+            }
+        }
+    };
+}
+
+impl_simd_vec!(
+    __m256,
+    f32,
+    8,
+    _mm256_set1_ps,
+    _mm256_loadu_ps,
+    _mm256_storeu_ps,
+    _mm256_add_ps,
+    _mm256_sub_ps,
+    _mm256_mul_ps,
+    _mm256_div_ps,
+    _mm256_max_ps,
+    _mm256_min_ps,
+    |s, a, b| _mm256_fmadd_ps(s, a, b),
+    |r| _mm256_round_ps::<ROUND_NEAREST>(r),
+    |p| {
+        let exponent = _mm256_add_epi32(_mm256_cvtps_epi32(p), _mm256_set1_epi32(127));
+        _mm256_castsi256_ps(_mm256_slli_epi32::<23>(exponent))
+    }
+);
+
+impl_simd_vec!(
+    __m256d,
+    f64,
+    4,
+    _mm256_set1_pd,
+    _mm256_loadu_pd,
+    _mm256_storeu_pd,
+    _mm256_add_pd,
+    _mm256_sub_pd,
+    _mm256_mul_pd,
+    _mm256_div_pd,
+    _mm256_max_pd,
+    _mm256_min_pd,
+    |s, a, b| _mm256_fmadd_pd(s, a, b),
+    |r| _mm256_round_pd::<ROUND_NEAREST>(r),
+    |p| {
+        let exponent = _mm_add_epi32(_mm256_cvtpd_epi32(p), _mm_set1_epi32(1023));
+        _mm256_castsi256_pd(_mm256_slli_epi64::<52>(_mm256_cvtepi32_epi64(exponent)))
+    }
+);
+
+// SSE2 has no fused multiply-add or rounding instruction, so these round by converting to integers
+// with the default rounding mode of nearest
+
+impl_simd_vec!(
+    __m128,
+    f32,
+    4,
+    _mm_set1_ps,
+    _mm_loadu_ps,
+    _mm_storeu_ps,
+    _mm_add_ps,
+    _mm_sub_ps,
+    _mm_mul_ps,
+    _mm_div_ps,
+    _mm_max_ps,
+    _mm_min_ps,
+    |s, a, b| _mm_add_ps(_mm_mul_ps(s, a), b),
+    |r| _mm_cvtepi32_ps(_mm_cvtps_epi32(r)),
+    |p| {
+        let exponent = _mm_add_epi32(_mm_cvtps_epi32(p), _mm_set1_epi32(127));
+        _mm_castsi128_ps(_mm_slli_epi32::<23>(exponent))
+    }
+);
+
+impl_simd_vec!(
+    __m128d,
+    f64,
+    2,
+    _mm_set1_pd,
+    _mm_loadu_pd,
+    _mm_storeu_pd,
+    _mm_add_pd,
+    _mm_sub_pd,
+    _mm_mul_pd,
+    _mm_div_pd,
+    _mm_max_pd,
+    _mm_min_pd,
+    |s, a, b| _mm_add_pd(_mm_mul_pd(s, a), b),
+    |r| _mm_cvtepi32_pd(_mm_cvtpd_epi32(r)),
+    |p| {
+        // the two exponents are in the low 64 bits, so widen them to 64 bits each by interleaving with zeros
+        let exponent = _mm_add_epi32(_mm_cvtpd_epi32(p), _mm_set1_epi32(1023));
+        _mm_castsi128_pd(_mm_slli_epi64::<52>(_mm_unpacklo_epi32(exponent, _mm_setzero_si128())))
+    }
+);
+
+/// `exp(x)` to within a couple of ulps, by splitting `x` into `n * ln(2) + r` and computing `2^n * exp(r)`
+#[inline(always)]
+unsafe fn exp<V: SimdVec>(x: V) -> V {
+    unsafe {
+        // clamped such that NaN inputs are propagated
+        let x = V::splat(V::Elem::EXP_MAX).min(V::splat(V::Elem::EXP_MIN).max(x));
+        let n = x.mul(V::splat(V::Elem::LOG2_E)).round();
+        let r = n.mul_add(V::splat(-V::Elem::LN2_HI), x);
+        let r = n.mul_add(V::splat(-V::Elem::LN2_LO), r);
+        let (&first, rest) = V::Elem::EXP_POLY.split_first().unwrap();
+        let mut p = V::splat(first);
+        for &c in rest {
+            p = p.mul_add(r, V::splat(c));
+        }
+        p.mul(n.pow2i())
+    }
+}
+
+#[inline(always)]
+unsafe fn sigmoid_kernel<V: SimdVec>(activation: &[V::Elem], output: &mut [V::Elem]) {
+    let mut activation = activation.chunks_exact(V::LANES);
+    let mut output = output.chunks_exact_mut(V::LANES);
+    unsafe {
+        let one = V::splat(V::Elem::ONE);
+        let zero = V::splat(V::Elem::ZERO);
+        for (a, o) in zip(&mut activation, &mut output) {
+            one.div(one.add(exp(zero.sub(V::load(a))))).store(o);
+        }
+    }
+    scalar::sigmoid(activation.remainder(), output.into_remainder());
+}
+
+#[inline(always)]
+unsafe fn swish_kernel<V: SimdVec>(activation: &[V::Elem], output: &mut [V::Elem]) {
+    let mut activation = activation.chunks_exact(V::LANES);
+    let mut output = output.chunks_exact_mut(V::LANES);
+    unsafe {
+        let one = V::splat(V::Elem::ONE);
+        let zero = V::splat(V::Elem::ZERO);
+        for (a, o) in zip(&mut activation, &mut output) {
+            let a = V::load(a);
+            a.div(one.add(exp(zero.sub(a)))).store(o);
+        }
+    }
+    scalar::swish(activation.remainder(), output.into_remainder());
+}
+
+#[inline(always)]
+unsafe fn softmax_kernel<V: SimdVec>(activation: &[V::Elem], output: &mut [V::Elem]) {
+    let split = activation.len() - activation.len() % V::LANES;
+    let (activation, activation_tail) = activation.split_at(split);
+    let (output, output_tail) = output.split_at_mut(split);
+    unsafe {
+        // shift the values by -max(inputs) to prevent overflow (does not affect derivative)
+        let mut max = activation_tail
+            .iter()
+            .copied()
+            .fold(V::Elem::neg_infinity(), V::Elem::max);
+        if split > 0 {
+            let mut max_vec = V::load(activation);
+            for a in activation.chunks_exact(V::LANES) {
+                max_vec = max_vec.max(V::load(a));
+            }
+            max = max.max(max_vec.reduce(V::Elem::max));
+        }
+        let max_vec = V::splat(max);
+        let mut sum_vec = V::splat(V::Elem::ZERO);
+        for (a, o) in zip(activation.chunks_exact(V::LANES), output.chunks_exact_mut(V::LANES)) {
+            let x = exp(V::load(a).sub(max_vec));
+            sum_vec = sum_vec.add(x);
+            x.store(o);
+        }
+        let mut sum = sum_vec.reduce(|a, b| a + b);
+        for (o, &a) in zip(output_tail.iter_mut(), activation_tail) {
+            *o = (a - max).exp();
+            sum += *o;
+        }
+        let scale = V::Elem::ONE / sum;
+        let scale_vec = V::splat(scale);
+        for o in output.chunks_exact_mut(V::LANES) {
+            V::load(o).mul(scale_vec).store(o);
+        }
+        for o in output_tail {
+            *o *= scale;
+        }
+    }
+}
+
+#[inline(always)]
+unsafe fn scale_add_kernel<V: SimdVec>(alpha: V::Elem, a: &[V::Elem], beta: V::Elem, b: &mut [V::Elem]) {
+    let mut a = a.chunks_exact(V::LANES);
+    let mut b = b.chunks_exact_mut(V::LANES);
+    unsafe {
+        let (alpha_vec, beta_vec) = (V::splat(alpha), V::splat(beta));
+        for (ai, bi) in zip(&mut a, &mut b) {
+            V::load(ai).mul_add(alpha_vec, V::load(bi).mul(beta_vec)).store(bi);
+        }
+    }
+    scalar::scale_add(alpha, a.remainder(), beta, b.into_remainder());
+}
+
+#[inline(always)]
+unsafe fn add_kernel<V: SimdVec>(a: &[V::Elem], sums: &mut [V::Elem]) {
+    let mut a = a.chunks_exact(V::LANES);
+    let mut sums = sums.chunks_exact_mut(V::LANES);
+    unsafe {
+        for (ai, si) in zip(&mut a, &mut sums) {
+            V::load(si).add(V::load(ai)).store(si);
+        }
+    }
+    scalar::add(a.remainder(), sums.into_remainder());
+}
+
+#[inline(always)]
+unsafe fn squared_error_kernel<V: SimdVec>(output: &[V::Elem], expected: &[V::Elem], diff: &mut [V::Elem]) -> V::Elem {
+    let mut output = output.chunks_exact(V::LANES);
+    let mut expected = expected.chunks_exact(V::LANES);
+    let mut diff = diff.chunks_exact_mut(V::LANES);
+    let sum = unsafe {
+        let mut sum_vec = V::splat(V::Elem::ZERO);
+        for ((o, e), d) in zip(zip(&mut output, &mut expected), &mut diff) {
+            let d_vec = V::load(o).sub(V::load(e));
+            d_vec.store(d);
+            sum_vec = d_vec.mul_add(d_vec, sum_vec);
+        }
+        sum_vec.reduce(|a, b| a + b)
+    };
+    sum + scalar::squared_error(output.remainder(), expected.remainder(), diff.into_remainder())
+}
+
+/// Compiles the kernels with the target features of an instruction set enabled
+macro_rules! impl_target_kernels {
+    ($name:ident, $features:literal) => {
+        mod $name {
+            use super::SimdVec;
+
+            #[target_feature(enable = $features)]
+            pub unsafe fn sigmoid<V: SimdVec>(activation: &[V::Elem], output: &mut [V::Elem]) {
+                unsafe { super::sigmoid_kernel::<V>(activation, output) }
+            }
+
+            #[target_feature(enable = $features)]
+            pub unsafe fn swish<V: SimdVec>(activation: &[V::Elem], output: &mut [V::Elem]) {
+                unsafe { super::swish_kernel::<V>(activation, output) }
+            }
+
+            #[target_feature(enable = $features)]
+            pub unsafe fn softmax<V: SimdVec>(activation: &[V::Elem], output: &mut [V::Elem]) {
+                unsafe { super::softmax_kernel::<V>(activation, output) }
+            }
+
+            #[target_feature(enable = $features)]
+            pub unsafe fn scale_add<V: SimdVec>(alpha: V::Elem, a: &[V::Elem], beta: V::Elem, b: &mut [V::Elem]) {
+                unsafe { super::scale_add_kernel::<V>(alpha, a, beta, b) }
+            }
+
+            #[target_feature(enable = $features)]
+            pub unsafe fn add<V: SimdVec>(a: &[V::Elem], sums: &mut [V::Elem]) {
+                unsafe { super::add_kernel::<V>(a, sums) }
+            }
+
+            #[target_feature(enable = $features)]
+            pub unsafe fn squared_error<V: SimdVec>(
+                output: &[V::Elem],
+                expected: &[V::Elem],
+                diff: &mut [V::Elem],
+            ) -> V::Elem {
+                unsafe { super::squared_error_kernel::<V>(output, expected, diff) }
+            }
+        }
+    };
+}
+
+impl_target_kernels!(avx2, "avx2,fma");
+impl_target_kernels!(sse2, "sse2");
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum InstructionSet {
+    Avx2,
+    Sse2,
+    Scalar,
+}
+
+impl InstructionSet {
+    /// the best supported instruction set, the detection is cached by std so this is cheap to call
+    #[inline]
+    fn detect() -> Self {
+        if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+            InstructionSet::Avx2
+        } else if is_x86_feature_detected!("sse2") {
+            InstructionSet::Sse2
+        } else {
+            InstructionSet::Scalar
+        }
+    }
+}
+
+macro_rules! dispatch {
+    ($kernel:ident::<$dt:ident>($($arg:expr),*)) => {
+        match InstructionSet::detect() {
+            InstructionSet::Avx2 => unsafe { avx2::$kernel::<$dt::Avx2>($($arg),*) },
+            InstructionSet::Sse2 => unsafe { sse2::$kernel::<$dt::Sse2>($($arg),*) },
+            InstructionSet::Scalar => scalar::$kernel($($arg),*),
+        }
+    };
+}
+
+pub fn sigmoid<DT: SimdFloat>(activation: &[DT], output: &mut [DT]) {
+    assert_eq!(activation.len(), output.len());
+    dispatch!(sigmoid::<DT>(activation, output))
+}
+
+pub fn swish<DT: SimdFloat>(activation: &[DT], output: &mut [DT]) {
+    assert_eq!(activation.len(), output.len());
+    dispatch!(swish::<DT>(activation, output))
+}
+
+/// the softmax of a single row
+pub fn softmax<DT: SimdFloat>(activation: &[DT], output: &mut [DT]) {
+    assert_eq!(activation.len(), output.len());
+    dispatch!(softmax::<DT>(activation, output))
+}
+
+/// `b = alpha * a + beta * b`
+pub fn scale_add<DT: SimdFloat>(alpha: DT, a: &[DT], beta: DT, b: &mut [DT]) {
+    assert_eq!(a.len(), b.len());
+    dispatch!(scale_add::<DT>(alpha, a, beta, b))
+}
+
+/// `sums += a`
+pub fn add<DT: SimdFloat>(a: &[DT], sums: &mut [DT]) {
+    assert_eq!(a.len(), sums.len());
+    dispatch!(add::<DT>(a, sums))
+}
+
+/// writes `output - expected` to `diff` and returns the sum of its squares
+pub fn squared_error<DT: SimdFloat>(output: &[DT], expected: &[DT], diff: &mut [DT]) -> DT {
+    assert_eq!(output.len(), expected.len());
+    assert_eq!(output.len(), diff.len());
+    dispatch!(squared_error::<DT>(output, expected, diff))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{InstructionSet, SimdFloat, avx2, scalar, sse2};
+    use crate::dtype::DType;
+    use rand::distributions::Uniform;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn assert_close<DT: SimdFloat>(actual: &[DT], expected: &[DT], tolerance: f64) {
+        assert_eq!(actual.len(), expected.len());
+        for (i, (&a, &e)) in actual.iter().zip(expected).enumerate() {
+            let (a, e) = (DType::to_f64(&a), DType::to_f64(&e));
+            assert!((a - e).abs() <= tolerance * e.abs().max(1.0), "{i}: {a} != {e}");
+        }
+    }
+
+    /// compares the kernels of an instruction set against the scalar loops
+    macro_rules! check_kernels {
+        ($name:ident, $vec:ident, $dt:ty, $tolerance:expr) => {{
+            let tolerance = $tolerance;
+            let mut rng = StdRng::seed_from_u64(0x18);
+            // not a multiple of the number of lanes, and wide enough to saturate exp
+            let len = 1003;
+            let a: Vec<$dt> = (&mut rng).sample_iter(Uniform::new(-120.0, 120.0)).take(len).collect();
+            let b: Vec<$dt> = (&mut rng).sample_iter(Uniform::new(-2.0, 2.0)).take(len).collect();
+            let small: Vec<$dt> = b.iter().map(|&x| x * 4.0).collect();
+
+            type V = <$dt as SimdFloat>::$vec;
+            let mut actual = vec![0.0; len];
+            let mut expected = vec![0.0; len];
+            for input in [&a, &small] {
+                unsafe { $name::sigmoid::<V>(input, &mut actual) };
+                scalar::sigmoid(input, &mut expected);
+                assert_close(&actual, &expected, tolerance);
+
+                unsafe { $name::swish::<V>(input, &mut actual) };
+                scalar::swish(input, &mut expected);
+                assert_close(&actual, &expected, tolerance);
+
+                for n in [1, 7, len] {
+                    unsafe { $name::softmax::<V>(&input[..n], &mut actual[..n]) };
+                    scalar::softmax(&input[..n], &mut expected[..n]);
+                    assert_close(&actual[..n], &expected[..n], tolerance);
+                }
+            }
+
+            let mut actual = b.clone();
+            let mut expected = b.clone();
+            unsafe { $name::scale_add::<V>(0.5, &a, -1.5, &mut actual) };
+            scalar::scale_add(0.5, &a, -1.5, &mut expected);
+            assert_close(&actual, &expected, tolerance);
+
+            unsafe { $name::add::<V>(&a, &mut actual) };
+            scalar::add(&a, &mut expected);
+            assert_close(&actual, &expected, tolerance);
+
+            let sum_actual = unsafe { $name::squared_error::<V>(&a, &b, &mut actual) };
+            let sum_expected = scalar::squared_error(&a, &b, &mut expected);
+            assert_close(&actual, &expected, tolerance);
+            assert_close(&[sum_actual], &[sum_expected], tolerance);
+        }};
+    }
+
+    #[test]
+    fn test_kernels() {
+        let instruction_set = InstructionSet::detect();
+        if instruction_set == InstructionSet::Avx2 {
+            check_kernels!(avx2, Avx2, f32, 1e-6);
+            check_kernels!(avx2, Avx2, f64, 1e-14);
+        }
+        if instruction_set != InstructionSet::Scalar {
+            check_kernels!(sse2, Sse2, f32, 1e-6);
+            check_kernels!(sse2, Sse2, f64, 1e-14);
+        }
+    }
+}
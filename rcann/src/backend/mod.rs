@@ -1,6 +1,6 @@
 use crate::dtype::DTypeFloat;
-use crate::tensor::{Dim1, Dim2, Dims, DimsMore, ITensor, Tensor, TensorBase, TensorBaseMut, TensorView};
-use std::fmt::Debug;
+use crate::tensor::{Dim1, Dim2, Dim3, Dims, DimsMore, ITensor, Tensor, TensorBase, TensorBaseMut, TensorView};
+use std::fmt::{Debug, Display};
 
 mod cpu;
 
@@ -8,6 +8,8 @@ pub use cpu::*;
 
 pub trait TensorTyped {
     type Float: DTypeFloat;
+    /// raised by the fallible ops of the backend, e.g. when a device runs out of memory
+    type Error: std::error::Error + Send + Sync + 'static;
     type Tensor<D: Dims>: ITensor<D>;
     type TensorRef<'a, D: Dims>: ITensor<D> + Clone + From<&'a Self::Tensor<D>>
     where
@@ -16,51 +18,117 @@ pub trait TensorTyped {
     type OutputAdaptionBuff<D: Dims>;
 }
 
+/// Allocating, reading and writing tensors can fail, so each of these ops has a `try_` variant returning the
+/// error of the backend, with the plain variant panicking instead. All other ops can't return errors directly,
+/// since they may run asynchronously. A backend which fails to run one of them records the error, skips
+/// the ops which follow, and returns the error from the next call to [TensorOps::take_error] or read.
 pub trait TensorOps: TensorTyped {
-    fn new_tensor_exact<D: Dims>(&self, dim: D) -> Self::Tensor<D>;
-    fn new_tensor_batch_sized<D: DimsMore>(&self, inner_dims: D) -> Self::Tensor<D::More>;
+    fn try_new_tensor_exact<D: Dims>(&self, dim: D) -> Result<Self::Tensor<D>, Self::Error>;
+    fn try_new_tensor_batch_sized<D: DimsMore>(&self, inner_dims: D) -> Result<Self::Tensor<D::More>, Self::Error>;
     fn resize_tensor<D: Dims>(&self, tensor: &mut Self::Tensor<D>, dims: D);
-    fn write_tensor<T, D>(&self, tensor: &mut Self::Tensor<D>, native_src: &T)
+    fn try_write_tensor<T, D>(&self, tensor: &mut Self::Tensor<D>, native_src: &T) -> Result<(), Self::Error>
     where
         T: TensorBase<Self::Float, D>,
         D: Dims;
-    fn read_tensor<T, D>(&self, tensor: &Self::Tensor<D>, native_dst: &mut T)
+    fn try_read_tensor<T, D>(&self, tensor: &Self::Tensor<D>, native_dst: &mut T) -> Result<(), Self::Error>
     where
         T: TensorBaseMut<Self::Float, D>,
         D: Dims;
 
+    /// returns the first error recorded by an op since the last call, see [TensorOps]
+    fn take_error(&self) -> Result<(), Self::Error>;
+
+    fn new_tensor_exact<D: Dims>(&self, dim: D) -> Self::Tensor<D> {
+        expect_op(self.try_new_tensor_exact(dim))
+    }
+
+    fn new_tensor_batch_sized<D: DimsMore>(&self, inner_dims: D) -> Self::Tensor<D::More> {
+        expect_op(self.try_new_tensor_batch_sized(inner_dims))
+    }
+
+    fn write_tensor<T, D>(&self, tensor: &mut Self::Tensor<D>, native_src: &T)
+    where
+        T: TensorBase<Self::Float, D>,
+        D: Dims,
+    {
+        expect_op(self.try_write_tensor(tensor, native_src))
+    }
+
+    fn read_tensor<T, D>(&self, tensor: &Self::Tensor<D>, native_dst: &mut T)
+    where
+        T: TensorBaseMut<Self::Float, D>,
+        D: Dims,
+    {
+        expect_op(self.try_read_tensor(tensor, native_dst))
+    }
+
     fn resize_tensor_major<D: Dims>(&self, tensor: &mut Self::Tensor<D>, size: usize) {
         self.resize_tensor(tensor, tensor.dims().resize_major(size));
     }
 
+    fn try_new_tensor_from_native<T, D>(&self, native: T) -> Result<Self::Tensor<D>, Self::Error>
+    where
+        T: TensorBase<Self::Float, D>,
+        D: Dims,
+    {
+        let mut tensor = self.try_new_tensor_exact(*native.dims())?;
+        self.try_write_tensor(&mut tensor, &native)?;
+        Ok(tensor)
+    }
+
     fn new_tensor_from_native<T, D>(&self, native: T) -> Self::Tensor<D>
     where
         T: TensorBase<Self::Float, D>,
         D: Dims,
     {
-        let mut tensor = self.new_tensor_exact(*native.dims());
-        self.write_tensor(&mut tensor, &native);
-        tensor
+        expect_op(self.try_new_tensor_from_native(native))
     }
 
-    fn tensor_as_native<D: Dims>(&self, tensor: &Self::Tensor<D>) -> Tensor<Self::Float, D> {
+    fn try_tensor_as_native<D: Dims>(&self, tensor: &Self::Tensor<D>) -> Result<Tensor<Self::Float, D>, Self::Error> {
         let mut native = Tensor::zeroed(tensor.dims().clone());
-        self.read_tensor(&tensor, &mut native);
-        native
+        self.try_read_tensor(&tensor, &mut native)?;
+        Ok(native)
     }
 
-    fn new_input_adaption_buff<D: DimsMore>(&self, inner_dims: D) -> Self::InputAdaptionBuff<D::More>;
+    fn tensor_as_native<D: Dims>(&self, tensor: &Self::Tensor<D>) -> Tensor<Self::Float, D> {
+        expect_op(self.try_tensor_as_native(tensor))
+    }
+
+    fn try_new_input_adaption_buff<D: DimsMore>(
+        &self,
+        inner_dims: D,
+    ) -> Result<Self::InputAdaptionBuff<D::More>, Self::Error>;
     fn new_output_adaption_buff<D: DimsMore>(&self, inner_dims: D) -> Self::OutputAdaptionBuff<D::More>;
+    fn try_adapt_input<'a, D: Dims>(
+        &self,
+        buff: &'a mut Self::InputAdaptionBuff<D>,
+        input: TensorView<'a, Self::Float, D>,
+    ) -> Result<Self::TensorRef<'a, D>, Self::Error>;
+    fn try_adapt_output<'a, D: Dims>(
+        &self,
+        buff: &'a mut Self::OutputAdaptionBuff<D>,
+        output: &'a Self::Tensor<D>,
+    ) -> Result<&'a Tensor<Self::Float, D>, Self::Error>;
+
+    fn new_input_adaption_buff<D: DimsMore>(&self, inner_dims: D) -> Self::InputAdaptionBuff<D::More> {
+        expect_op(self.try_new_input_adaption_buff(inner_dims))
+    }
+
     fn adapt_input<'a, D: Dims>(
         &self,
         buff: &'a mut Self::InputAdaptionBuff<D>,
         input: TensorView<'a, Self::Float, D>,
-    ) -> Self::TensorRef<'a, D>;
+    ) -> Self::TensorRef<'a, D> {
+        expect_op(self.try_adapt_input(buff, input))
+    }
+
     fn adapt_output<'a, D: Dims>(
         &self,
         buff: &'a mut Self::OutputAdaptionBuff<D>,
         output: &'a Self::Tensor<D>,
-    ) -> &'a Tensor<Self::Float, D>;
+    ) -> &'a Tensor<Self::Float, D> {
+        expect_op(self.try_adapt_output(buff, output))
+    }
 
     fn debug_tensor<D: Dims>(&self, tensor: &Self::Tensor<D>);
 
@@ -68,7 +136,7 @@ pub trait TensorOps: TensorTyped {
 }
 
 pub trait MatrixMultiplication: TensorTyped {
-    /// performs a generic matrix multiplication (gemm) operation
+    /// performs a generic matrix multiplication (gemm) operation, errors are recorded as described in [TensorOps]
     fn matmul(
         &self,
         alpha: Self::Float,
@@ -79,6 +147,24 @@ pub trait MatrixMultiplication: TensorTyped {
         beta: Self::Float,
         c: &mut Self::Tensor<Dim2>,
     );
+
+    /// performs a generic matrix multiplication (gemm) operation, returning any error
+    fn try_matmul(
+        &self,
+        alpha: Self::Float,
+        a: Self::TensorRef<'_, Dim2>,
+        ta: bool,
+        b: Self::TensorRef<'_, Dim2>,
+        tb: bool,
+        beta: Self::Float,
+        c: &mut Self::Tensor<Dim2>,
+    ) -> Result<(), Self::Error>
+    where
+        Self: TensorOps,
+    {
+        self.matmul(alpha, a, ta, b, tb, beta, c);
+        self.take_error()
+    }
 }
 
 pub trait BackendOther: TensorTyped {
@@ -86,8 +172,46 @@ pub trait BackendOther: TensorTyped {
 
     fn add_assign<D: Dims>(&self, alpha: Self::Float, a: &Self::Tensor<D>, beta: Self::Float, b: &mut Self::Tensor<D>);
 
+    /// computes `b = alpha * a + beta * b` where `a` is broadcast across every row of `b`
+    fn add_assign_row_broadcast(
+        &self,
+        alpha: Self::Float,
+        a: &Self::Tensor<Dim1>,
+        beta: Self::Float,
+        b: &mut Self::Tensor<Dim2>,
+    );
+
+    /// performs an adaptive gradient update (e.g. Adagrad, RMSProp) for all elements in a given tensor:
+    /// `accum = decay * accum + scale * grad^2`, then `params -= learn_rate * grad / (sqrt(accum) + epsilon)`
+    fn adaptive_update<D: Dims>(
+        &self,
+        learn_rate: Self::Float,
+        decay: Self::Float,
+        scale: Self::Float,
+        epsilon: Self::Float,
+        grad: &Self::Tensor<D>,
+        accum: &mut Self::Tensor<D>,
+        params: &mut Self::Tensor<D>,
+    );
+
+    /// performs an Adam update for all elements in a given tensor:
+    /// `m = beta1 * m + (1 - beta1) * grad`, `v = beta2 * v + (1 - beta2) * grad^2`,
+    /// then `params = (1 - decay) * params - step_size * m / (sqrt(v) + epsilon)`
+    fn adam_update<D: Dims>(
+        &self,
+        step_size: Self::Float,
+        beta1: Self::Float,
+        beta2: Self::Float,
+        epsilon: Self::Float,
+        decay: Self::Float,
+        grad: &Self::Tensor<D>,
+        m: &mut Self::Tensor<D>,
+        v: &mut Self::Tensor<D>,
+        params: &mut Self::Tensor<D>,
+    );
+
     /// computes the sigmoid function for all elements in a given tensor
-    fn sigmoid(&self, activation: &Self::Tensor<Dim2>, output: &mut Self::Tensor<Dim2>);
+    fn sigmoid(&self, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>);
     fn sigmoid_error(
         &self,
         output: &Self::Tensor<Dim2>,
@@ -96,16 +220,74 @@ pub trait BackendOther: TensorTyped {
     );
 
     /// computes the leaky ReLU function for all elements in a given tensor
-    fn relu(&self, leak: Self::Float, activation: &Self::Tensor<Dim2>, output: &mut Self::Tensor<Dim2>);
+    fn relu(&self, leak: Self::Float, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>);
     fn relu_error(
         &self,
         leak: Self::Float,
-        activation: &Self::Tensor<Dim2>,
+        activation: Self::TensorRef<'_, Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        result: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes the hyperbolic tangent for all elements in a given tensor
+    fn tanh(&self, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>);
+    fn tanh_error(
+        &self,
+        output: &Self::Tensor<Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        result: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes `scale * x` for positive elements and `scale * alpha * (exp(x) - 1)` otherwise,
+    /// which is ELU for a `scale` of one and SELU for its fixed `alpha` and `scale`
+    fn elu(
+        &self,
+        alpha: Self::Float,
+        scale: Self::Float,
+        activation: Self::TensorRef<'_, Dim2>,
+        output: &mut Self::Tensor<Dim2>,
+    );
+    fn elu_error(
+        &self,
+        alpha: Self::Float,
+        scale: Self::Float,
+        activation: Self::TensorRef<'_, Dim2>,
         out_error: &Self::Tensor<Dim2>,
         result: &mut Self::Tensor<Dim2>,
     );
 
-    fn softmax(&self, activation: &Self::Tensor<Dim2>, output: &mut Self::Tensor<Dim2>);
+    /// computes the tanh approximation of GELU for all elements in a given tensor
+    fn gelu(&self, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>);
+    fn gelu_error(
+        &self,
+        activation: Self::TensorRef<'_, Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        result: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes `x * sigmoid(x)` for all elements in a given tensor
+    fn swish(&self, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>);
+    fn swish_error(
+        &self,
+        activation: Self::TensorRef<'_, Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        result: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes `ln(1 + exp(x))` for all elements in a given tensor
+    fn softplus(&self, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>);
+    fn softplus_error(
+        &self,
+        activation: Self::TensorRef<'_, Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        result: &mut Self::Tensor<Dim2>,
+    );
+
+    /// copies the activation to the output
+    fn identity(&self, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>);
+    fn identity_error(&self, out_error: &Self::Tensor<Dim2>, result: &mut Self::Tensor<Dim2>);
+
+    fn softmax(&self, activation: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>);
     fn softmax_error(
         &self,
         output: &Self::Tensor<Dim2>,
@@ -121,6 +303,188 @@ pub trait BackendOther: TensorTyped {
         result_deriv: &mut Self::Tensor<Dim2>,
     );
 
+    /// computes the categorical cross-entropy `-sum(e * ln(o))` of each row, where `output` is expected
+    /// to be a probability distribution (e.g. the output of softmax)
+    fn categorical_cross_entropy(
+        &self,
+        output: &Self::Tensor<Dim2>,
+        expected: Self::TensorRef<'_, Dim2>,
+        result: &mut Self::Tensor<Dim1>,
+        result_deriv: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes the binary cross-entropy `-mean(e * ln(o) + (1 - e) * ln(1 - o))` of each row, where each
+    /// output is expected to be an independent probability (e.g. the output of sigmoid)
+    fn binary_cross_entropy(
+        &self,
+        output: &Self::Tensor<Dim2>,
+        expected: Self::TensorRef<'_, Dim2>,
+        result: &mut Self::Tensor<Dim1>,
+        result_deriv: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes the categorical cross-entropy of `softmax(output)` for each row directly from the logits,
+    /// which avoids taking the log of a rounded probability and yields the gradient w.r.t. the logits
+    fn softmax_cross_entropy(
+        &self,
+        output: &Self::Tensor<Dim2>,
+        expected: Self::TensorRef<'_, Dim2>,
+        result: &mut Self::Tensor<Dim1>,
+        result_deriv: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes the 2d convolution (cross-correlation) of each input image with each filter in `kernel`,
+    /// which has dims `(filters, channels * size * size)`. Output rows hold `(filters, out_height, out_width)`.
+    fn conv2d(
+        &self,
+        window: &Window2d,
+        input: Self::TensorRef<'_, Dim2>,
+        kernel: &Self::Tensor<Dim2>,
+        bias: Option<&Self::Tensor<Dim1>>,
+        output: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes the error of the input of `conv2d` given the error of its output
+    fn conv2d_input_error(
+        &self,
+        window: &Window2d,
+        out_error: &Self::Tensor<Dim2>,
+        kernel: &Self::Tensor<Dim2>,
+        input_error: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes the gradients of the kernel and bias of `conv2d` given the error of its output
+    fn conv2d_param_grad(
+        &self,
+        window: &Window2d,
+        input: Self::TensorRef<'_, Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        kernel_grad: &mut Self::Tensor<Dim2>,
+        bias_grad: Option<&mut Self::Tensor<Dim1>>,
+    );
+
+    /// computes the max of each window in each channel of the input images. The position of each max within
+    /// its window is written to `indices`, which routes the error back in `max_pool2d_error`.
+    fn max_pool2d(
+        &self,
+        window: &Window2d,
+        input: Self::TensorRef<'_, Dim2>,
+        output: &mut Self::Tensor<Dim2>,
+        indices: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes the error of the input of `max_pool2d`, given the indices it produced
+    fn max_pool2d_error(
+        &self,
+        window: &Window2d,
+        indices: &Self::Tensor<Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        input_error: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes the mean of each window in each channel of the input images, excluding any padding
+    fn avg_pool2d(&self, window: &Window2d, input: Self::TensorRef<'_, Dim2>, output: &mut Self::Tensor<Dim2>);
+
+    /// computes the error of the input of `avg_pool2d`
+    fn avg_pool2d_error(
+        &self,
+        window: &Window2d,
+        out_error: &Self::Tensor<Dim2>,
+        input_error: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes the mean and the (biased) variance of each column
+    fn column_mean_var(
+        &self,
+        a: Self::TensorRef<'_, Dim2>,
+        mean: &mut Self::Tensor<Dim1>,
+        var: &mut Self::Tensor<Dim1>,
+    );
+
+    /// normalizes each column by the given mean and variance, then scales and shifts it by `gamma` and `beta`.
+    /// The values before scaling are written to `normalized`, which is needed for backprop.
+    fn batch_norm(
+        &self,
+        epsilon: Self::Float,
+        input: Self::TensorRef<'_, Dim2>,
+        mean: &Self::Tensor<Dim1>,
+        var: &Self::Tensor<Dim1>,
+        gamma: &Self::Tensor<Dim1>,
+        beta: &Self::Tensor<Dim1>,
+        normalized: &mut Self::Tensor<Dim2>,
+        output: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes the gradients of `gamma` and `beta` of `batch_norm`
+    fn batch_norm_param_grad(
+        &self,
+        normalized: &Self::Tensor<Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        gamma_grad: &mut Self::Tensor<Dim1>,
+        beta_grad: &mut Self::Tensor<Dim1>,
+    );
+
+    /// computes the error of the input of `batch_norm` when it was normalized by the statistics of the batch
+    /// itself, given the gradients computed by `batch_norm_param_grad`
+    fn batch_norm_input_error(
+        &self,
+        epsilon: Self::Float,
+        normalized: &Self::Tensor<Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        var: &Self::Tensor<Dim1>,
+        gamma: &Self::Tensor<Dim1>,
+        gamma_grad: &Self::Tensor<Dim1>,
+        beta_grad: &Self::Tensor<Dim1>,
+        input_error: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes the mean and the (biased) variance of each row
+    fn row_mean_var(&self, a: Self::TensorRef<'_, Dim2>, mean: &mut Self::Tensor<Dim1>, var: &mut Self::Tensor<Dim1>);
+
+    /// normalizes each row by the given mean and variance of that row, then scales and shifts each column by
+    /// `gain` and `bias`. The values before scaling are written to `normalized`, which is needed for backprop.
+    fn layer_norm(
+        &self,
+        epsilon: Self::Float,
+        input: Self::TensorRef<'_, Dim2>,
+        mean: &Self::Tensor<Dim1>,
+        var: &Self::Tensor<Dim1>,
+        gain: &Self::Tensor<Dim1>,
+        bias: &Self::Tensor<Dim1>,
+        normalized: &mut Self::Tensor<Dim2>,
+        output: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes the error of the input of `layer_norm`. The gradients of `gain` and `bias` are computed the same
+    /// way as for `batch_norm`, see `batch_norm_param_grad`.
+    fn layer_norm_input_error(
+        &self,
+        epsilon: Self::Float,
+        normalized: &Self::Tensor<Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        var: &Self::Tensor<Dim1>,
+        gain: &Self::Tensor<Dim1>,
+        input_error: &mut Self::Tensor<Dim2>,
+    );
+
+    /// multiplies each input by 0 where it is dropped by the mask and by `1 / (1 - rate)` otherwise,
+    /// writing the factor applied to each value to `scale`
+    fn dropout(
+        &self,
+        mask: &DropoutMask,
+        input: Self::TensorRef<'_, Dim2>,
+        output: &mut Self::Tensor<Dim2>,
+        scale: &mut Self::Tensor<Dim2>,
+    );
+
+    /// computes the error of the input of `dropout`, given the scale it produced
+    fn dropout_error(
+        &self,
+        scale: &Self::Tensor<Dim2>,
+        out_error: &Self::Tensor<Dim2>,
+        input_error: &mut Self::Tensor<Dim2>,
+    );
+
     fn flush(&self);
     fn sync(&self);
 
@@ -132,6 +496,123 @@ pub trait BackendOther: TensorTyped {
     );
 }
 
+/// A square window sliding over images, such as a convolution kernel or a pooling window.
+/// Each image is stored as a row of a [Dim2] tensor in `(channels, height, width)` order.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Window2d {
+    /// the `(channels, height, width)` of each input image
+    pub input_dims: Dim3,
+    pub size: usize,
+    pub stride: usize,
+    /// number of zeros implicitly added to each side of the input
+    pub padding: usize,
+}
+
+impl Window2d {
+    #[inline]
+    pub fn channels(&self) -> usize {
+        self.input_dims.0
+    }
+    #[inline]
+    pub fn output_height(&self) -> usize {
+        (self.input_dims.1 + 2 * self.padding - self.size) / self.stride + 1
+    }
+    #[inline]
+    pub fn output_width(&self) -> usize {
+        (self.input_dims.2 + 2 * self.padding - self.size) / self.stride + 1
+    }
+    /// the dims of each output image with the given number of channels
+    #[inline]
+    pub fn output_dims(&self, channels: usize) -> Dim3 {
+        Dim3(channels, self.output_height(), self.output_width())
+    }
+    /// the number of input values covered by the window across all channels
+    #[inline]
+    pub fn patch_len(&self) -> usize {
+        self.channels() * self.size * self.size
+    }
+    pub fn is_valid(&self) -> bool {
+        let Dim3(channels, height, width) = self.input_dims;
+        channels > 0
+            && self.size > 0
+            && self.stride > 0
+            && self.padding < self.size
+            && self.size <= height + 2 * self.padding
+            && self.size <= width + 2 * self.padding
+    }
+}
+
+/// A dropout mask that is generated from a counter-based hash of the seed and the position of each value,
+/// so that every backend drops exactly the same values for the same seed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct DropoutMask {
+    seed: u32,
+    /// values whose 24-bit hash is below the threshold are dropped
+    threshold: u32,
+}
+
+impl DropoutMask {
+    const HASH_BITS: u32 = 24;
+
+    /// a mask dropping values with probability `rate`, which is unique for each `(seed, step)`
+    pub fn new(rate: f64, seed: u64, step: u64) -> Self {
+        assert!((0.0..1.0).contains(&rate), "Invalid dropout rate: {rate}");
+        let mut hash = hash32((step >> 32) as u32);
+        for word in [step as u32, (seed >> 32) as u32, seed as u32] {
+            hash = hash32(word ^ hash);
+        }
+        DropoutMask {
+            seed: hash,
+            threshold: (rate * (1 << Self::HASH_BITS) as f64).round() as u32,
+        }
+    }
+
+    /// a mask which keeps every value
+    pub fn identity() -> Self {
+        DropoutMask { seed: 0, threshold: 0 }
+    }
+
+    #[inline]
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    #[inline]
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    /// whether the value at the given row-major position of a tensor is kept
+    #[inline]
+    pub fn keep(&self, idx: usize) -> bool {
+        hash32(idx as u32 ^ self.seed) >> (32 - Self::HASH_BITS) >= self.threshold
+    }
+
+    /// the factor applied to kept values, which is exactly the inverse of the probability of keeping a value
+    #[inline]
+    pub fn scale(&self) -> f64 {
+        let range = (1 << Self::HASH_BITS) as f64;
+        range / (range - self.threshold as f64)
+    }
+}
+
+/// the "lowbias32" integer hash, which is replicated by the OpenCL dropout kernel
+#[inline]
+pub fn hash32(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846ca68b);
+    x ^= x >> 16;
+    x
+}
+
+/// unwraps the result of a fallible op for the panicking variants of [TensorOps]
+#[inline]
+fn expect_op<T, E: Display>(result: Result<T, E>) -> T {
+    result.unwrap_or_else(|err| panic!("Backend op failed: {err}"))
+}
+
 pub trait Backend: 'static + Debug + TensorTyped + TensorOps + MatrixMultiplication + BackendOther {}
 
 pub trait PreparedDataset {
@@ -3,6 +3,7 @@ pub mod backend;
 pub mod dtype;
 pub mod loss;
 pub mod net;
+pub mod optimizer;
 pub mod scoring;
 pub mod tensor;
 pub mod util;
@@ -12,6 +13,7 @@ extern crate approx;
 extern crate matrixmultiply;
 extern crate num_traits;
 extern crate rand;
+extern crate rand_chacha;
 extern crate rand_distr;
 #[cfg(feature = "serde")]
 extern crate serde;
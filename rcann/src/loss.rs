@@ -1,10 +1,20 @@
 use crate::backend::Backend;
 use crate::tensor::{Dim1, Dim2};
 
-#[derive(Copy, Clone, Default)]
+/// Lower bound applied to probabilities before taking their log in the cross-entropy loss functions
+pub const CROSS_ENTROPY_EPSILON: f64 = 1e-7;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub enum LossFn {
     #[default]
     MSE,
+    /// Categorical cross-entropy, for outputs which form a probability distribution (e.g. softmax)
+    CategoricalCrossEntropy,
+    /// Binary cross-entropy, for outputs which are independent probabilities (e.g. sigmoid)
+    BinaryCrossEntropy,
+    /// Softmax followed by categorical cross-entropy, computed directly from the raw (linear) outputs.
+    /// The output layer should therefore not apply an activation of its own, i.e. use `ActivationFn::Identity`
+    SoftmaxCrossEntropy,
 }
 
 impl LossFn {
@@ -18,6 +28,41 @@ impl LossFn {
     ) {
         match self {
             LossFn::MSE => backend.mean_squared_error(output, expected, result, result_deriv),
+            LossFn::CategoricalCrossEntropy => {
+                backend.categorical_cross_entropy(output, expected, result, result_deriv)
+            }
+            LossFn::BinaryCrossEntropy => backend.binary_cross_entropy(output, expected, result, result_deriv),
+            LossFn::SoftmaxCrossEntropy => backend.softmax_cross_entropy(output, expected, result, result_deriv),
         }
     }
 }
+
+#[cfg(all(test, feature = "approx"))]
+mod test {
+    use crate::backend::{BackendOther, CpuBackend};
+    use crate::tensor::{Dim1, Dim2, Tensor1, Tensor2, TensorBase};
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn test_softmax_cross_entropy_matches_categorical() {
+        let cpu = CpuBackend::<f64>::new(0);
+        let logits = Tensor2::from_vec(vec![1.0, -2.0, 0.5, 3.0, 0.0, 0.0, -1.0, 2.5, -0.5], Dim2(3, 3));
+        let expected = Tensor2::from_vec(vec![0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.2, 0.5, 0.3], Dim2(3, 3));
+
+        let mut result = Tensor1::zeroed(Dim1(3));
+        let mut result_deriv = Tensor2::zeroed(Dim2(3, 3));
+        cpu.softmax_cross_entropy(&logits, expected.view(), &mut result, &mut result_deriv);
+
+        // softmax followed by categorical cross-entropy, back-propagated through softmax
+        let mut output = Tensor2::zeroed(Dim2(3, 3));
+        cpu.softmax(logits.view(), &mut output);
+        let mut expected_result = Tensor1::zeroed(Dim1(3));
+        let mut output_deriv = Tensor2::zeroed(Dim2(3, 3));
+        cpu.categorical_cross_entropy(&output, expected.view(), &mut expected_result, &mut output_deriv);
+        let mut expected_result_deriv = Tensor2::zeroed(Dim2(3, 3));
+        cpu.softmax_error(&output, &output_deriv, &mut expected_result_deriv);
+
+        assert_abs_diff_eq!(result, expected_result, epsilon = 1e-9);
+        assert_abs_diff_eq!(result_deriv, expected_result_deriv, epsilon = 1e-9);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::activation::ActivationFn;
+use crate::backend::Backend;
+use crate::net::layer::{ConcreteLayerParams, Layer, LayerParams, NetInitializer};
+use crate::net::model::{ModelError, ModelResult, ParamTensor};
+use crate::optimizer::Optimizer;
+use crate::tensor::{Dim2, ITensor};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+
+/// Applies an activation function to the output of the previous layer, for layers which don't apply one
+/// themselves such as the normalization layers.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ActivationLayerParams {
+    pub activation_fn: ActivationFn,
+}
+
+impl ActivationLayerParams {
+    pub fn new(activation_fn: ActivationFn) -> Self {
+        ActivationLayerParams { activation_fn }
+    }
+}
+
+impl<B: Backend> LayerParams<B> for ActivationLayerParams {
+    type Layer = ActivationLayer<B>;
+
+    fn create_layer(
+        &self,
+        _backend: &B,
+        _layer_idx: usize,
+        input_size: usize,
+        _initializer: &mut dyn NetInitializer<B::Float>,
+    ) -> Self::Layer {
+        ActivationLayer {
+            size: input_size,
+            activation_fn: self.activation_fn,
+            _backend: PhantomData,
+        }
+    }
+}
+
+impl Into<ConcreteLayerParams> for ActivationLayerParams {
+    fn into(self) -> ConcreteLayerParams {
+        ConcreteLayerParams::Activation(self)
+    }
+}
+
+impl Into<ConcreteLayerParams> for ActivationFn {
+    fn into(self) -> ConcreteLayerParams {
+        ConcreteLayerParams::Activation(ActivationLayerParams::new(self))
+    }
+}
+
+pub struct ActivationLayer<B: Backend> {
+    size: usize,
+    activation_fn: ActivationFn,
+    _backend: PhantomData<B>,
+}
+
+impl<B: Backend> Layer<B> for ActivationLayer<B> {
+    fn forward(&mut self, backend: &B, input: B::TensorRef<'_, Dim2>, output: &mut B::Tensor<Dim2>, _training: bool) {
+        let num_rows = input.dims().rows();
+
+        assert_eq!(
+            input.dims().cols(),
+            self.size,
+            "Invalid number of columns for input tensor"
+        );
+        assert_eq!(
+            output.dims(),
+            &Dim2(num_rows, self.size),
+            "Invalid dimensions for output tensor"
+        );
+
+        self.activation_fn.compute(backend, input, output);
+    }
+
+    fn backprop(
+        &mut self,
+        backend: &B,
+        input: B::TensorRef<'_, Dim2>,
+        output: &B::Tensor<Dim2>,
+        input_error: Option<&mut B::Tensor<Dim2>>,
+        out_error: &B::Tensor<Dim2>,
+    ) {
+        let num_rows = input.dims().rows();
+
+        assert_eq!(
+            out_error.dims(),
+            &Dim2(num_rows, self.size),
+            "Invalid dimensions for out_error tensor"
+        );
+
+        if let Some(input_error) = input_error {
+            assert_eq!(
+                input_error.dims(),
+                &Dim2(num_rows, self.size),
+                "Invalid dimensions for input_error"
+            );
+            self.activation_fn
+                .compute_error(backend, input, output, out_error, input_error);
+        }
+    }
+
+    fn update_params(&mut self, _backend: &B, _optimizer: &mut Optimizer<B>) {}
+
+    fn export_params(&self, _backend: &B) -> Vec<ParamTensor> {
+        Vec::new()
+    }
+
+    fn import_params(&mut self, _backend: &B, params: &[ParamTensor]) -> ModelResult<()> {
+        if params.is_empty() {
+            Ok(())
+        } else {
+            Err(ModelError::ParamMismatch(format!(
+                "expected no tensors for Activation layer, found {}",
+                params.len()
+            )))
+        }
+    }
+
+    #[inline]
+    fn input_size(&self) -> usize {
+        self.size
+    }
+
+    #[inline]
+    fn output_size(&self) -> usize {
+        self.size
+    }
+}
+
+impl<B: Backend> Debug for ActivationLayer<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ActivationLayer")
+            .field("size", &self.size)
+            .field("activation_fn", &self.activation_fn)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ActivationLayerParams;
+    use crate::activation::ActivationFn;
+    use crate::backend::CpuBackend;
+    use crate::loss::LossFn;
+    use crate::net::initializer::RandomNetInitializer;
+    use crate::net::layer::{DenseLayerParams, LayerNormLayerParams};
+    use crate::net::model::{ModelFormat, NetModel};
+    use crate::net::{Net, NetBuilder, TrainConfig};
+    use crate::optimizer::OptimizerFn;
+    use crate::tensor::{Tensor2, TensorBase};
+
+    #[test]
+    fn test_activation_layer() {
+        let dense = |size, activation_fn| DenseLayerParams {
+            size,
+            activation_fn,
+            use_bias: true,
+        };
+        let builder = || {
+            NetBuilder::new(CpuBackend::<f64>::new(4), 3).with_initializer(RandomNetInitializer::seed_from_u64(0x1234))
+        };
+        let train = |net: &mut Net<CpuBackend<f64>>| {
+            let input = Tensor2::from_vec_2d(vec![[0.5, -1.0, 2.0], [1.5, 0.25, -0.75], [-0.5, 1.0, 0.0]]);
+            let expected = Tensor2::from_vec_2d(vec![[2.5, -1.0], [0.0, 3.0], [-1.5, 0.5]]);
+            let config = TrainConfig {
+                loss: LossFn::MSE,
+                optimizer: OptimizerFn::sgd(0.05, 0.0),
+                num_epochs: 20,
+                ..TrainConfig::default()
+            };
+            let history = net.train(input.view(), expected.view(), &config).unwrap();
+            (history, net.predict(input.view()).unwrap().clone())
+        };
+
+        // a separate activation layer behaves the same as a dense layer's own activation
+        let leaky_relu = ActivationFn::ReLU { leak: 0.1 };
+        let mut fused = builder()
+            .with_layer(dense(6, leaky_relu))
+            .with_layer(dense(2, ActivationFn::Identity))
+            .build()
+            .unwrap();
+        let mut separate = builder()
+            .with_layer(dense(6, ActivationFn::Identity))
+            .with_layer(ActivationLayerParams::new(leaky_relu))
+            .with_layer(dense(2, ActivationFn::Identity))
+            .build()
+            .unwrap();
+        let (history, output) = train(&mut fused);
+        assert_eq!(output, train(&mut separate).1);
+        // the linear output can fit targets outside of (0, 1)
+        assert!(history.last().unwrap().loss < history.epochs[0].loss);
+
+        let mut normalized = builder()
+            .with_layer(dense(6, ActivationFn::Identity))
+            .with_layer(LayerNormLayerParams::default())
+            .with_layer(ActivationFn::Sigmoid)
+            .with_layer(dense(2, ActivationFn::Identity))
+            .build()
+            .unwrap();
+        train(&mut normalized);
+        let mut bytes = Vec::new();
+        normalized.to_model().write(&mut bytes, ModelFormat::Binary).unwrap();
+        let model = NetModel::read(bytes.as_slice(), ModelFormat::Binary).unwrap();
+        assert_eq!(model, normalized.to_model());
+        let mut loaded = Net::from_model(CpuBackend::<f64>::new(4), &model).unwrap();
+        assert_eq!(train(&mut loaded).1, train(&mut normalized).1);
+    }
+}
@@ -0,0 +1,395 @@
+use crate::backend::Backend;
+use crate::dtype::DType;
+use crate::net::layer::{ConcreteLayerParams, Layer, LayerParams, NetInitializer};
+use crate::net::model::{ModelError, ModelResult, ParamTensor};
+use crate::optimizer::Optimizer;
+use crate::tensor::{Dim1, Dim2, ITensor, Tensor1};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Formatter};
+
+/// Normalizes each column by the mean and variance of the batch while training, then applies a learnable
+/// scale and shift. Running averages of the statistics are tracked during training and used to normalize
+/// outside of training, so predictions don't depend on the other rows of the batch.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BatchNormLayerParams {
+    /// the fraction of the running statistics kept at each training step
+    pub momentum: f64,
+    /// added to the variance to avoid dividing by zero
+    pub epsilon: f64,
+}
+
+impl Default for BatchNormLayerParams {
+    fn default() -> Self {
+        BatchNormLayerParams {
+            momentum: 0.9,
+            epsilon: 1e-5,
+        }
+    }
+}
+
+impl<B: Backend> LayerParams<B> for BatchNormLayerParams {
+    type Layer = BatchNormLayer<B>;
+
+    fn create_layer(
+        &self,
+        backend: &B,
+        _layer_idx: usize,
+        input_size: usize,
+        _initializer: &mut dyn NetInitializer<B::Float>,
+    ) -> Self::Layer {
+        assert!(
+            (0.0..1.0).contains(&self.momentum),
+            "BatchNorm momentum must be in [0, 1), found {}",
+            self.momentum
+        );
+        assert!(self.epsilon > 0.0, "BatchNorm epsilon must be positive");
+        let filled = |value| backend.new_tensor_from_native(Tensor1::filled(value, Dim1(input_size)));
+        BatchNormLayer {
+            size: input_size,
+            momentum: self.momentum,
+            epsilon: self.epsilon,
+            gamma: filled(B::Float::ONE),
+            beta: filled(B::Float::ZERO),
+            running_mean: filled(B::Float::ZERO),
+            running_var: filled(B::Float::ONE),
+            batch_mean: backend.new_tensor_exact(Dim1(input_size)),
+            batch_var: backend.new_tensor_exact(Dim1(input_size)),
+            normalized: backend.new_tensor_batch_sized(Dim1(input_size)),
+            training_tensors: None,
+        }
+    }
+}
+
+impl Into<ConcreteLayerParams> for BatchNormLayerParams {
+    fn into(self) -> ConcreteLayerParams {
+        ConcreteLayerParams::BatchNorm(self)
+    }
+}
+
+pub struct BatchNormLayer<B: Backend> {
+    size: usize,
+    momentum: f64,
+    epsilon: f64,
+    gamma: B::Tensor<Dim1>,
+    beta: B::Tensor<Dim1>,
+    running_mean: B::Tensor<Dim1>,
+    running_var: B::Tensor<Dim1>,
+    batch_mean: B::Tensor<Dim1>,
+    batch_var: B::Tensor<Dim1>,
+    normalized: B::Tensor<Dim2>,
+    training_tensors: Option<TrainingTensors<B>>,
+}
+
+struct TrainingTensors<B: Backend> {
+    gamma_grad: B::Tensor<Dim1>,
+    beta_grad: B::Tensor<Dim1>,
+}
+
+impl<B: Backend> TrainingTensors<B> {
+    fn new(backend: &B, size: usize) -> Self {
+        TrainingTensors {
+            gamma_grad: backend.new_tensor_exact(Dim1(size)),
+            beta_grad: backend.new_tensor_exact(Dim1(size)),
+        }
+    }
+}
+
+impl<B: Backend> Layer<B> for BatchNormLayer<B> {
+    fn forward(&mut self, backend: &B, input: B::TensorRef<'_, Dim2>, output: &mut B::Tensor<Dim2>, training: bool) {
+        let num_rows = input.dims().rows();
+
+        assert_eq!(
+            input.dims().cols(),
+            self.size,
+            "Invalid number of columns for input tensor"
+        );
+        assert_eq!(
+            output.dims(),
+            &Dim2(num_rows, self.size),
+            "Invalid dimensions for output tensor"
+        );
+
+        let epsilon = B::Float::from_f64(self.epsilon);
+        backend.resize_tensor_major(&mut self.normalized, num_rows);
+        if training {
+            backend.column_mean_var(input.clone(), &mut self.batch_mean, &mut self.batch_var);
+            backend.batch_norm(
+                epsilon,
+                input,
+                &self.batch_mean,
+                &self.batch_var,
+                &self.gamma,
+                &self.beta,
+                &mut self.normalized,
+                output,
+            );
+            // the running variance is an unbiased estimate of the variance of the inputs
+            let momentum = B::Float::from_f64(self.momentum);
+            let update = B::Float::ONE - momentum;
+            let correction = if num_rows > 1 {
+                B::Float::from_usize(num_rows) / B::Float::from_usize(num_rows - 1)
+            } else {
+                B::Float::ONE
+            };
+            backend.add_assign(update, &self.batch_mean, momentum, &mut self.running_mean);
+            backend.add_assign(update * correction, &self.batch_var, momentum, &mut self.running_var);
+        } else {
+            backend.batch_norm(
+                epsilon,
+                input,
+                &self.running_mean,
+                &self.running_var,
+                &self.gamma,
+                &self.beta,
+                &mut self.normalized,
+                output,
+            );
+        }
+    }
+
+    fn backprop(
+        &mut self,
+        backend: &B,
+        input: B::TensorRef<'_, Dim2>,
+        _output: &B::Tensor<Dim2>,
+        input_error: Option<&mut B::Tensor<Dim2>>,
+        out_error: &B::Tensor<Dim2>,
+    ) {
+        let num_rows = input.dims().rows();
+
+        assert_eq!(
+            out_error.dims(),
+            &Dim2(num_rows, self.size),
+            "Invalid dimensions for out_error tensor"
+        );
+
+        let tt = self
+            .training_tensors
+            .get_or_insert_with(|| TrainingTensors::new(backend, self.size));
+
+        backend.batch_norm_param_grad(&self.normalized, out_error, &mut tt.gamma_grad, &mut tt.beta_grad);
+
+        if let Some(input_error) = input_error {
+            assert_eq!(
+                input_error.dims(),
+                &Dim2(num_rows, self.size),
+                "Invalid dimensions for input_error"
+            );
+            backend.batch_norm_input_error(
+                B::Float::from_f64(self.epsilon),
+                &self.normalized,
+                out_error,
+                &self.batch_var,
+                &self.gamma,
+                &tt.gamma_grad,
+                &tt.beta_grad,
+                input_error,
+            );
+        }
+    }
+
+    fn update_params(&mut self, backend: &B, optimizer: &mut Optimizer<B>) {
+        let tt = self
+            .training_tensors
+            .as_ref()
+            .expect("backprop must be called before update_params");
+        optimizer.update(backend, &mut self.gamma, &tt.gamma_grad);
+        optimizer.update(backend, &mut self.beta, &tt.beta_grad);
+    }
+
+    fn export_params(&self, backend: &B) -> Vec<ParamTensor> {
+        [&self.gamma, &self.beta, &self.running_mean, &self.running_var]
+            .into_iter()
+            .map(|tensor| ParamTensor::read_from::<B, _>(backend, tensor))
+            .collect()
+    }
+
+    fn import_params(&mut self, backend: &B, params: &[ParamTensor]) -> ModelResult<()> {
+        match params {
+            [gamma, beta, running_mean, running_var] => {
+                gamma.write_to::<B, _>(backend, &mut self.gamma)?;
+                beta.write_to::<B, _>(backend, &mut self.beta)?;
+                running_mean.write_to::<B, _>(backend, &mut self.running_mean)?;
+                running_var.write_to::<B, _>(backend, &mut self.running_var)
+            }
+            _ => Err(ModelError::ParamMismatch(format!(
+                "expected 4 tensors for BatchNorm layer, found {}",
+                params.len()
+            ))),
+        }
+    }
+
+    #[inline]
+    fn input_size(&self) -> usize {
+        self.size
+    }
+
+    #[inline]
+    fn output_size(&self) -> usize {
+        self.size
+    }
+}
+
+impl<B: Backend> Debug for BatchNormLayer<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BatchNormLayer")
+            .field("size", &self.size)
+            .field("momentum", &self.momentum)
+            .field("epsilon", &self.epsilon)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BatchNormLayerParams;
+    use crate::activation::ActivationFn;
+    use crate::backend::{BackendOther, CpuBackend};
+    use crate::loss::LossFn;
+    use crate::net::initializer::RandomNetInitializer;
+    use crate::net::layer::DenseLayerParams;
+    use crate::net::{NetBuilder, TrainConfig};
+    use crate::optimizer::OptimizerFn;
+    use crate::tensor::{Dim1, Dim2, ITensor, Tensor1, Tensor2, TensorBase};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rand_distr::StandardNormal;
+
+    /// checks the gradients of `batch_norm` with batch statistics against finite differences of
+    /// `sum(batch_norm(input) * out_error)`
+    #[test]
+    fn test_batch_norm_gradients() {
+        let backend = CpuBackend::<f64>::new(0);
+        let mut rng = StdRng::seed_from_u64(0xb4);
+        let dims = Dim2(5, 3);
+        let epsilon = 1e-5;
+        let input = Tensor2::from_distribution(&mut rng, StandardNormal, dims);
+        let gamma = Tensor1::from_distribution(&mut rng, StandardNormal, Dim1(3));
+        let beta = Tensor1::from_distribution(&mut rng, StandardNormal, Dim1(3));
+        let out_error = Tensor2::from_distribution(&mut rng, StandardNormal, dims);
+
+        let forward = |input: &Tensor2<f64>, gamma: &Tensor1<f64>, beta: &Tensor1<f64>| {
+            let mut mean = Tensor1::zeroed(Dim1(3));
+            let mut var = Tensor1::zeroed(Dim1(3));
+            let mut normalized = Tensor2::zeroed(dims);
+            let mut output = Tensor2::zeroed(dims);
+            backend.column_mean_var(input.view(), &mut mean, &mut var);
+            backend.batch_norm(
+                epsilon,
+                input.view(),
+                &mean,
+                &var,
+                gamma,
+                beta,
+                &mut normalized,
+                &mut output,
+            );
+            (var, normalized, output)
+        };
+        let loss = |input: &Tensor2<f64>, gamma: &Tensor1<f64>, beta: &Tensor1<f64>| {
+            let (_, _, output) = forward(input, gamma, beta);
+            output.iter().zip(out_error.iter()).map(|(o, e)| o * e).sum::<f64>()
+        };
+
+        let (var, normalized, output) = forward(&input, &gamma, &beta);
+        // each column of the normalized values has zero mean and unit variance
+        let mut mean = Tensor1::zeroed(Dim1(3));
+        let mut norm_var = Tensor1::zeroed(Dim1(3));
+        backend.column_mean_var(normalized.view(), &mut mean, &mut norm_var);
+        for (&m, &v) in mean.iter().zip(norm_var.iter()) {
+            assert!(m.abs() < 1e-9 && (v - 1.0).abs() < 1e-4, "mean {m}, var {v}");
+        }
+        assert_eq!(output.dims(), &dims);
+
+        let mut gamma_grad = Tensor1::zeroed(Dim1(3));
+        let mut beta_grad = Tensor1::zeroed(Dim1(3));
+        let mut input_error = Tensor2::zeroed(dims);
+        backend.batch_norm_param_grad(&normalized, &out_error, &mut gamma_grad, &mut beta_grad);
+        backend.batch_norm_input_error(
+            epsilon,
+            &normalized,
+            &out_error,
+            &var,
+            &gamma,
+            &gamma_grad,
+            &beta_grad,
+            &mut input_error,
+        );
+
+        const H: f64 = 1e-6;
+        for i in 0..input.len() {
+            let (mut plus, mut minus) = (input.clone(), input.clone());
+            plus[i] += H;
+            minus[i] -= H;
+            let expected = (loss(&plus, &gamma, &beta) - loss(&minus, &gamma, &beta)) / (2.0 * H);
+            assert!(
+                (expected - input_error[i]).abs() < 1e-5,
+                "input gradient {i}: {expected} != {}",
+                input_error[i]
+            );
+        }
+        for i in 0..3 {
+            let (mut plus, mut minus) = (gamma.clone(), gamma.clone());
+            plus[i] += H;
+            minus[i] -= H;
+            let expected = (loss(&input, &plus, &beta) - loss(&input, &minus, &beta)) / (2.0 * H);
+            assert!(
+                (expected - gamma_grad[i]).abs() < 1e-5,
+                "gamma gradient {i}: {expected} != {}",
+                gamma_grad[i]
+            );
+            let (mut plus, mut minus) = (beta.clone(), beta.clone());
+            plus[i] += H;
+            minus[i] -= H;
+            let expected = (loss(&input, &gamma, &plus) - loss(&input, &gamma, &minus)) / (2.0 * H);
+            assert!(
+                (expected - beta_grad[i]).abs() < 1e-5,
+                "beta gradient {i}: {expected} != {}",
+                beta_grad[i]
+            );
+        }
+    }
+
+    #[test]
+    fn test_batch_norm_net() {
+        let mut net = NetBuilder::new(CpuBackend::<f64>::new(8), 2)
+            .with_initializer(RandomNetInitializer::seed_from_u64(0x1234))
+            .with_layer(DenseLayerParams {
+                size: 8,
+                activation_fn: ActivationFn::Sigmoid,
+                use_bias: true,
+            })
+            .with_layer(BatchNormLayerParams::default())
+            .with_layer(DenseLayerParams {
+                size: 1,
+                activation_fn: ActivationFn::Sigmoid,
+                use_bias: true,
+            })
+            .build()
+            .unwrap();
+
+        // xor
+        let input = Tensor2::from_vec_2d(vec![[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0]]);
+        let expected = Tensor2::from_vec_2d(vec![[0.0], [1.0], [1.0], [0.0]]);
+        let config = TrainConfig {
+            loss: LossFn::MSE,
+            optimizer: OptimizerFn::adam(0.05),
+            num_epochs: 300,
+            ..TrainConfig::default()
+        };
+        let history = net.train(input.view(), expected.view(), &config).unwrap();
+        let first_loss = history.epochs[0].loss;
+        let last_loss = history.last().unwrap().loss;
+        assert!(
+            last_loss < first_loss * 0.5,
+            "loss did not decrease: {first_loss} -> {last_loss}"
+        );
+
+        // predictions use the running statistics, so they don't depend on the rest of the batch
+        let batch = net.predict(input.view()).unwrap().clone();
+        let single = net.predict(Tensor2::from_vec_2d(vec![[1.0, 0.0]]).view()).unwrap().clone();
+        assert_eq!(single[0], batch[2]);
+    }
+}
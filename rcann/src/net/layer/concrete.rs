@@ -1,7 +1,15 @@
-use super::{DenseLayer, DenseLayerParams, Layer, LayerParams};
+use super::{
+    ActivationLayer, ActivationLayerParams, BatchNormLayer, BatchNormLayerParams, Conv2dLayer, Conv2dLayerParams,
+    DenseLayer, DenseLayerParams, DropoutLayer, DropoutLayerParams, Layer, LayerNormLayer, LayerNormLayerParams,
+    LayerParams, Pool2dLayer, Pool2dLayerParams, PoolMode,
+};
 use crate::backend::Backend;
 use crate::net::initializer::NetInitializer;
+use crate::net::model::{ModelResult, ParamTensor};
+use crate::optimizer::Optimizer;
 use crate::tensor::Dim2;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Formatter};
 
 // This is needed because we can't put dyn LayerParam in a Box :(
@@ -9,8 +17,18 @@ use std::fmt::{Debug, Formatter};
 // Probably because GATs aren't object-safe, but I'm not certain.
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ConcreteLayerParams {
     FullyConnected(DenseLayerParams),
+    Conv2d(Conv2dLayerParams),
+    MaxPool2d(Pool2dLayerParams),
+    AvgPool2d(Pool2dLayerParams),
+    Dropout(DropoutLayerParams),
+    BatchNorm(BatchNormLayerParams),
+    LayerNorm(LayerNormLayerParams),
+    Activation(ActivationLayerParams),
+    /// a layer added with [super::CustomLayerParams], which can't be recreated from its params alone
+    Custom { name: String },
 }
 
 impl<B: Backend> LayerParams<B> for ConcreteLayerParams {
@@ -26,30 +44,76 @@ impl<B: Backend> LayerParams<B> for ConcreteLayerParams {
             ConcreteLayerParams::FullyConnected(params) => {
                 ConcreteLayer::FullyConnected(params.create_layer(backend, layer_idx, input_size, initializer))
             }
+            ConcreteLayerParams::Conv2d(params) => {
+                ConcreteLayer::Conv2d(params.create_layer(backend, layer_idx, input_size, initializer))
+            }
+            ConcreteLayerParams::MaxPool2d(params) => {
+                ConcreteLayer::Pool2d(params.create_layer(backend, PoolMode::Max, input_size))
+            }
+            ConcreteLayerParams::AvgPool2d(params) => {
+                ConcreteLayer::Pool2d(params.create_layer(backend, PoolMode::Avg, input_size))
+            }
+            ConcreteLayerParams::Dropout(params) => {
+                ConcreteLayer::Dropout(params.create_layer(backend, layer_idx, input_size, initializer))
+            }
+            ConcreteLayerParams::BatchNorm(params) => {
+                ConcreteLayer::BatchNorm(params.create_layer(backend, layer_idx, input_size, initializer))
+            }
+            ConcreteLayerParams::LayerNorm(params) => {
+                ConcreteLayer::LayerNorm(params.create_layer(backend, layer_idx, input_size, initializer))
+            }
+            ConcreteLayerParams::Activation(params) => {
+                ConcreteLayer::Activation(params.create_layer(backend, layer_idx, input_size, initializer))
+            }
+            ConcreteLayerParams::Custom { name } => {
+                // NetBuilder::build refuses these, so this is only reached by calling create_layer directly
+                panic!("Custom layer {name} must be created from its CustomLayerParams")
+            }
         }
     }
 }
 
 pub enum ConcreteLayer<B: Backend> {
     FullyConnected(DenseLayer<B>),
+    Conv2d(Conv2dLayer<B>),
+    Pool2d(Pool2dLayer<B>),
+    Dropout(DropoutLayer<B>),
+    BatchNorm(BatchNormLayer<B>),
+    LayerNorm(LayerNormLayer<B>),
+    Activation(ActivationLayer<B>),
+    Custom(Box<dyn Layer<B>>),
 }
 
 impl<B: Backend> ConcreteLayer<B> {
     fn inner(&self) -> &dyn Layer<B> {
         match self {
             ConcreteLayer::FullyConnected(inner) => inner,
+            ConcreteLayer::Conv2d(inner) => inner,
+            ConcreteLayer::Pool2d(inner) => inner,
+            ConcreteLayer::Dropout(inner) => inner,
+            ConcreteLayer::BatchNorm(inner) => inner,
+            ConcreteLayer::LayerNorm(inner) => inner,
+            ConcreteLayer::Activation(inner) => inner,
+            ConcreteLayer::Custom(inner) => inner.as_ref(),
         }
     }
     fn inner_mut(&mut self) -> &mut dyn Layer<B> {
         match self {
             ConcreteLayer::FullyConnected(inner) => inner,
+            ConcreteLayer::Conv2d(inner) => inner,
+            ConcreteLayer::Pool2d(inner) => inner,
+            ConcreteLayer::Dropout(inner) => inner,
+            ConcreteLayer::BatchNorm(inner) => inner,
+            ConcreteLayer::LayerNorm(inner) => inner,
+            ConcreteLayer::Activation(inner) => inner,
+            ConcreteLayer::Custom(inner) => inner.as_mut(),
         }
     }
 }
 
 impl<B: Backend> Layer<B> for ConcreteLayer<B> {
-    fn forward(&mut self, backend: &B, input: B::TensorRef<'_, Dim2>, output: &mut B::Tensor<Dim2>) {
-        self.inner_mut().forward(backend, input, output)
+    fn forward(&mut self, backend: &B, input: B::TensorRef<'_, Dim2>, output: &mut B::Tensor<Dim2>, training: bool) {
+        self.inner_mut().forward(backend, input, output, training)
     }
 
     fn backprop(
@@ -59,11 +123,29 @@ impl<B: Backend> Layer<B> for ConcreteLayer<B> {
         output: &B::Tensor<Dim2>,
         input_error: Option<&mut B::Tensor<Dim2>>,
         output_error: &B::Tensor<Dim2>,
-        learn_rate: B::Float,
-        momentum: B::Float,
     ) {
         self.inner_mut()
-            .backprop(backend, input, output, input_error, output_error, learn_rate, momentum)
+            .backprop(backend, input, output, input_error, output_error)
+    }
+
+    fn update_params(&mut self, backend: &B, optimizer: &mut Optimizer<B>) {
+        self.inner_mut().update_params(backend, optimizer)
+    }
+
+    fn export_params(&self, backend: &B) -> Vec<ParamTensor> {
+        self.inner().export_params(backend)
+    }
+
+    fn import_params(&mut self, backend: &B, params: &[ParamTensor]) -> ModelResult<()> {
+        self.inner_mut().import_params(backend, params)
+    }
+
+    fn export_train_state(&self) -> Vec<u64> {
+        self.inner().export_train_state()
+    }
+
+    fn import_train_state(&mut self, state: &[u64]) -> ModelResult<()> {
+        self.inner_mut().import_train_state(state)
     }
 
     #[inline]
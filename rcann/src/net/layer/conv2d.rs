@@ -0,0 +1,336 @@
+use crate::activation::ActivationFn;
+use crate::backend::{Backend, Window2d};
+use crate::net::layer::{ConcreteLayerParams, Layer, LayerParams, LayerType, NetInitializer};
+use crate::net::model::{ModelError, ModelResult, ParamTensor};
+use crate::optimizer::Optimizer;
+use crate::tensor::{Dim1, Dim2, Dim3, Dims, ITensor, Tensor1, Tensor2};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Formatter};
+
+/// A 2d convolution over images stored row-wise in `(channels, height, width)` order.
+/// Each output row holds `(filters, out_height, out_width)`, see [Conv2dLayerParams::output_dims].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Conv2dLayerParams {
+    /// the `(channels, height, width)` of each input image
+    pub input_dims: Dim3,
+    /// the number of output channels
+    pub filters: usize,
+    pub kernel_size: usize,
+    pub stride: usize,
+    pub padding: usize,
+    pub activation_fn: ActivationFn,
+    pub use_bias: bool,
+}
+
+impl Conv2dLayerParams {
+    #[inline]
+    pub fn window(&self) -> Window2d {
+        Window2d {
+            input_dims: self.input_dims,
+            size: self.kernel_size,
+            stride: self.stride,
+            padding: self.padding,
+        }
+    }
+
+    /// the `(filters, height, width)` of each output image
+    #[inline]
+    pub fn output_dims(&self) -> Dim3 {
+        self.window().output_dims(self.filters)
+    }
+}
+
+impl<B: Backend> LayerParams<B> for Conv2dLayerParams {
+    type Layer = Conv2dLayer<B>;
+
+    fn create_layer(
+        &self,
+        backend: &B,
+        layer_idx: usize,
+        input_size: usize,
+        initializer: &mut dyn NetInitializer<B::Float>,
+    ) -> Self::Layer {
+        let window = self.window();
+        assert!(window.is_valid(), "Invalid convolution window: {window:?}");
+        assert!(self.filters > 0, "Conv2d layer must have at least one filter");
+        assert_eq!(
+            input_size,
+            self.input_dims.tensor_len(),
+            "Input size does not match input dims {}",
+            self.input_dims
+        );
+        let patch_len = window.patch_len();
+        let kernel = Tensor2::from_vec(
+            initializer.get_weights(
+                LayerType::Conv2d,
+                self.filters * patch_len,
+                layer_idx,
+                patch_len,
+                self.filters * self.kernel_size * self.kernel_size,
+            ),
+            Dim2(self.filters, patch_len),
+        );
+        let biases = if self.use_bias {
+            let biases = initializer.get_biases(LayerType::Conv2d, self.filters, layer_idx);
+            Some(backend.new_tensor_from_native(Tensor1::from_vec_1d(biases)))
+        } else {
+            None
+        };
+        let output_size = self.output_dims().tensor_len();
+        Conv2dLayer {
+            window,
+            filters: self.filters,
+            output_size,
+            kernel: backend.new_tensor_from_native(kernel),
+            biases,
+            activation: backend.new_tensor_batch_sized(Dim1(output_size)),
+            training_tensors: None,
+            activation_fn: self.activation_fn,
+        }
+    }
+}
+
+impl Into<ConcreteLayerParams> for Conv2dLayerParams {
+    fn into(self) -> ConcreteLayerParams {
+        ConcreteLayerParams::Conv2d(self)
+    }
+}
+
+pub struct Conv2dLayer<B: Backend> {
+    window: Window2d,
+    filters: usize,
+    output_size: usize,
+    kernel: B::Tensor<Dim2>,
+    biases: Option<B::Tensor<Dim1>>,
+    activation: B::Tensor<Dim2>,
+    training_tensors: Option<TrainingTensors<B>>,
+    activation_fn: ActivationFn,
+}
+
+struct TrainingTensors<B: Backend> {
+    activation_error: B::Tensor<Dim2>,
+    kernel_grad: B::Tensor<Dim2>,
+    bias_grad: Option<B::Tensor<Dim1>>,
+}
+
+impl<B: Backend> TrainingTensors<B> {
+    fn new(backend: &B, output_size: usize, filters: usize, patch_len: usize, use_bias: bool) -> Self {
+        TrainingTensors {
+            activation_error: backend.new_tensor_batch_sized(Dim1(output_size)),
+            kernel_grad: backend.new_tensor_exact(Dim2(filters, patch_len)),
+            bias_grad: use_bias.then(|| backend.new_tensor_exact(Dim1(filters))),
+        }
+    }
+}
+
+impl<B: Backend> Layer<B> for Conv2dLayer<B> {
+    fn forward(&mut self, backend: &B, input: B::TensorRef<'_, Dim2>, output: &mut B::Tensor<Dim2>, _training: bool) {
+        let num_rows = input.dims().rows();
+
+        assert_eq!(
+            input.dims().cols(),
+            self.input_size(),
+            "Invalid number of columns for input tensor"
+        );
+        assert_eq!(
+            output.dims(),
+            &Dim2(num_rows, self.output_size),
+            "Invalid dimensions for output tensor"
+        );
+
+        backend.resize_tensor_major(&mut self.activation, num_rows);
+        backend.conv2d(
+            &self.window,
+            input,
+            &self.kernel,
+            self.biases.as_ref(),
+            &mut self.activation,
+        );
+
+        self.activation_fn
+            .compute(backend, B::TensorRef::from(&self.activation), output);
+    }
+
+    fn backprop(
+        &mut self,
+        backend: &B,
+        input: B::TensorRef<'_, Dim2>,
+        output: &B::Tensor<Dim2>,
+        input_error: Option<&mut B::Tensor<Dim2>>,
+        out_error: &B::Tensor<Dim2>,
+    ) {
+        let num_rows = input.dims().rows();
+
+        assert_eq!(
+            input.dims().cols(),
+            self.input_size(),
+            "Invalid number of columns for input tensor"
+        );
+        assert_eq!(
+            output.dims(),
+            &Dim2(num_rows, self.output_size),
+            "Invalid dimensions for output tensor"
+        );
+        assert_eq!(
+            out_error.dims(),
+            &Dim2(num_rows, self.output_size),
+            "Invalid dimensions for out_error tensor"
+        );
+
+        let use_bias = self.biases.is_some();
+        let patch_len = self.window.patch_len();
+        let tt = self.training_tensors.get_or_insert_with(|| {
+            TrainingTensors::new(backend, self.output_size, self.filters, patch_len, use_bias)
+        });
+
+        backend.resize_tensor_major(&mut tt.activation_error, num_rows);
+        self.activation_fn
+            .compute_error(
+                backend,
+                B::TensorRef::from(&self.activation),
+                output,
+                out_error,
+                &mut tt.activation_error,
+            );
+
+        if let Some(input_error) = input_error {
+            assert_eq!(
+                input_error.dims(),
+                &Dim2(num_rows, self.window.input_dims.tensor_len()),
+                "Invalid dimensions for input_error"
+            );
+            backend.conv2d_input_error(&self.window, &tt.activation_error, &self.kernel, input_error);
+        }
+
+        backend.conv2d_param_grad(
+            &self.window,
+            input,
+            &tt.activation_error,
+            &mut tt.kernel_grad,
+            tt.bias_grad.as_mut(),
+        );
+    }
+
+    fn update_params(&mut self, backend: &B, optimizer: &mut Optimizer<B>) {
+        let tt = self
+            .training_tensors
+            .as_ref()
+            .expect("backprop must be called before update_params");
+        optimizer.update(backend, &mut self.kernel, &tt.kernel_grad);
+        if let (Some(biases), Some(bias_grad)) = (&mut self.biases, &tt.bias_grad) {
+            optimizer.update(backend, biases, bias_grad);
+        }
+    }
+
+    fn export_params(&self, backend: &B) -> Vec<ParamTensor> {
+        let mut params = vec![ParamTensor::read_from::<B, _>(backend, &self.kernel)];
+        if let Some(biases) = &self.biases {
+            params.push(ParamTensor::read_from::<B, _>(backend, biases));
+        }
+        params
+    }
+
+    fn import_params(&mut self, backend: &B, params: &[ParamTensor]) -> ModelResult<()> {
+        match (params, &mut self.biases) {
+            ([kernel], None) => kernel.write_to::<B, _>(backend, &mut self.kernel),
+            ([kernel, biases_src], Some(biases)) => {
+                kernel.write_to::<B, _>(backend, &mut self.kernel)?;
+                biases_src.write_to::<B, _>(backend, biases)
+            }
+            _ => Err(ModelError::ParamMismatch(format!(
+                "expected {} tensors for Conv2d layer, found {}",
+                if self.biases.is_some() { 2 } else { 1 },
+                params.len()
+            ))),
+        }
+    }
+
+    #[inline]
+    fn input_size(&self) -> usize {
+        self.window.input_dims.tensor_len()
+    }
+
+    #[inline]
+    fn output_size(&self) -> usize {
+        self.output_size
+    }
+}
+
+impl<B: Backend> Debug for Conv2dLayer<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Conv2dLayer")
+            .field("input_dims", &self.window.input_dims)
+            .field("filters", &self.filters)
+            .field("kernel_size", &self.window.size)
+            .field("stride", &self.window.stride)
+            .field("padding", &self.window.padding)
+            .field("activation_fn", &self.activation_fn)
+            .field("use_bias", &self.biases.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::backend::{BackendOther, CpuBackend, Window2d};
+    use crate::tensor::{Dim1, Dim2, Dim3, Dims, ITensor, Tensor, Tensor1, Tensor2, TensorBase};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rand_distr::StandardNormal;
+
+    /// checks the gradients of `conv2d` against finite differences of `sum(conv2d(input) * out_error)`
+    #[test]
+    fn test_conv2d_gradients() {
+        let backend = CpuBackend::<f64>::new(0);
+        let mut rng = StdRng::seed_from_u64(0x2d2d);
+        let window = Window2d {
+            input_dims: Dim3(2, 5, 6),
+            size: 3,
+            stride: 2,
+            padding: 1,
+        };
+        let filters = 3;
+        let out_len = window.output_dims(filters).tensor_len();
+        let input = Tensor2::from_distribution(&mut rng, StandardNormal, Dim2(2, window.input_dims.tensor_len()));
+        let kernel = Tensor2::from_distribution(&mut rng, StandardNormal, Dim2(filters, window.patch_len()));
+        let bias = Tensor1::from_distribution(&mut rng, StandardNormal, Dim1(filters));
+        let out_error = Tensor2::from_distribution(&mut rng, StandardNormal, Dim2(2, out_len));
+
+        let loss = |input: &Tensor2<f64>, kernel: &Tensor2<f64>, bias: &Tensor1<f64>| {
+            let mut output = Tensor2::zeroed(Dim2(2, out_len));
+            backend.conv2d(&window, input.view(), kernel, Some(bias), &mut output);
+            output.iter().zip(out_error.iter()).map(|(o, e)| o * e).sum::<f64>()
+        };
+        fn check<D: Dims>(actual: &Tensor<f64, D>, mut f: impl FnMut(usize, f64) -> f64) {
+            const H: f64 = 1e-6;
+            for (i, &a) in actual.iter().enumerate() {
+                let expected = (f(i, H) - f(i, -H)) / (2.0 * H);
+                assert!((expected - a).abs() < 1e-6, "gradient {i}: {expected} != {a}");
+            }
+        }
+
+        let mut input_error = Tensor2::zeroed(*input.dims());
+        backend.conv2d_input_error(&window, &out_error, &kernel, &mut input_error);
+        let mut kernel_grad = Tensor2::zeroed(*kernel.dims());
+        let mut bias_grad = Tensor1::zeroed(*bias.dims());
+        backend.conv2d_param_grad(&window, input.view(), &out_error, &mut kernel_grad, Some(&mut bias_grad));
+
+        check(&input_error, |i, h| {
+            let mut input = input.clone();
+            input[i] += h;
+            loss(&input, &kernel, &bias)
+        });
+        check(&kernel_grad, |i, h| {
+            let mut kernel = kernel.clone();
+            kernel[i] += h;
+            loss(&input, &kernel, &bias)
+        });
+        check(&bias_grad, |i, h| {
+            let mut bias = bias.clone();
+            bias[i] += h;
+            loss(&input, &kernel, &bias)
+        });
+    }
+}
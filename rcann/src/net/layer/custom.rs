@@ -0,0 +1,274 @@
+use crate::backend::Backend;
+use crate::net::initializer::NetInitializer;
+use crate::net::layer::{ConcreteLayer, ConcreteLayerParams, Layer, LayerParams};
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+/// An object safe version of [LayerParams] for layers implemented outside of this crate.
+/// Wrap the params in a [CustomLayerParams] to add them to a [crate::net::NetBuilder].
+pub trait DynLayerParams<B: Backend>: Debug {
+    /// identifies the layer in saved models, so it should include any configuration of the layer.
+    /// Saved params can only be imported into a net whose custom layers have the same names.
+    fn name(&self) -> String;
+
+    fn create_layer(
+        &self,
+        backend: &B,
+        layer_idx: usize,
+        input_size: usize,
+        initializer: &mut dyn NetInitializer<B::Float>,
+    ) -> Box<dyn Layer<B>>;
+}
+
+pub struct CustomLayerParams<B: Backend>(Rc<dyn DynLayerParams<B>>);
+
+impl<B: Backend> CustomLayerParams<B> {
+    pub fn new<P: 'static + DynLayerParams<B>>(params: P) -> Self {
+        CustomLayerParams(Rc::new(params))
+    }
+
+    #[inline]
+    pub fn name(&self) -> String {
+        self.0.name()
+    }
+}
+
+impl<B: Backend> Clone for CustomLayerParams<B> {
+    fn clone(&self) -> Self {
+        CustomLayerParams(self.0.clone())
+    }
+}
+
+impl<B: Backend> Debug for CustomLayerParams<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self.0.as_ref(), f)
+    }
+}
+
+/// The params of any layer which can be added to a [crate::net::NetBuilder]
+#[derive(Debug)]
+pub enum NetLayerParams<B: Backend> {
+    Concrete(ConcreteLayerParams),
+    Custom(CustomLayerParams<B>),
+}
+
+impl<B: Backend> Clone for NetLayerParams<B> {
+    fn clone(&self) -> Self {
+        match self {
+            NetLayerParams::Concrete(params) => NetLayerParams::Concrete(params.clone()),
+            NetLayerParams::Custom(params) => NetLayerParams::Custom(params.clone()),
+        }
+    }
+}
+
+impl<B: Backend> NetLayerParams<B> {
+    /// the params stored in a model of the net. Custom layers are stored by name only.
+    pub fn model_params(&self) -> ConcreteLayerParams {
+        match self {
+            NetLayerParams::Concrete(params) => params.clone(),
+            NetLayerParams::Custom(params) => ConcreteLayerParams::Custom { name: params.name() },
+        }
+    }
+}
+
+impl<B: Backend, T: Into<ConcreteLayerParams>> From<T> for NetLayerParams<B> {
+    fn from(params: T) -> Self {
+        NetLayerParams::Concrete(params.into())
+    }
+}
+
+impl<B: Backend> From<CustomLayerParams<B>> for NetLayerParams<B> {
+    fn from(params: CustomLayerParams<B>) -> Self {
+        NetLayerParams::Custom(params)
+    }
+}
+
+impl<B: Backend> LayerParams<B> for NetLayerParams<B> {
+    type Layer = ConcreteLayer<B>;
+
+    fn create_layer(
+        &self,
+        backend: &B,
+        layer_idx: usize,
+        input_size: usize,
+        initializer: &mut dyn NetInitializer<B::Float>,
+    ) -> Self::Layer {
+        match self {
+            NetLayerParams::Concrete(params) => params.create_layer(backend, layer_idx, input_size, initializer),
+            NetLayerParams::Custom(params) => {
+                ConcreteLayer::Custom(params.0.create_layer(backend, layer_idx, input_size, initializer))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CustomLayerParams, DynLayerParams};
+    use crate::activation::ActivationFn;
+    use crate::backend::{Backend, CpuBackend};
+    use crate::loss::LossFn;
+    use crate::net::initializer::{NetInitializer, RandomNetInitializer};
+    use crate::net::layer::{ConcreteLayerParams, DenseLayer, DenseLayerParams, Layer, LayerParams};
+    use crate::net::model::{ModelError, ModelFormat, ModelResult, NetModel, ParamTensor};
+    use crate::net::{Net, NetBuilder, TrainConfig};
+    use crate::optimizer::{Optimizer, OptimizerFn};
+    use crate::tensor::Dim2;
+    use crate::tensor::{Tensor2, TensorBase};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// a dense layer which counts how often it is called
+    #[derive(Debug)]
+    struct CountingLayerParams {
+        dense: DenseLayerParams,
+        calls: Rc<Cell<usize>>,
+    }
+
+    #[derive(Debug)]
+    struct CountingLayer<B: Backend> {
+        inner: DenseLayer<B>,
+        calls: Rc<Cell<usize>>,
+    }
+
+    impl<B: Backend> DynLayerParams<B> for CountingLayerParams {
+        fn name(&self) -> String {
+            format!("Counting(size={})", self.dense.size)
+        }
+
+        fn create_layer(
+            &self,
+            backend: &B,
+            layer_idx: usize,
+            input_size: usize,
+            initializer: &mut dyn NetInitializer<B::Float>,
+        ) -> Box<dyn Layer<B>> {
+            Box::new(CountingLayer {
+                inner: self.dense.create_layer(backend, layer_idx, input_size, initializer),
+                calls: self.calls.clone(),
+            })
+        }
+    }
+
+    impl<B: Backend> Layer<B> for CountingLayer<B> {
+        fn forward(
+            &mut self,
+            backend: &B,
+            input: B::TensorRef<'_, Dim2>,
+            output: &mut B::Tensor<Dim2>,
+            training: bool,
+        ) {
+            self.calls.set(self.calls.get() + 1);
+            self.inner.forward(backend, input, output, training)
+        }
+
+        fn backprop(
+            &mut self,
+            backend: &B,
+            input: B::TensorRef<'_, Dim2>,
+            output: &B::Tensor<Dim2>,
+            input_error: Option<&mut B::Tensor<Dim2>>,
+            output_error: &B::Tensor<Dim2>,
+        ) {
+            self.calls.set(self.calls.get() + 1);
+            self.inner.backprop(backend, input, output, input_error, output_error)
+        }
+
+        fn update_params(&mut self, backend: &B, optimizer: &mut Optimizer<B>) {
+            self.inner.update_params(backend, optimizer)
+        }
+
+        fn export_params(&self, backend: &B) -> Vec<ParamTensor> {
+            self.inner.export_params(backend)
+        }
+
+        fn import_params(&mut self, backend: &B, params: &[ParamTensor]) -> ModelResult<()> {
+            self.inner.import_params(backend, params)
+        }
+
+        fn input_size(&self) -> usize {
+            self.inner.input_size()
+        }
+
+        fn output_size(&self) -> usize {
+            self.inner.output_size()
+        }
+    }
+
+    #[test]
+    fn test_custom_layer() {
+        let hidden = DenseLayerParams {
+            size: 6,
+            activation_fn: ActivationFn::ReLU { leak: 0.1 },
+            use_bias: true,
+        };
+        let output = DenseLayerParams {
+            size: 2,
+            activation_fn: ActivationFn::Sigmoid,
+            use_bias: true,
+        };
+        let calls = Rc::new(Cell::new(0));
+        let create_net = |custom: bool| {
+            let builder = NetBuilder::new(CpuBackend::<f64>::new(4), 3)
+                .with_initializer(RandomNetInitializer::seed_from_u64(0x1234))
+                .with_layer(hidden.clone());
+            let builder = if custom {
+                builder.with_layer(CustomLayerParams::new(CountingLayerParams {
+                    dense: output.clone(),
+                    calls: calls.clone(),
+                }))
+            } else {
+                builder.with_layer(output.clone())
+            };
+            builder.build().unwrap()
+        };
+        let train = |net: &mut Net<CpuBackend<f64>>| {
+            let input = Tensor2::from_vec_2d(vec![[0.5, -1.0, 2.0], [1.5, 0.25, -0.75]]);
+            let expected = Tensor2::from_vec_2d(vec![[1.0, 0.0], [0.0, 1.0]]);
+            let config = TrainConfig {
+                loss: LossFn::MSE,
+                optimizer: OptimizerFn::sgd(0.1, 0.0),
+                num_epochs: 5,
+                ..TrainConfig::default()
+            };
+            net.train(input.view(), expected.view(), &config).unwrap();
+            net.predict(input.view()).unwrap().clone()
+        };
+
+        // the custom layer trains exactly like the dense layer it wraps
+        let mut custom = create_net(true);
+        let mut dense = create_net(false);
+        assert_eq!(train(&mut custom), train(&mut dense));
+        assert_eq!(calls.get(), 11);
+
+        let model = custom.to_model();
+        assert_eq!(
+            model.layers[1].params,
+            ConcreteLayerParams::Custom {
+                name: "Counting(size=2)".to_string()
+            }
+        );
+        let mut bytes = Vec::new();
+        model.write(&mut bytes, ModelFormat::Binary).unwrap();
+        let loaded = NetModel::read(bytes.as_slice(), ModelFormat::Binary).unwrap();
+        assert_eq!(loaded, model);
+        assert!(matches!(
+            Net::from_model(CpuBackend::<f64>::new(4), &loaded),
+            Err(ModelError::UnsupportedLayer(_))
+        ));
+        let mut imported = create_net(true);
+        imported.import_params(&loaded).unwrap();
+        assert_eq!(imported.to_model(), model);
+        assert!(dense.import_params(&loaded).is_err());
+
+        // the name of a custom layer alone can't create it
+        let builder = NetBuilder::new(CpuBackend::<f64>::new(4), 3)
+            .with_layer(DenseLayerParams {
+                size: 2,
+                activation_fn: ActivationFn::Sigmoid,
+                use_bias: true,
+            })
+            .with_layer(model.layers[1].params.clone());
+        assert!(builder.build().is_none());
+    }
+}
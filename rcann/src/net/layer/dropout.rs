@@ -0,0 +1,261 @@
+use crate::backend::{Backend, DropoutMask};
+use crate::net::layer::{ConcreteLayerParams, Layer, LayerParams, NetInitializer};
+use crate::net::model::{ModelError, ModelResult, ParamTensor};
+use crate::optimizer::Optimizer;
+use crate::tensor::{Dim1, Dim2, ITensor};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Formatter};
+
+/// Randomly zeroes each input with probability `rate` while training, scaling the remaining inputs by
+/// `1 / (1 - rate)`. Outside of training the layer passes its input through unchanged.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DropoutLayerParams {
+    pub rate: f64,
+    /// the masks sampled by the layer are fully determined by the seed and the index of the layer
+    pub seed: u64,
+}
+
+impl DropoutLayerParams {
+    pub fn new(rate: f64) -> Self {
+        DropoutLayerParams { rate, seed: 0 }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        DropoutLayerParams { seed, ..self }
+    }
+}
+
+impl<B: Backend> LayerParams<B> for DropoutLayerParams {
+    type Layer = DropoutLayer<B>;
+
+    fn create_layer(
+        &self,
+        backend: &B,
+        layer_idx: usize,
+        input_size: usize,
+        _initializer: &mut dyn NetInitializer<B::Float>,
+    ) -> Self::Layer {
+        assert!(
+            (0.0..1.0).contains(&self.rate),
+            "Dropout rate must be in [0, 1), found {}",
+            self.rate
+        );
+        DropoutLayer {
+            size: input_size,
+            rate: self.rate,
+            seed: self.seed ^ (layer_idx as u64).wrapping_mul(0x9e3779b97f4a7c15),
+            step: 0,
+            scale: backend.new_tensor_batch_sized(Dim1(input_size)),
+        }
+    }
+}
+
+impl Into<ConcreteLayerParams> for DropoutLayerParams {
+    fn into(self) -> ConcreteLayerParams {
+        ConcreteLayerParams::Dropout(self)
+    }
+}
+
+pub struct DropoutLayer<B: Backend> {
+    size: usize,
+    rate: f64,
+    seed: u64,
+    /// the number of masks sampled so far
+    step: u64,
+    scale: B::Tensor<Dim2>,
+}
+
+impl<B: Backend> Layer<B> for DropoutLayer<B> {
+    fn forward(&mut self, backend: &B, input: B::TensorRef<'_, Dim2>, output: &mut B::Tensor<Dim2>, training: bool) {
+        let num_rows = input.dims().rows();
+
+        assert_eq!(
+            input.dims().cols(),
+            self.size,
+            "Invalid number of columns for input tensor"
+        );
+        assert_eq!(
+            output.dims(),
+            &Dim2(num_rows, self.size),
+            "Invalid dimensions for output tensor"
+        );
+
+        let mask = if training {
+            self.step += 1;
+            DropoutMask::new(self.rate, self.seed, self.step)
+        } else {
+            DropoutMask::identity()
+        };
+        backend.resize_tensor_major(&mut self.scale, num_rows);
+        backend.dropout(&mask, input, output, &mut self.scale);
+    }
+
+    fn backprop(
+        &mut self,
+        backend: &B,
+        input: B::TensorRef<'_, Dim2>,
+        _output: &B::Tensor<Dim2>,
+        input_error: Option<&mut B::Tensor<Dim2>>,
+        out_error: &B::Tensor<Dim2>,
+    ) {
+        let num_rows = input.dims().rows();
+
+        assert_eq!(
+            out_error.dims(),
+            &Dim2(num_rows, self.size),
+            "Invalid dimensions for out_error tensor"
+        );
+
+        if let Some(input_error) = input_error {
+            assert_eq!(
+                input_error.dims(),
+                &Dim2(num_rows, self.size),
+                "Invalid dimensions for input_error"
+            );
+            backend.dropout_error(&self.scale, out_error, input_error);
+        }
+    }
+
+    fn update_params(&mut self, _backend: &B, _optimizer: &mut Optimizer<B>) {}
+
+    fn export_params(&self, _backend: &B) -> Vec<ParamTensor> {
+        Vec::new()
+    }
+
+    fn import_params(&mut self, _backend: &B, params: &[ParamTensor]) -> ModelResult<()> {
+        if params.is_empty() {
+            Ok(())
+        } else {
+            Err(ModelError::ParamMismatch(format!(
+                "expected no tensors for Dropout layer, found {}",
+                params.len()
+            )))
+        }
+    }
+
+    /// the number of masks sampled so far, so that training resumed from a checkpoint samples the same masks
+    fn export_train_state(&self) -> Vec<u64> {
+        vec![self.step]
+    }
+
+    fn import_train_state(&mut self, state: &[u64]) -> ModelResult<()> {
+        match state {
+            &[step] => {
+                self.step = step;
+                Ok(())
+            }
+            _ => Err(ModelError::ParamMismatch(
+                "expected a single step counter for Dropout layer".to_string(),
+            )),
+        }
+    }
+
+    #[inline]
+    fn input_size(&self) -> usize {
+        self.size
+    }
+
+    #[inline]
+    fn output_size(&self) -> usize {
+        self.size
+    }
+}
+
+impl<B: Backend> Debug for DropoutLayer<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DropoutLayer")
+            .field("size", &self.size)
+            .field("rate", &self.rate)
+            .field("step", &self.step)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DropoutLayerParams;
+    use crate::activation::ActivationFn;
+    use crate::backend::{BackendOther, CpuBackend, DropoutMask};
+    use crate::loss::LossFn;
+    use crate::net::initializer::RandomNetInitializer;
+    use crate::net::layer::DenseLayerParams;
+    use crate::net::{Net, NetBuilder, TrainConfig};
+    use crate::optimizer::OptimizerFn;
+    use crate::tensor::{Dim2, Tensor2, TensorBase};
+
+    #[test]
+    fn test_dropout_mask() {
+        let backend = CpuBackend::<f32>::new(0);
+        let input = Tensor2::from_vec(vec![1.0; 4000], Dim2(4, 1000));
+        let mut output = Tensor2::zeroed(Dim2(4, 1000));
+        let mut scale = Tensor2::zeroed(Dim2(4, 1000));
+        backend.dropout(&DropoutMask::new(0.25, 42, 1), input.view(), &mut output, &mut scale);
+        let kept = output.iter().filter(|&&v| v != 0.0).count();
+        assert!((2850..3150).contains(&kept), "unexpected number of kept values: {kept}");
+        assert!(output.iter().all(|&v| v == 0.0 || (v - 4.0 / 3.0).abs() < 1e-6));
+        assert_eq!(output, scale);
+
+        // the same seed and step give the same mask, a different step gives a different one
+        let mut other = Tensor2::zeroed(Dim2(4, 1000));
+        backend.dropout(&DropoutMask::new(0.25, 42, 1), input.view(), &mut other, &mut scale);
+        assert_eq!(output, other);
+        backend.dropout(&DropoutMask::new(0.25, 42, 2), input.view(), &mut other, &mut scale);
+        assert_ne!(output, other);
+
+        backend.dropout(&DropoutMask::identity(), input.view(), &mut output, &mut scale);
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn test_dropout_layer() {
+        let create_net = || {
+            NetBuilder::new(CpuBackend::<f64>::new(4), 3)
+                .with_initializer(RandomNetInitializer::seed_from_u64(0x1234))
+                .with_layer(DenseLayerParams {
+                    size: 8,
+                    activation_fn: ActivationFn::ReLU { leak: 0.1 },
+                    use_bias: true,
+                })
+                .with_layer(DropoutLayerParams::new(0.5).with_seed(7))
+                .with_layer(DenseLayerParams {
+                    size: 2,
+                    activation_fn: ActivationFn::Sigmoid,
+                    use_bias: true,
+                })
+                .build()
+                .unwrap()
+        };
+        let train = |net: &mut Net<CpuBackend<f64>>| {
+            let input = Tensor2::from_vec_2d(vec![[0.5, -1.0, 2.0], [1.5, 0.25, -0.75]]);
+            let expected = Tensor2::from_vec_2d(vec![[1.0, 0.0], [0.0, 1.0]]);
+            let config = TrainConfig {
+                loss: LossFn::MSE,
+                optimizer: OptimizerFn::sgd(0.1, 0.0),
+                num_epochs: 5,
+                ..TrainConfig::default()
+            };
+            net.train(input.view(), expected.view(), &config).unwrap();
+            net.predict(input.view()).unwrap().clone()
+        };
+
+        // training is reproducible from the seed
+        let mut a = create_net();
+        let mut b = create_net();
+        assert_eq!(train(&mut a), train(&mut b));
+        assert_eq!(a.to_model(), b.to_model());
+
+        // predictions don't sample a mask
+        let input = Tensor2::from_vec_2d(vec![[0.5, -1.0, 2.0]]);
+        let first = a.predict(input.view()).unwrap().clone();
+        assert_eq!(&first, a.predict(input.view()).unwrap());
+
+        // the number of sampled masks is training state, so it isn't part of the model
+        assert!(a.to_model().layers[1].tensors.is_empty());
+        assert_eq!(a.export_train_state(), vec![vec![], vec![5], vec![]]);
+        let mut c = create_net();
+        c.import_train_state(&a.export_train_state()).unwrap();
+        assert_eq!(c.export_train_state(), a.export_train_state());
+    }
+}
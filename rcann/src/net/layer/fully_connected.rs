@@ -2,13 +2,19 @@ use crate::activation::ActivationFn;
 use crate::backend::Backend;
 use crate::dtype::DType;
 use crate::net::layer::{ConcreteLayerParams, Layer, LayerParams, LayerType, NetInitializer};
+use crate::net::model::{ModelError, ModelResult, ParamTensor};
+use crate::optimizer::Optimizer;
 use crate::tensor::{Dim1, Dim2, ITensor, Tensor1, Tensor2};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Formatter};
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DenseLayerParams {
     pub size: usize,
     pub activation_fn: ActivationFn,
+    pub use_bias: bool,
 }
 
 impl<B: Backend> LayerParams<B> for DenseLayerParams {
@@ -32,12 +38,17 @@ impl<B: Backend> LayerParams<B> for DenseLayerParams {
             ),
             Dim2(output_size, input_size),
         );
-        let biases = Tensor1::from_vec_1d(initializer.get_biases(LayerType::FullyConnected, output_size, layer_idx));
+        let biases = if self.use_bias {
+            let biases = initializer.get_biases(LayerType::FullyConnected, output_size, layer_idx);
+            Some(backend.new_tensor_from_native(Tensor1::from_vec_1d(biases)))
+        } else {
+            None
+        };
         DenseLayer {
             input_size,
             output_size,
             weights: backend.new_tensor_from_native(weights),
-            biases: backend.new_tensor_from_native(biases),
+            biases,
             activation: backend.new_tensor_batch_sized(Dim1(output_size)),
             training_tensors: None,
             activation_fn: self.activation_fn,
@@ -55,19 +66,25 @@ pub struct DenseLayer<B: Backend> {
     input_size: usize,
     output_size: usize,
     weights: B::Tensor<Dim2>,
-    biases: B::Tensor<Dim1>,
+    biases: Option<B::Tensor<Dim1>>,
     activation: B::Tensor<Dim2>,
     training_tensors: Option<TrainingTensors<B>>,
     activation_fn: ActivationFn,
 }
 
 impl<B: Backend> DenseLayer<B> {
-    pub fn new(backend: &B, input_size: usize, output_size: usize, activation_fn: ActivationFn) -> Self {
+    pub fn new(
+        backend: &B,
+        input_size: usize,
+        output_size: usize,
+        activation_fn: ActivationFn,
+        use_bias: bool,
+    ) -> Self {
         DenseLayer {
             input_size,
             output_size,
             weights: backend.new_tensor_exact(Dim2(output_size, input_size)),
-            biases: backend.new_tensor_exact(Dim1(output_size)),
+            biases: use_bias.then(|| backend.new_tensor_exact(Dim1(output_size))),
             activation: backend.new_tensor_batch_sized(Dim1(output_size)),
             training_tensors: None,
             activation_fn,
@@ -77,22 +94,22 @@ impl<B: Backend> DenseLayer<B> {
 
 struct TrainingTensors<B: Backend> {
     activation_error: B::Tensor<Dim2>,
-    weight_error: B::Tensor<Dim2>,
-    bias_error: B::Tensor<Dim1>,
+    weight_grad: B::Tensor<Dim2>,
+    bias_grad: Option<B::Tensor<Dim1>>,
 }
 
 impl<B: Backend> TrainingTensors<B> {
-    fn new(backend: &B, size: usize, prev_size: usize) -> Self {
+    fn new(backend: &B, size: usize, prev_size: usize, use_bias: bool) -> Self {
         TrainingTensors {
             activation_error: backend.new_tensor_batch_sized(Dim1(size)),
-            weight_error: backend.new_tensor_exact(Dim2(size, prev_size)),
-            bias_error: backend.new_tensor_exact(Dim1(size)),
+            weight_grad: backend.new_tensor_exact(Dim2(size, prev_size)),
+            bias_grad: use_bias.then(|| backend.new_tensor_exact(Dim1(size))),
         }
     }
 }
 
 impl<B: Backend> Layer<B> for DenseLayer<B> {
-    fn forward(&mut self, backend: &B, input: B::TensorRef<'_, Dim2>, output: &mut B::Tensor<Dim2>) {
+    fn forward(&mut self, backend: &B, input: B::TensorRef<'_, Dim2>, output: &mut B::Tensor<Dim2>, _training: bool) {
         let num_rows = input.dims().rows();
 
         assert_eq!(
@@ -117,7 +134,12 @@ impl<B: Backend> Layer<B> for DenseLayer<B> {
             &mut self.activation,
         );
 
-        self.activation_fn.compute(backend, &self.activation, output);
+        if let Some(biases) = &self.biases {
+            backend.add_assign_row_broadcast(B::Float::ONE, biases, B::Float::ONE, &mut self.activation);
+        }
+
+        self.activation_fn
+            .compute(backend, B::TensorRef::from(&self.activation), output);
     }
 
     fn backprop(
@@ -127,8 +149,6 @@ impl<B: Backend> Layer<B> for DenseLayer<B> {
         output: &B::Tensor<Dim2>,
         input_error: Option<&mut B::Tensor<Dim2>>,
         out_error: &B::Tensor<Dim2>,
-        learn_rate: B::Float,
-        momentum: B::Float,
     ) {
         let num_rows = input.dims().rows();
 
@@ -148,13 +168,20 @@ impl<B: Backend> Layer<B> for DenseLayer<B> {
             "Invalid dimensions for out_error tensor"
         );
 
+        let use_bias = self.biases.is_some();
         let tt = self
             .training_tensors
-            .get_or_insert_with(|| TrainingTensors::new(backend, self.output_size, self.input_size));
+            .get_or_insert_with(|| TrainingTensors::new(backend, self.output_size, self.input_size, use_bias));
 
         backend.resize_tensor_major(&mut tt.activation_error, num_rows);
         self.activation_fn
-            .compute_error(backend, &self.activation, output, out_error, &mut tt.activation_error);
+            .compute_error(
+                backend,
+                B::TensorRef::from(&self.activation),
+                output,
+                out_error,
+                &mut tt.activation_error,
+            );
 
         if let Some(input_error) = input_error {
             assert_eq!(
@@ -174,20 +201,52 @@ impl<B: Backend> Layer<B> for DenseLayer<B> {
         }
 
         backend.matmul(
-            learn_rate,
+            B::Float::ONE,
             B::TensorRef::from(&tt.activation_error),
             true,
             input,
             false,
-            momentum,
-            &mut tt.weight_error,
+            B::Float::ZERO,
+            &mut tt.weight_grad,
         );
 
-        backend.column_sum(learn_rate, &tt.activation_error, momentum, &mut tt.bias_error);
+        if let Some(bias_grad) = &mut tt.bias_grad {
+            backend.column_sum(B::Float::ONE, &tt.activation_error, B::Float::ZERO, bias_grad);
+        }
+    }
+
+    fn update_params(&mut self, backend: &B, optimizer: &mut Optimizer<B>) {
+        let tt = self
+            .training_tensors
+            .as_ref()
+            .expect("backprop must be called before update_params");
+        optimizer.update(backend, &mut self.weights, &tt.weight_grad);
+        if let (Some(biases), Some(bias_grad)) = (&mut self.biases, &tt.bias_grad) {
+            optimizer.update(backend, biases, bias_grad);
+        }
+    }
 
-        backend.add_assign(-B::Float::ONE, &tt.weight_error, B::Float::ONE, &mut self.weights);
+    fn export_params(&self, backend: &B) -> Vec<ParamTensor> {
+        let mut params = vec![ParamTensor::read_from::<B, _>(backend, &self.weights)];
+        if let Some(biases) = &self.biases {
+            params.push(ParamTensor::read_from::<B, _>(backend, biases));
+        }
+        params
+    }
 
-        backend.add_assign(-B::Float::ONE, &tt.bias_error, B::Float::ONE, &mut self.biases);
+    fn import_params(&mut self, backend: &B, params: &[ParamTensor]) -> ModelResult<()> {
+        match (params, &mut self.biases) {
+            ([weights], None) => weights.write_to::<B, _>(backend, &mut self.weights),
+            ([weights, biases_src], Some(biases)) => {
+                weights.write_to::<B, _>(backend, &mut self.weights)?;
+                biases_src.write_to::<B, _>(backend, biases)
+            }
+            _ => Err(ModelError::ParamMismatch(format!(
+                "expected {} tensors for FullyConnected layer, found {}",
+                if self.biases.is_some() { 2 } else { 1 },
+                params.len()
+            ))),
+        }
     }
 
     #[inline]
@@ -206,6 +265,7 @@ impl<B: Backend> Debug for DenseLayer<B> {
         f.debug_struct("FullyConnectedLayer")
             .field("size", &self.output_size)
             .field("activation_fn", &self.activation_fn)
+            .field("use_bias", &self.biases.is_some())
             // TODO: implement debug for these values
             //.field("weights", &self.weights)
             //.field("biases", &self.biases)
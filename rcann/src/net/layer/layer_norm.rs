@@ -0,0 +1,295 @@
+use crate::backend::Backend;
+use crate::dtype::DType;
+use crate::net::layer::{ConcreteLayerParams, Layer, LayerParams, NetInitializer};
+use crate::net::model::{ModelError, ModelResult, ParamTensor};
+use crate::optimizer::Optimizer;
+use crate::tensor::{Dim0, Dim1, Dim2, ITensor, Tensor1};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Formatter};
+
+/// Normalizes each row by its own mean and variance, then applies a learnable gain and bias to each column.
+/// Unlike [super::BatchNormLayerParams] the output of each row doesn't depend on the rest of the batch,
+/// so the layer behaves the same in training and inference.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LayerNormLayerParams {
+    /// added to the variance to avoid dividing by zero
+    pub epsilon: f64,
+}
+
+impl Default for LayerNormLayerParams {
+    fn default() -> Self {
+        LayerNormLayerParams { epsilon: 1e-5 }
+    }
+}
+
+impl<B: Backend> LayerParams<B> for LayerNormLayerParams {
+    type Layer = LayerNormLayer<B>;
+
+    fn create_layer(
+        &self,
+        backend: &B,
+        _layer_idx: usize,
+        input_size: usize,
+        _initializer: &mut dyn NetInitializer<B::Float>,
+    ) -> Self::Layer {
+        assert!(self.epsilon > 0.0, "LayerNorm epsilon must be positive");
+        let filled = |value| backend.new_tensor_from_native(Tensor1::filled(value, Dim1(input_size)));
+        LayerNormLayer {
+            size: input_size,
+            epsilon: self.epsilon,
+            gain: filled(B::Float::ONE),
+            bias: filled(B::Float::ZERO),
+            mean: backend.new_tensor_batch_sized(Dim0),
+            var: backend.new_tensor_batch_sized(Dim0),
+            normalized: backend.new_tensor_batch_sized(Dim1(input_size)),
+            training_tensors: None,
+        }
+    }
+}
+
+impl Into<ConcreteLayerParams> for LayerNormLayerParams {
+    fn into(self) -> ConcreteLayerParams {
+        ConcreteLayerParams::LayerNorm(self)
+    }
+}
+
+pub struct LayerNormLayer<B: Backend> {
+    size: usize,
+    epsilon: f64,
+    gain: B::Tensor<Dim1>,
+    bias: B::Tensor<Dim1>,
+    /// the mean of each row
+    mean: B::Tensor<Dim1>,
+    /// the variance of each row
+    var: B::Tensor<Dim1>,
+    normalized: B::Tensor<Dim2>,
+    training_tensors: Option<TrainingTensors<B>>,
+}
+
+struct TrainingTensors<B: Backend> {
+    gain_grad: B::Tensor<Dim1>,
+    bias_grad: B::Tensor<Dim1>,
+}
+
+impl<B: Backend> TrainingTensors<B> {
+    fn new(backend: &B, size: usize) -> Self {
+        TrainingTensors {
+            gain_grad: backend.new_tensor_exact(Dim1(size)),
+            bias_grad: backend.new_tensor_exact(Dim1(size)),
+        }
+    }
+}
+
+impl<B: Backend> Layer<B> for LayerNormLayer<B> {
+    fn forward(&mut self, backend: &B, input: B::TensorRef<'_, Dim2>, output: &mut B::Tensor<Dim2>, _training: bool) {
+        let num_rows = input.dims().rows();
+
+        assert_eq!(
+            input.dims().cols(),
+            self.size,
+            "Invalid number of columns for input tensor"
+        );
+        assert_eq!(
+            output.dims(),
+            &Dim2(num_rows, self.size),
+            "Invalid dimensions for output tensor"
+        );
+
+        backend.resize_tensor(&mut self.mean, Dim1(num_rows));
+        backend.resize_tensor(&mut self.var, Dim1(num_rows));
+        backend.resize_tensor_major(&mut self.normalized, num_rows);
+        backend.row_mean_var(input.clone(), &mut self.mean, &mut self.var);
+        backend.layer_norm(
+            B::Float::from_f64(self.epsilon),
+            input,
+            &self.mean,
+            &self.var,
+            &self.gain,
+            &self.bias,
+            &mut self.normalized,
+            output,
+        );
+    }
+
+    fn backprop(
+        &mut self,
+        backend: &B,
+        input: B::TensorRef<'_, Dim2>,
+        _output: &B::Tensor<Dim2>,
+        input_error: Option<&mut B::Tensor<Dim2>>,
+        out_error: &B::Tensor<Dim2>,
+    ) {
+        let num_rows = input.dims().rows();
+
+        assert_eq!(
+            out_error.dims(),
+            &Dim2(num_rows, self.size),
+            "Invalid dimensions for out_error tensor"
+        );
+
+        let tt = self
+            .training_tensors
+            .get_or_insert_with(|| TrainingTensors::new(backend, self.size));
+
+        backend.batch_norm_param_grad(&self.normalized, out_error, &mut tt.gain_grad, &mut tt.bias_grad);
+
+        if let Some(input_error) = input_error {
+            assert_eq!(
+                input_error.dims(),
+                &Dim2(num_rows, self.size),
+                "Invalid dimensions for input_error"
+            );
+            backend.layer_norm_input_error(
+                B::Float::from_f64(self.epsilon),
+                &self.normalized,
+                out_error,
+                &self.var,
+                &self.gain,
+                input_error,
+            );
+        }
+    }
+
+    fn update_params(&mut self, backend: &B, optimizer: &mut Optimizer<B>) {
+        let tt = self
+            .training_tensors
+            .as_ref()
+            .expect("backprop must be called before update_params");
+        optimizer.update(backend, &mut self.gain, &tt.gain_grad);
+        optimizer.update(backend, &mut self.bias, &tt.bias_grad);
+    }
+
+    fn export_params(&self, backend: &B) -> Vec<ParamTensor> {
+        vec![
+            ParamTensor::read_from::<B, _>(backend, &self.gain),
+            ParamTensor::read_from::<B, _>(backend, &self.bias),
+        ]
+    }
+
+    fn import_params(&mut self, backend: &B, params: &[ParamTensor]) -> ModelResult<()> {
+        match params {
+            [gain, bias] => {
+                gain.write_to::<B, _>(backend, &mut self.gain)?;
+                bias.write_to::<B, _>(backend, &mut self.bias)
+            }
+            _ => Err(ModelError::ParamMismatch(format!(
+                "expected 2 tensors for LayerNorm layer, found {}",
+                params.len()
+            ))),
+        }
+    }
+
+    #[inline]
+    fn input_size(&self) -> usize {
+        self.size
+    }
+
+    #[inline]
+    fn output_size(&self) -> usize {
+        self.size
+    }
+}
+
+impl<B: Backend> Debug for LayerNormLayer<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LayerNormLayer")
+            .field("size", &self.size)
+            .field("epsilon", &self.epsilon)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::backend::{BackendOther, CpuBackend};
+    use crate::tensor::{Dim1, Dim2, ITensor, Tensor1, Tensor2, TensorBase};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rand_distr::StandardNormal;
+
+    /// checks the gradients of `layer_norm` against finite differences of `sum(layer_norm(input) * out_error)`
+    #[test]
+    fn test_layer_norm_gradients() {
+        let backend = CpuBackend::<f64>::new(0);
+        let mut rng = StdRng::seed_from_u64(0x1a);
+        let dims = Dim2(3, 6);
+        let epsilon = 1e-5;
+        let input = Tensor2::from_distribution(&mut rng, StandardNormal, dims);
+        let gain = Tensor1::from_distribution(&mut rng, StandardNormal, Dim1(6));
+        let bias = Tensor1::from_distribution(&mut rng, StandardNormal, Dim1(6));
+        let out_error = Tensor2::from_distribution(&mut rng, StandardNormal, dims);
+
+        let forward = |input: &Tensor2<f64>, gain: &Tensor1<f64>, bias: &Tensor1<f64>| {
+            let mut mean = Tensor1::zeroed(Dim1(3));
+            let mut var = Tensor1::zeroed(Dim1(3));
+            let mut normalized = Tensor2::zeroed(dims);
+            let mut output = Tensor2::zeroed(dims);
+            backend.row_mean_var(input.view(), &mut mean, &mut var);
+            backend.layer_norm(
+                epsilon,
+                input.view(),
+                &mean,
+                &var,
+                gain,
+                bias,
+                &mut normalized,
+                &mut output,
+            );
+            (var, normalized, output)
+        };
+        let loss = |input: &Tensor2<f64>, gain: &Tensor1<f64>, bias: &Tensor1<f64>| {
+            let (_, _, output) = forward(input, gain, bias);
+            output.iter().zip(out_error.iter()).map(|(o, e)| o * e).sum::<f64>()
+        };
+
+        let (var, normalized, _) = forward(&input, &gain, &bias);
+        // each row of the normalized values has zero mean and unit variance
+        let mut mean = Tensor1::zeroed(Dim1(3));
+        let mut norm_var = Tensor1::zeroed(Dim1(3));
+        backend.row_mean_var(normalized.view(), &mut mean, &mut norm_var);
+        for (&m, &v) in mean.iter().zip(norm_var.iter()) {
+            assert!(m.abs() < 1e-9 && (v - 1.0).abs() < 1e-4, "mean {m}, var {v}");
+        }
+
+        let mut gain_grad = Tensor1::zeroed(Dim1(6));
+        let mut bias_grad = Tensor1::zeroed(Dim1(6));
+        let mut input_error = Tensor2::zeroed(dims);
+        backend.batch_norm_param_grad(&normalized, &out_error, &mut gain_grad, &mut bias_grad);
+        backend.layer_norm_input_error(epsilon, &normalized, &out_error, &var, &gain, &mut input_error);
+
+        const H: f64 = 1e-6;
+        for i in 0..input.len() {
+            let (mut plus, mut minus) = (input.clone(), input.clone());
+            plus[i] += H;
+            minus[i] -= H;
+            let expected = (loss(&plus, &gain, &bias) - loss(&minus, &gain, &bias)) / (2.0 * H);
+            assert!(
+                (expected - input_error[i]).abs() < 1e-5,
+                "input gradient {i}: {expected} != {}",
+                input_error[i]
+            );
+        }
+        for i in 0..6 {
+            let (mut plus, mut minus) = (gain.clone(), gain.clone());
+            plus[i] += H;
+            minus[i] -= H;
+            let expected = (loss(&input, &plus, &bias) - loss(&input, &minus, &bias)) / (2.0 * H);
+            assert!(
+                (expected - gain_grad[i]).abs() < 1e-5,
+                "gain gradient {i}: {expected} != {}",
+                gain_grad[i]
+            );
+            let (mut plus, mut minus) = (bias.clone(), bias.clone());
+            plus[i] += H;
+            minus[i] -= H;
+            let expected = (loss(&input, &gain, &plus) - loss(&input, &gain, &minus)) / (2.0 * H);
+            assert!(
+                (expected - bias_grad[i]).abs() < 1e-5,
+                "bias gradient {i}: {expected} != {}",
+                bias_grad[i]
+            );
+        }
+    }
+}
@@ -1,17 +1,34 @@
+mod activation;
+mod batch_norm;
 mod concrete;
+mod conv2d;
+mod custom;
+mod dropout;
 mod fully_connected;
+mod layer_norm;
+mod pool2d;
 
 use crate::backend::Backend;
 use crate::net::initializer::NetInitializer;
+use crate::net::model::{ModelError, ModelResult, ParamTensor};
+use crate::optimizer::Optimizer;
 use std::fmt::Debug;
 
 use crate::tensor::Dim2;
+pub use activation::{ActivationLayer, ActivationLayerParams};
+pub use batch_norm::{BatchNormLayer, BatchNormLayerParams};
 pub use concrete::{ConcreteLayer, ConcreteLayerParams};
+pub use conv2d::{Conv2dLayer, Conv2dLayerParams};
+pub use custom::{CustomLayerParams, DynLayerParams, NetLayerParams};
+pub use dropout::{DropoutLayer, DropoutLayerParams};
 pub use fully_connected::{DenseLayer, DenseLayerParams};
+pub use layer_norm::{LayerNormLayer, LayerNormLayerParams};
+pub use pool2d::{Pool2dLayer, Pool2dLayerParams, PoolMode};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum LayerType {
     FullyConnected,
+    Conv2d,
 }
 
 pub trait LayerParams<B: Backend>: Clone + Debug {
@@ -27,8 +44,17 @@ pub trait LayerParams<B: Backend>: Clone + Debug {
 }
 
 pub trait Layer<B: Backend>: Debug {
-    fn forward(&mut self, backend: &B, input: B::TensorRef<'_, Dim2>, output: &mut B::Tensor<Dim2>);
+    /// computes the output of this layer. `training` is set when the output will be backpropagated, so layers
+    /// which behave differently during training (such as dropout) know which mode to use.
+    fn forward(
+        &mut self,
+        backend: &B,
+        input: B::TensorRef<'_, Dim2>,
+        output: &mut B::Tensor<Dim2>,
+        training: bool,
+    );
 
+    /// computes the input error (if requested) and the gradients of this layer's parameters
     fn backprop(
         &mut self,
         backend: &B,
@@ -36,10 +62,35 @@ pub trait Layer<B: Backend>: Debug {
         output: &B::Tensor<Dim2>,
         input_error: Option<&mut B::Tensor<Dim2>>,
         output_error: &B::Tensor<Dim2>,
-        learn_rate: B::Float,
-        momentum: B::Float,
     );
 
+    /// hands this layer's parameters and the gradients computed by the last call to `backprop` to the optimizer
+    fn update_params(&mut self, backend: &B, optimizer: &mut Optimizer<B>);
+
+    /// reads the values of this layer's parameters, in a fixed order
+    fn export_params(&self, backend: &B) -> Vec<ParamTensor>;
+
+    /// overwrites this layer's parameters with values in the order given by `export_params`
+    fn import_params(&mut self, backend: &B, params: &[ParamTensor]) -> ModelResult<()>;
+
+    /// reads the state besides the parameters which training needs to continue exactly where it left off,
+    /// e.g. the number of masks a dropout layer has sampled. It is saved in a checkpoint, not in the model.
+    fn export_train_state(&self) -> Vec<u64> {
+        Vec::new()
+    }
+
+    /// restores the state given by `export_train_state`
+    fn import_train_state(&mut self, state: &[u64]) -> ModelResult<()> {
+        if state.is_empty() {
+            Ok(())
+        } else {
+            Err(ModelError::ParamMismatch(format!(
+                "expected no training state, found {} values",
+                state.len()
+            )))
+        }
+    }
+
     fn input_size(&self) -> usize;
     fn output_size(&self) -> usize;
 }
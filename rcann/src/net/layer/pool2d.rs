@@ -0,0 +1,300 @@
+use crate::backend::{Backend, Window2d};
+use crate::net::layer::Layer;
+use crate::net::model::{ModelError, ModelResult, ParamTensor};
+use crate::optimizer::Optimizer;
+use crate::tensor::{Dim1, Dim2, Dim3, Dims, ITensor};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Formatter};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PoolMode {
+    Max,
+    Avg,
+}
+
+/// Pools each channel of images stored row-wise in `(channels, height, width)` order. Used by both the
+/// [super::ConcreteLayerParams::MaxPool2d] and [super::ConcreteLayerParams::AvgPool2d] layers.
+/// Each output row holds `(channels, out_height, out_width)`, see [Pool2dLayerParams::output_dims].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Pool2dLayerParams {
+    /// the `(channels, height, width)` of each input image
+    pub input_dims: Dim3,
+    pub size: usize,
+    pub stride: usize,
+    pub padding: usize,
+}
+
+impl Pool2dLayerParams {
+    /// pooling over non-overlapping windows of the given size
+    pub fn new(input_dims: Dim3, size: usize) -> Self {
+        Pool2dLayerParams {
+            input_dims,
+            size,
+            stride: size,
+            padding: 0,
+        }
+    }
+
+    #[inline]
+    pub fn window(&self) -> Window2d {
+        Window2d {
+            input_dims: self.input_dims,
+            size: self.size,
+            stride: self.stride,
+            padding: self.padding,
+        }
+    }
+
+    /// the `(channels, height, width)` of each output image
+    #[inline]
+    pub fn output_dims(&self) -> Dim3 {
+        self.window().output_dims(self.input_dims.0)
+    }
+
+    pub fn create_layer<B: Backend>(&self, backend: &B, mode: PoolMode, input_size: usize) -> Pool2dLayer<B> {
+        let window = self.window();
+        assert!(window.is_valid(), "Invalid pooling window: {window:?}");
+        assert_eq!(
+            input_size,
+            self.input_dims.tensor_len(),
+            "Input size does not match input dims {}",
+            self.input_dims
+        );
+        let output_size = self.output_dims().tensor_len();
+        Pool2dLayer {
+            mode,
+            window,
+            output_size,
+            indices: (mode == PoolMode::Max).then(|| backend.new_tensor_batch_sized(Dim1(output_size))),
+        }
+    }
+}
+
+pub struct Pool2dLayer<B: Backend> {
+    mode: PoolMode,
+    window: Window2d,
+    output_size: usize,
+    /// the position of the max within each window, only used for max pooling
+    indices: Option<B::Tensor<Dim2>>,
+}
+
+impl<B: Backend> Layer<B> for Pool2dLayer<B> {
+    fn forward(&mut self, backend: &B, input: B::TensorRef<'_, Dim2>, output: &mut B::Tensor<Dim2>, _training: bool) {
+        let num_rows = input.dims().rows();
+
+        assert_eq!(
+            input.dims().cols(),
+            self.input_size(),
+            "Invalid number of columns for input tensor"
+        );
+        assert_eq!(
+            output.dims(),
+            &Dim2(num_rows, self.output_size),
+            "Invalid dimensions for output tensor"
+        );
+
+        match &mut self.indices {
+            Some(indices) => {
+                backend.resize_tensor_major(indices, num_rows);
+                backend.max_pool2d(&self.window, input, output, indices);
+            }
+            None => backend.avg_pool2d(&self.window, input, output),
+        }
+    }
+
+    fn backprop(
+        &mut self,
+        backend: &B,
+        input: B::TensorRef<'_, Dim2>,
+        _output: &B::Tensor<Dim2>,
+        input_error: Option<&mut B::Tensor<Dim2>>,
+        out_error: &B::Tensor<Dim2>,
+    ) {
+        let num_rows = input.dims().rows();
+
+        assert_eq!(
+            out_error.dims(),
+            &Dim2(num_rows, self.output_size),
+            "Invalid dimensions for out_error tensor"
+        );
+
+        if let Some(input_error) = input_error {
+            assert_eq!(
+                input_error.dims(),
+                &Dim2(num_rows, self.input_size()),
+                "Invalid dimensions for input_error"
+            );
+            match &self.indices {
+                Some(indices) => backend.max_pool2d_error(&self.window, indices, out_error, input_error),
+                None => backend.avg_pool2d_error(&self.window, out_error, input_error),
+            }
+        }
+    }
+
+    fn update_params(&mut self, _backend: &B, _optimizer: &mut Optimizer<B>) {}
+
+    fn export_params(&self, _backend: &B) -> Vec<ParamTensor> {
+        Vec::new()
+    }
+
+    fn import_params(&mut self, _backend: &B, params: &[ParamTensor]) -> ModelResult<()> {
+        if params.is_empty() {
+            Ok(())
+        } else {
+            Err(ModelError::ParamMismatch(format!(
+                "expected no tensors for Pool2d layer, found {}",
+                params.len()
+            )))
+        }
+    }
+
+    #[inline]
+    fn input_size(&self) -> usize {
+        self.window.input_dims.tensor_len()
+    }
+
+    #[inline]
+    fn output_size(&self) -> usize {
+        self.output_size
+    }
+}
+
+impl<B: Backend> Debug for Pool2dLayer<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pool2dLayer")
+            .field("mode", &self.mode)
+            .field("input_dims", &self.window.input_dims)
+            .field("size", &self.window.size)
+            .field("stride", &self.window.stride)
+            .field("padding", &self.window.padding)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Pool2dLayerParams;
+    use crate::activation::ActivationFn;
+    use crate::backend::{BackendOther, CpuBackend};
+    use crate::loss::LossFn;
+    use crate::net::initializer::RandomNetInitializer;
+    use crate::net::layer::{ConcreteLayerParams, Conv2dLayerParams, DenseLayerParams};
+    use crate::net::model::{ModelFormat, NetModel};
+    use crate::net::{NetBuilder, TrainConfig};
+    use crate::optimizer::OptimizerFn;
+    use crate::tensor::{Dim2, Dim3, Tensor2, TensorBase};
+
+    #[test]
+    fn test_pool2d() {
+        let backend = CpuBackend::<f32>::new(0);
+        // a single 2 channel 3x4 image
+        let input = Tensor2::from_vec(
+            vec![
+                1.0, 5.0, 2.0, 0.0, //
+                3.0, 4.0, 8.0, 6.0, //
+                9.0, 0.0, 1.0, 7.0, //
+                -1.0, -5.0, -2.0, -4.0, //
+                -3.0, -2.0, -8.0, -6.0, //
+                -9.0, -1.0, -1.0, -7.0,
+            ],
+            Dim2(1, 24),
+        );
+        let window = Pool2dLayerParams {
+            input_dims: Dim3(2, 3, 4),
+            size: 2,
+            stride: 2,
+            padding: 1,
+        }
+        .window();
+        let mut output = Tensor2::zeroed(Dim2(1, 12));
+        let mut indices = Tensor2::zeroed(Dim2(1, 12));
+        backend.max_pool2d(&window, input.view(), &mut output, &mut indices);
+        assert_eq!(
+            output.as_ref(),
+            &[1.0, 5.0, 0.0, 9.0, 8.0, 7.0, -1.0, -2.0, -4.0, -3.0, -1.0, -6.0]
+        );
+        let out_error = Tensor2::from_vec((1..=12).map(|v| v as f32).collect(), Dim2(1, 12));
+        let mut input_error = Tensor2::zeroed(Dim2(1, 24));
+        backend.max_pool2d_error(&window, &indices, &out_error, &mut input_error);
+        assert_eq!(
+            input_error.as_ref(),
+            &[
+                1.0, 2.0, 0.0, 3.0, //
+                0.0, 0.0, 5.0, 0.0, //
+                4.0, 0.0, 0.0, 6.0, //
+                7.0, 0.0, 8.0, 9.0, //
+                10.0, 0.0, 0.0, 12.0, //
+                0.0, 11.0, 0.0, 0.0,
+            ]
+        );
+
+        backend.avg_pool2d(&window, input.view(), &mut output);
+        assert_eq!(&output.as_ref()[..6], &[1.0, 3.5, 0.0, 6.0, 3.25, 6.5]);
+        backend.avg_pool2d_error(&window, &out_error, &mut input_error);
+        assert_eq!(
+            &input_error.as_ref()[..12],
+            &[
+                1.0, 1.0, 1.0, 3.0, //
+                2.0, 1.25, 1.25, 3.0, //
+                2.0, 1.25, 1.25, 3.0,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conv_pool_dense_net() {
+        let conv = Conv2dLayerParams {
+            input_dims: Dim3(1, 6, 6),
+            filters: 3,
+            kernel_size: 3,
+            stride: 1,
+            padding: 1,
+            activation_fn: ActivationFn::ReLU { leak: 0.1 },
+            use_bias: true,
+        };
+        let max_pool = Pool2dLayerParams::new(conv.output_dims(), 2);
+        let avg_pool = Pool2dLayerParams::new(max_pool.output_dims(), 3);
+        let mut net = NetBuilder::new(CpuBackend::<f64>::new(4), 36)
+            .with_initializer(RandomNetInitializer::seed_from_u64(0x1234))
+            .with_layer(conv)
+            .with_layer(ConcreteLayerParams::MaxPool2d(max_pool))
+            .with_layer(ConcreteLayerParams::AvgPool2d(avg_pool))
+            .with_layer(DenseLayerParams {
+                size: 2,
+                activation_fn: ActivationFn::Softmax,
+                use_bias: true,
+            })
+            .build()
+            .unwrap();
+
+        // classify whether the bright pixels are in the top or bottom half of the image
+        let mut input = Tensor2::zeroed(Dim2(8, 36));
+        let mut expected = Tensor2::zeroed(Dim2(8, 2));
+        for i in 0..8 {
+            let top = i % 2 == 0;
+            for j in 0..6 {
+                let y = if top { j % 3 } else { 3 + j % 3 };
+                input[i * 36 + y * 6 + (i + j) % 6] = 1.0;
+            }
+            expected[i * 2 + if top { 0 } else { 1 }] = 1.0;
+        }
+        let config = TrainConfig {
+            loss: LossFn::CategoricalCrossEntropy,
+            optimizer: OptimizerFn::adam(0.05),
+            batch_size: Some(4),
+            num_epochs: 50,
+            ..TrainConfig::default()
+        };
+        let history = net.train(input.view(), expected.view(), &config).unwrap();
+        let first_loss = history.epochs[0].loss;
+        let last_loss = history.last().unwrap().loss;
+        assert!(last_loss < first_loss * 0.5, "loss did not decrease: {first_loss} -> {last_loss}");
+
+        let mut bytes = Vec::new();
+        net.to_model().write(&mut bytes, ModelFormat::Binary).unwrap();
+        let model = NetModel::read(bytes.as_slice(), ModelFormat::Binary).unwrap();
+        assert_eq!(model, net.to_model());
+    }
+}
@@ -2,16 +2,21 @@ use crate::backend::Backend;
 use crate::dtype::DType;
 use crate::loss::LossFn;
 use crate::net::initializer::{NetInitializer, RandomNetInitializer};
-use crate::net::layer::{ConcreteLayer, ConcreteLayerParams, Layer, LayerParams};
-use crate::scoring::{NoOpScorer, Scorer};
+use crate::net::layer::{ConcreteLayer, ConcreteLayerParams, Layer, LayerParams, NetLayerParams};
+use crate::net::model::{LayerModel, ModelError, ModelFormat, ModelResult, NetModel, MODEL_FORMAT_VERSION};
+use crate::optimizer::Optimizer;
+use crate::scoring::Scorer;
 use crate::tensor::{Dim0, Dim1, Dim2, ITensor, Tensor1, Tensor2, TensorBase, TensorView2};
-use rand::seq::SliceRandom;
-use rand::Rng;
 use std::fmt::{Debug, Formatter};
-use std::iter::zip;
+use std::iter::{once, zip};
+use std::path::Path;
 
 pub mod initializer;
 pub mod layer;
+pub mod model;
+pub mod train;
+
+pub use train::{CheckpointConfig, EpochStats, TrainConfig, Trainer, TrainingHistory};
 
 struct RawNet<B: Backend> {
     backend: B,
@@ -27,6 +32,8 @@ struct RawNet<B: Backend> {
     last_input_error: B::Tensor<Dim2>,
     output_error_buff: B::Tensor<Dim1>,
     output_error_deriv_buff: B::Tensor<Dim2>,
+    /// the summed loss of each row of the batches since the last call to `take_loss`, one per batch size
+    loss_accum: Vec<B::Tensor<Dim1>>,
 }
 
 impl<B: Backend> RawNet<B> {
@@ -56,24 +63,51 @@ impl<B: Backend> RawNet<B> {
             output_error_deriv_buff,
             hidden_input_error,
             last_input_error,
+            loss_accum: Vec::new(),
         }
     }
 
-    fn forward(&mut self, input: B::TensorRef<'_, Dim2>) {
+    fn forward(&mut self, input: B::TensorRef<'_, Dim2>, training: bool) {
         let num_rows = input.dims().rows();
         self.backend.resize_tensor_major(&mut self.first_output, num_rows);
-        self.first.forward(&self.backend, input, &mut self.first_output);
+        self.first.forward(&self.backend, input, &mut self.first_output, training);
 
         let mut input = &self.first_output;
         for (layer, output) in zip(self.hidden.iter_mut(), self.hidden_outputs.iter_mut()) {
             self.backend.resize_tensor_major(output, num_rows);
-            layer.forward(&self.backend, B::TensorRef::from(input), output);
+            layer.forward(&self.backend, B::TensorRef::from(input), output, training);
             input = output;
         }
 
         self.backend.resize_tensor_major(&mut self.last_output, num_rows);
         self.last
-            .forward(&self.backend, B::TensorRef::from(input), &mut self.last_output);
+            .forward(&self.backend, B::TensorRef::from(input), &mut self.last_output, training);
+    }
+
+    /// adds the loss of each row of the last batch to an accumulator on the device, so that training only
+    /// reads it back once per epoch instead of waiting for every batch
+    fn accum_loss(&mut self) -> Result<(), B::Error> {
+        let dims = *self.output_error_buff.dims();
+        let accum = match self.loss_accum.iter().position(|accum| accum.dims() == &dims) {
+            Some(index) => &mut self.loss_accum[index],
+            None => {
+                self.loss_accum.push(self.backend.try_new_tensor_exact(dims)?);
+                self.loss_accum.last_mut().unwrap()
+            }
+        };
+        self.backend
+            .add_assign(B::Float::ONE, &self.output_error_buff, B::Float::ONE, accum);
+        Ok(())
+    }
+
+    /// reads the total loss accumulated by `accum_loss` and resets it
+    fn take_loss(&mut self) -> Result<f64, B::Error> {
+        let mut total = 0.0;
+        for accum in self.loss_accum.drain(..) {
+            let loss = self.backend.try_tensor_as_native(&accum)?;
+            total += loss.iter().map(|l| l.to_f64()).sum::<f64>();
+        }
+        Ok(total)
     }
 
     fn backprop(
@@ -81,8 +115,6 @@ impl<B: Backend> RawNet<B> {
         input: B::TensorRef<'_, Dim2>,
         expected: B::TensorRef<'_, Dim2>,
         loss: &LossFn,
-        learn_rate: B::Float,
-        momentum: B::Float,
     ) {
         let num_rows = input.dims().rows();
         self.backend.resize_tensor(&mut self.output_error_buff, Dim1(num_rows));
@@ -108,8 +140,6 @@ impl<B: Backend> RawNet<B> {
             &self.last_output,
             Some(&mut self.last_input_error),
             &self.output_error_deriv_buff,
-            learn_rate,
-            momentum,
         );
 
         let mut output_error = &self.last_input_error;
@@ -132,21 +162,26 @@ impl<B: Backend> RawNet<B> {
                 output,
                 Some(input_error),
                 output_error,
-                learn_rate,
-                momentum,
             );
             output_error = input_error;
         }
 
-        self.first.backprop(
-            &self.backend,
-            input,
-            &self.first_output,
-            None,
-            output_error,
-            learn_rate,
-            momentum,
-        )
+        self.first
+            .backprop(&self.backend, input, &self.first_output, None, output_error)
+    }
+
+    /// iterates over all layers in order
+    fn layers(&self) -> impl Iterator<Item = &ConcreteLayer<B>> {
+        once(&self.first).chain(self.hidden.iter()).chain(once(&self.last))
+    }
+
+    fn update_params(&mut self, optimizer: &mut Optimizer<B>) {
+        optimizer.begin_step();
+        self.first.update_params(&self.backend, optimizer);
+        for layer in self.hidden.iter_mut() {
+            layer.update_params(&self.backend, optimizer);
+        }
+        self.last.update_params(&self.backend, optimizer);
     }
 }
 
@@ -154,7 +189,7 @@ pub struct NetBuilder<B: Backend> {
     backend: B,
     input_size: usize,
     initializer: Box<dyn NetInitializer<B::Float>>,
-    layers: Vec<ConcreteLayerParams>,
+    layers: Vec<NetLayerParams<B>>,
 }
 
 impl<B: Backend> NetBuilder<B> {
@@ -174,18 +209,26 @@ impl<B: Backend> NetBuilder<B> {
         self
     }
 
+    /// adds a layer, given either the params of a layer in this crate or a [layer::CustomLayerParams]
     pub fn with_layer<T>(mut self, layer: T) -> Self
     where
-        T: Into<ConcreteLayerParams>,
+        T: Into<NetLayerParams<B>>,
     {
         self.layers.push(layer.into());
         self
     }
 
+    /// creates the net, or returns `None` if it has less than two layers or a [ConcreteLayerParams::Custom],
+    /// which only names a custom layer and has to be replaced by its [layer::CustomLayerParams]
     pub fn build(mut self) -> Option<Net<B>> {
-        if self.layers.len() < 2 {
+        let has_unresolved_custom = self
+            .layers
+            .iter()
+            .any(|layer| matches!(layer, NetLayerParams::Concrete(ConcreteLayerParams::Custom { .. })));
+        if self.layers.len() < 2 || has_unresolved_custom {
             None
         } else {
+            let layer_params = self.layers.iter().map(NetLayerParams::model_params).collect();
             let first_params = self.layers.remove(0);
             let last_params = self.layers.pop().unwrap();
             let first = first_params.create_layer(&self.backend, 0, self.input_size, self.initializer.as_mut());
@@ -199,12 +242,10 @@ impl<B: Backend> NetBuilder<B> {
                 hidden.push(layer);
             }
             let last = last_params.create_layer(&self.backend, layer_idx, last_size, self.initializer.as_mut());
-            Some(Net::new(RawNet::new(
-                self.backend,
-                first,
-                hidden.into_boxed_slice(),
-                last,
-            )))
+            Some(Net::new(
+                RawNet::new(self.backend, first, hidden.into_boxed_slice(), last),
+                layer_params,
+            ))
         }
     }
 }
@@ -221,6 +262,7 @@ pub struct TrainBatchResult<'a, T> {
 
 pub struct Net<B: Backend> {
     raw: RawNet<B>,
+    layer_params: Vec<ConcreteLayerParams>,
     input_buff: B::InputAdaptionBuff<Dim2>,
     output_buff: B::OutputAdaptionBuff<Dim2>,
     // training only
@@ -229,7 +271,7 @@ pub struct Net<B: Backend> {
 }
 
 impl<B: Backend> Net<B> {
-    fn new(raw: RawNet<B>) -> Self {
+    fn new(raw: RawNet<B>, layer_params: Vec<ConcreteLayerParams>) -> Self {
         let input_size = raw.first.input_size();
         let output_size = raw.last.output_size();
         let input_buff = raw.backend.new_input_adaption_buff(Dim1(input_size));
@@ -238,6 +280,7 @@ impl<B: Backend> Net<B> {
         let error_buff = raw.backend.new_output_adaption_buff(Dim0);
         Self {
             raw,
+            layer_params,
             input_buff,
             output_buff,
             expected_buff,
@@ -245,7 +288,7 @@ impl<B: Backend> Net<B> {
         }
     }
 
-    pub fn predict(&mut self, input: TensorView2<B::Float>) -> &Tensor2<B::Float> {
+    pub fn predict(&mut self, input: TensorView2<B::Float>) -> Result<&Tensor2<B::Float>, B::Error> {
         let &Dim2(num_rows, num_cols) = input.dims();
         let max_batch_size = self.max_batch_size();
         assert_eq!(
@@ -257,11 +300,11 @@ impl<B: Backend> Net<B> {
             num_rows <= max_batch_size,
             "Invalid number of rows for input tensor: {num_rows}. Max allowed: {max_batch_size}.",
         );
-        let input = self.raw.backend.adapt_input(&mut self.input_buff, input);
-        self.raw.forward(input);
+        let input = self.raw.backend.try_adapt_input(&mut self.input_buff, input)?;
+        self.raw.forward(input, false);
         self.raw
             .backend
-            .adapt_output(&mut self.output_buff, &self.raw.last_output)
+            .try_adapt_output(&mut self.output_buff, &self.raw.last_output)
     }
 
     pub fn train_batch(
@@ -269,9 +312,8 @@ impl<B: Backend> Net<B> {
         input: TensorView2<B::Float>,
         expected: TensorView2<B::Float>,
         loss: &LossFn,
-        learn_rate: B::Float,
-        momentum: B::Float,
-    ) -> TrainBatchResult<B::Float> {
+        optimizer: &mut Optimizer<B>,
+    ) -> Result<TrainBatchResult<B::Float>, B::Error> {
         let &Dim2(num_rows, num_cols) = input.dims();
         let max_batch_size = self.max_batch_size();
 
@@ -290,110 +332,33 @@ impl<B: Backend> Net<B> {
             "Invalid dimensions for expected tensor"
         );
 
-        let input = self.raw.backend.adapt_input(&mut self.input_buff, input);
-        let expected = self.raw.backend.adapt_input(&mut self.expected_buff, expected);
+        let input = self.raw.backend.try_adapt_input(&mut self.input_buff, input)?;
+        let expected = self.raw.backend.try_adapt_input(&mut self.expected_buff, expected)?;
 
-        self.raw.forward(input.clone());
-        self.raw.backprop(input, expected, &loss, learn_rate, momentum);
+        self.raw.forward(input.clone(), true);
+        self.raw.backprop(input, expected, loss);
+        self.raw.update_params(optimizer);
 
         let output = self
             .raw
             .backend
-            .adapt_output(&mut self.output_buff, &self.raw.last_output);
+            .try_adapt_output(&mut self.output_buff, &self.raw.last_output)?;
         let error = self
             .raw
             .backend
-            .adapt_output(&mut self.error_buff, &self.raw.output_error_buff);
+            .try_adapt_output(&mut self.error_buff, &self.raw.output_error_buff)?;
 
-        TrainBatchResult { output, error }
+        Ok(TrainBatchResult { output, error })
     }
 
-    fn train_epoch<S: Scorer<B>>(
+    /// trains the net with a new [Trainer] for the given config, see [Trainer::train]
+    pub fn train(
         &mut self,
-        batches: &[(B::TensorRef<'_, Dim2>, B::TensorRef<'_, Dim2>)],
-        loss: &LossFn,
-        learn_rate: B::Float,
-        momentum: B::Float,
-        scorer: &mut S,
-    ) {
-        for (input, expected) in batches {
-            let num_rows = input.dims().rows();
-            debug_assert_eq!(num_rows, expected.dims().rows());
-            debug_assert_eq!(input.dims().cols(), self.input_size());
-            debug_assert_eq!(expected.dims().cols(), self.output_size());
-            self.raw.forward(input.clone());
-            self.raw
-                .backprop(input.clone(), expected.clone(), &loss, learn_rate, momentum);
-            scorer.process_batch(&self.raw.backend, &self.raw.last_output, expected.clone());
-            self.raw.backend.flush();
-        }
-        self.raw.backend.sync();
-    }
-
-    pub fn train<R: Rng>(
-        &mut self,
-        rng: &mut R,
         input: TensorView2<B::Float>,
         expected: TensorView2<B::Float>,
-        num_epochs: usize,
-    ) {
-        let input_size = self.input_size();
-        let output_size = self.output_size();
-        assert_eq!(
-            input.dims().rows(),
-            expected.dims().rows(),
-            "Mismatched number of rows in input and expected"
-        );
-        assert_eq!(
-            input.dims().cols(),
-            self.input_size(),
-            "Mismatched number of columns in input"
-        );
-        assert_eq!(
-            expected.dims().cols(),
-            self.output_size(),
-            "Mismatched number of columns in expected"
-        );
-        let batch_size = self.raw.backend.max_batch_size();
-        let num_batches = (input.dims().rows() as f64 / batch_size as f64).ceil() as usize;
-
-        // allocate buffers for the input and expected data
-        let mut buffers: Vec<_> = (0..num_batches)
-            .map(|_| {
-                (
-                    self.raw.backend.new_input_adaption_buff(Dim1(input_size)),
-                    self.raw.backend.new_input_adaption_buff(Dim1(output_size)),
-                )
-            })
-            .collect();
-
-        let mut batches = Vec::with_capacity(num_batches);
-
-        // convert input to backend format
-        for ((input_batch, expected_batch), (input_buff, expected_buff)) in zip(
-            zip(
-                input.iter_major_axis_chunks(batch_size),
-                expected.iter_major_axis_chunks(batch_size),
-            ),
-            buffers.iter_mut(),
-        ) {
-            batches.push((
-                self.raw.backend.adapt_input(input_buff, input_batch),
-                self.raw.backend.adapt_input(expected_buff, expected_batch),
-            ));
-        }
-
-        for i in 0..num_epochs {
-            batches.shuffle(rng);
-            self.train_epoch(
-                &batches,
-                &LossFn::MSE,
-                B::Float::from_f64(0.1),
-                B::Float::from_f64(0.1),
-                &mut NoOpScorer,
-            );
-            println!("epoch {i}");
-        }
+        config: &TrainConfig,
+    ) -> ModelResult<TrainingHistory> {
+        Trainer::new(config.clone()).train(self, input, expected)
     }
 
     pub fn evaluate<S: Scorer<B>>(
@@ -401,7 +366,7 @@ impl<B: Backend> Net<B> {
         input: TensorView2<B::Float>,
         expected: TensorView2<B::Float>,
         scorer: &mut S,
-    ) {
+    ) -> Result<(), B::Error> {
         assert_eq!(
             input.dims().rows(),
             expected.dims().rows(),
@@ -422,11 +387,111 @@ impl<B: Backend> Net<B> {
             input.iter_major_axis_chunks(batch_size),
             expected.iter_major_axis_chunks(batch_size),
         ) {
-            let input_batch = self.raw.backend.adapt_input(&mut self.input_buff, input_batch);
-            let expected_batch = self.raw.backend.adapt_input(&mut self.expected_buff, expected_batch);
-            self.raw.forward(input_batch);
+            let input_batch = self.raw.backend.try_adapt_input(&mut self.input_buff, input_batch)?;
+            let expected_batch = self
+                .raw
+                .backend
+                .try_adapt_input(&mut self.expected_buff, expected_batch)?;
+            self.raw.forward(input_batch, false);
             scorer.process_batch(&self.raw.backend, &self.raw.last_output, expected_batch)
         }
+        self.raw.backend.take_error()
+    }
+
+    /// snapshots the structure and parameters of this net
+    pub fn to_model(&self) -> NetModel {
+        let backend = &self.raw.backend;
+        let layers = zip(self.layer_params.iter(), self.raw.layers())
+            .map(|(params, layer)| LayerModel {
+                params: params.clone(),
+                tensors: layer.export_params(backend),
+            })
+            .collect();
+        NetModel {
+            version: MODEL_FORMAT_VERSION,
+            float_bits: NetModel::float_bits_of::<B::Float>(),
+            input_size: self.input_size(),
+            layers,
+        }
+    }
+
+    /// creates a net on the given backend from a saved model, which may have been created on any backend.
+    /// Nets with custom layers must instead be built with the same layers, then use [Net::import_params].
+    pub fn from_model(backend: B, model: &NetModel) -> ModelResult<Self> {
+        let mut builder = NetBuilder::new(backend, model.input_size);
+        for layer in model.layers.iter() {
+            if let ConcreteLayerParams::Custom { name } = &layer.params {
+                return Err(ModelError::UnsupportedLayer(format!(
+                    "custom layer {name} can't be created from a model"
+                )));
+            }
+            builder = builder.with_layer(layer.params.clone());
+        }
+        let mut net = builder
+            .build()
+            .ok_or_else(|| ModelError::InvalidFormat("a net requires at least two layers".to_string()))?;
+        net.import_params(model)?;
+        Ok(net)
+    }
+
+    /// overwrites the parameters of this net with those of a saved model with the same structure
+    pub fn import_params(&mut self, model: &NetModel) -> ModelResult<()> {
+        if model.input_size != self.input_size()
+            || model.layers.len() != self.layer_params.len()
+            || zip(model.layers.iter(), self.layer_params.iter()).any(|(layer, params)| &layer.params != params)
+        {
+            return Err(ModelError::ParamMismatch(
+                "the model does not have the same structure as the net".to_string(),
+            ));
+        }
+        let RawNet {
+            backend,
+            first,
+            hidden,
+            last,
+            ..
+        } = &mut self.raw;
+        let layers = once(first).chain(hidden.iter_mut()).chain(once(last));
+        for (layer, layer_model) in zip(layers, model.layers.iter()) {
+            layer.import_params(backend, &layer_model.tensors)?;
+        }
+        Ok(())
+    }
+
+    /// the training state of each layer, see [Layer::export_train_state]
+    pub(crate) fn export_train_state(&self) -> Vec<Vec<u64>> {
+        self.raw.layers().map(|layer| layer.export_train_state()).collect()
+    }
+
+    pub(crate) fn import_train_state(&mut self, states: &[Vec<u64>]) -> ModelResult<()> {
+        if states.len() != self.layer_params.len() {
+            return Err(ModelError::ParamMismatch(format!(
+                "expected the training state of {} layers, found {}",
+                self.layer_params.len(),
+                states.len()
+            )));
+        }
+        let RawNet {
+            first, hidden, last, ..
+        } = &mut self.raw;
+        let layers = once(first).chain(hidden.iter_mut()).chain(once(last));
+        for (layer, state) in zip(layers, states.iter()) {
+            layer.import_train_state(state)?;
+        }
+        Ok(())
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P, format: ModelFormat) -> ModelResult<()> {
+        self.to_model().save(path, format)
+    }
+
+    pub fn load<P: AsRef<Path>>(backend: B, path: P) -> ModelResult<Self> {
+        Self::from_model(backend, &NetModel::load(path)?)
+    }
+
+    #[inline]
+    pub fn layer_params(&self) -> &[ConcreteLayerParams] {
+        &self.layer_params
     }
 
     #[inline]
@@ -0,0 +1,606 @@
+//! Compact binary encoding of a [NetModel] and [Checkpoint]. All values are little-endian, sizes are stored as u64,
+//! and parameters as f32 or f64 depending on [NetModel::float_bits].
+
+use super::{Checkpoint, LayerModel, ModelError, ModelResult, NetModel, ParamTensor, RngState};
+use crate::activation::ActivationFn;
+use crate::net::layer::{
+    ActivationLayerParams, BatchNormLayerParams, ConcreteLayerParams, Conv2dLayerParams, DenseLayerParams,
+    DropoutLayerParams, LayerNormLayerParams, Pool2dLayerParams,
+};
+use crate::optimizer::{OptimizerFn, OptimizerState};
+use crate::tensor::Dim3;
+use std::io::{Read, Write};
+
+pub(super) const MAGIC: &[u8] = b"RCANNMDL";
+pub(super) const CHECKPOINT_MAGIC: &[u8] = b"RCANNCKP";
+
+const LAYER_FULLY_CONNECTED: u8 = 0;
+const LAYER_CONV_2D: u8 = 1;
+const LAYER_MAX_POOL_2D: u8 = 2;
+const LAYER_AVG_POOL_2D: u8 = 3;
+const LAYER_DROPOUT: u8 = 4;
+const LAYER_BATCH_NORM: u8 = 5;
+const LAYER_LAYER_NORM: u8 = 6;
+const LAYER_CUSTOM: u8 = 7;
+const LAYER_ACTIVATION: u8 = 8;
+
+const ACTIVATION_SIGMOID: u8 = 0;
+const ACTIVATION_RELU: u8 = 1;
+const ACTIVATION_SOFTMAX: u8 = 2;
+const ACTIVATION_IDENTITY: u8 = 3;
+const ACTIVATION_TANH: u8 = 4;
+const ACTIVATION_ELU: u8 = 5;
+const ACTIVATION_SELU: u8 = 6;
+const ACTIVATION_GELU: u8 = 7;
+const ACTIVATION_SWISH: u8 = 8;
+const ACTIVATION_SOFTPLUS: u8 = 9;
+
+const OPTIMIZER_SGD: u8 = 0;
+const OPTIMIZER_ADAGRAD: u8 = 1;
+const OPTIMIZER_RMS_PROP: u8 = 2;
+const OPTIMIZER_ADAM: u8 = 3;
+const OPTIMIZER_ADAM_W: u8 = 4;
+
+pub(super) fn write_model<W: Write>(writer: W, model: &NetModel) -> ModelResult<()> {
+    let mut w = BinaryWriter(writer);
+    w.write_bytes(MAGIC)?;
+    write_model_body(&mut w, model)
+}
+
+pub(super) fn read_model<R: Read>(reader: R) -> ModelResult<NetModel> {
+    let mut r = BinaryReader(reader);
+    r.read_magic(MAGIC)?;
+    read_model_body(&mut r)
+}
+
+pub(super) fn write_checkpoint<W: Write>(writer: W, checkpoint: &Checkpoint) -> ModelResult<()> {
+    let mut w = BinaryWriter(writer);
+    w.write_bytes(CHECKPOINT_MAGIC)?;
+    w.write_u32(checkpoint.version)?;
+    w.write_usize(checkpoint.epoch)?;
+    write_model_body(&mut w, &checkpoint.model)?;
+    write_optimizer_state(&mut w, &checkpoint.optimizer, checkpoint.model.float_bits)?;
+    let RngState { seed, stream, word_pos } = &checkpoint.rng;
+    w.write_bytes(seed)?;
+    w.write_u64(*stream)?;
+    w.write_bytes(&word_pos.to_le_bytes())?;
+    w.write_usize(checkpoint.layer_states.len())?;
+    for state in checkpoint.layer_states.iter() {
+        w.write_usize(state.len())?;
+        for &value in state.iter() {
+            w.write_u64(value)?;
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn read_checkpoint<R: Read>(reader: R) -> ModelResult<Checkpoint> {
+    let mut r = BinaryReader(reader);
+    r.read_magic(CHECKPOINT_MAGIC)?;
+    let version = read_version(&mut r)?;
+    let epoch = r.read_usize()?;
+    let model = read_model_body(&mut r)?;
+    let optimizer = read_optimizer_state(&mut r, model.float_bits)?;
+    let mut seed = [0u8; 32];
+    r.read_bytes(&mut seed)?;
+    let stream = r.read_u64()?;
+    let mut word_pos = [0u8; 16];
+    r.read_bytes(&mut word_pos)?;
+    let num_layers = r.read_usize()?;
+    let layer_states = (0..num_layers)
+        .map(|_| {
+            let len = r.read_usize()?;
+            (0..len).map(|_| r.read_u64()).collect()
+        })
+        .collect::<ModelResult<_>>()?;
+    Ok(Checkpoint {
+        version,
+        epoch,
+        model,
+        optimizer,
+        rng: RngState {
+            seed,
+            stream,
+            word_pos: u128::from_le_bytes(word_pos),
+        },
+        layer_states,
+    })
+}
+
+fn read_version<R: Read>(r: &mut BinaryReader<R>) -> ModelResult<u32> {
+    let version = r.read_u32()?;
+    if version == 0 || version > super::MODEL_FORMAT_VERSION {
+        Err(ModelError::UnsupportedVersion(version))
+    } else {
+        Ok(version)
+    }
+}
+
+fn write_model_body<W: Write>(w: &mut BinaryWriter<W>, model: &NetModel) -> ModelResult<()> {
+    w.write_u32(model.version)?;
+    w.write_u8(model.float_bits)?;
+    w.write_usize(model.input_size)?;
+    w.write_usize(model.layers.len())?;
+    for layer in model.layers.iter() {
+        write_layer_params(w, &layer.params)?;
+        write_tensors(w, &layer.tensors, model.float_bits)?;
+    }
+    Ok(())
+}
+
+fn read_model_body<R: Read>(r: &mut BinaryReader<R>) -> ModelResult<NetModel> {
+    let version = read_version(r)?;
+    let float_bits = r.read_u8()?;
+    if float_bits != 32 && float_bits != 64 {
+        return Err(ModelError::InvalidFormat(format!("invalid float width: {float_bits}")));
+    }
+    let input_size = r.read_usize()?;
+    let num_layers = r.read_usize()?;
+    let mut layers = Vec::new();
+    for _ in 0..num_layers {
+        let params = read_layer_params(r)?;
+        let tensors = read_tensors(r, float_bits)?;
+        layers.push(LayerModel { params, tensors });
+    }
+    Ok(NetModel {
+        version,
+        float_bits,
+        input_size,
+        layers,
+    })
+}
+
+fn write_optimizer_state<W: Write>(w: &mut BinaryWriter<W>, state: &OptimizerState, float_bits: u8) -> ModelResult<()> {
+    write_optimizer_fn(w, &state.optimizer_fn)?;
+    w.write_usize(state.step)?;
+    for states in [&state.dim1, &state.dim2] {
+        w.write_usize(states.len())?;
+        for tensors in states.iter() {
+            write_tensors(w, tensors, float_bits)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_optimizer_state<R: Read>(r: &mut BinaryReader<R>, float_bits: u8) -> ModelResult<OptimizerState> {
+    let optimizer_fn = read_optimizer_fn(r)?;
+    let step = r.read_usize()?;
+    let mut read_states = || -> ModelResult<Vec<Vec<ParamTensor>>> {
+        let len = r.read_usize()?;
+        (0..len).map(|_| read_tensors(r, float_bits)).collect()
+    };
+    let dim1 = read_states()?;
+    let dim2 = read_states()?;
+    Ok(OptimizerState {
+        optimizer_fn,
+        step,
+        dim1,
+        dim2,
+    })
+}
+
+fn write_optimizer_fn<W: Write>(w: &mut BinaryWriter<W>, optimizer_fn: &OptimizerFn) -> ModelResult<()> {
+    match *optimizer_fn {
+        OptimizerFn::SGD {
+            learn_rate,
+            momentum,
+            nesterov,
+        } => {
+            w.write_u8(OPTIMIZER_SGD)?;
+            w.write_f64(learn_rate)?;
+            w.write_f64(momentum)?;
+            w.write_bool(nesterov)
+        }
+        OptimizerFn::Adagrad { learn_rate, epsilon } => {
+            w.write_u8(OPTIMIZER_ADAGRAD)?;
+            w.write_f64(learn_rate)?;
+            w.write_f64(epsilon)
+        }
+        OptimizerFn::RMSProp {
+            learn_rate,
+            rho,
+            epsilon,
+        } => {
+            w.write_u8(OPTIMIZER_RMS_PROP)?;
+            w.write_f64(learn_rate)?;
+            w.write_f64(rho)?;
+            w.write_f64(epsilon)
+        }
+        OptimizerFn::Adam {
+            learn_rate,
+            beta1,
+            beta2,
+            epsilon,
+        } => {
+            w.write_u8(OPTIMIZER_ADAM)?;
+            w.write_f64(learn_rate)?;
+            w.write_f64(beta1)?;
+            w.write_f64(beta2)?;
+            w.write_f64(epsilon)
+        }
+        OptimizerFn::AdamW {
+            learn_rate,
+            beta1,
+            beta2,
+            epsilon,
+            weight_decay,
+        } => {
+            w.write_u8(OPTIMIZER_ADAM_W)?;
+            w.write_f64(learn_rate)?;
+            w.write_f64(beta1)?;
+            w.write_f64(beta2)?;
+            w.write_f64(epsilon)?;
+            w.write_f64(weight_decay)
+        }
+    }
+}
+
+fn read_optimizer_fn<R: Read>(r: &mut BinaryReader<R>) -> ModelResult<OptimizerFn> {
+    match r.read_u8()? {
+        OPTIMIZER_SGD => Ok(OptimizerFn::SGD {
+            learn_rate: r.read_f64()?,
+            momentum: r.read_f64()?,
+            nesterov: r.read_bool()?,
+        }),
+        OPTIMIZER_ADAGRAD => Ok(OptimizerFn::Adagrad {
+            learn_rate: r.read_f64()?,
+            epsilon: r.read_f64()?,
+        }),
+        OPTIMIZER_RMS_PROP => Ok(OptimizerFn::RMSProp {
+            learn_rate: r.read_f64()?,
+            rho: r.read_f64()?,
+            epsilon: r.read_f64()?,
+        }),
+        OPTIMIZER_ADAM => Ok(OptimizerFn::Adam {
+            learn_rate: r.read_f64()?,
+            beta1: r.read_f64()?,
+            beta2: r.read_f64()?,
+            epsilon: r.read_f64()?,
+        }),
+        OPTIMIZER_ADAM_W => Ok(OptimizerFn::AdamW {
+            learn_rate: r.read_f64()?,
+            beta1: r.read_f64()?,
+            beta2: r.read_f64()?,
+            epsilon: r.read_f64()?,
+            weight_decay: r.read_f64()?,
+        }),
+        tag => Err(ModelError::InvalidFormat(format!("unknown optimizer: {tag}"))),
+    }
+}
+
+fn write_layer_params<W: Write>(w: &mut BinaryWriter<W>, params: &ConcreteLayerParams) -> ModelResult<()> {
+    match params {
+        ConcreteLayerParams::FullyConnected(DenseLayerParams {
+            size,
+            activation_fn,
+            use_bias,
+        }) => {
+            w.write_u8(LAYER_FULLY_CONNECTED)?;
+            w.write_usize(*size)?;
+            write_activation_fn(w, activation_fn)?;
+            w.write_bool(*use_bias)
+        }
+        ConcreteLayerParams::Conv2d(Conv2dLayerParams {
+            input_dims: Dim3(channels, height, width),
+            filters,
+            kernel_size,
+            stride,
+            padding,
+            activation_fn,
+            use_bias,
+        }) => {
+            w.write_u8(LAYER_CONV_2D)?;
+            for value in [channels, height, width, filters, kernel_size, stride, padding] {
+                w.write_usize(*value)?;
+            }
+            write_activation_fn(w, activation_fn)?;
+            w.write_bool(*use_bias)
+        }
+        ConcreteLayerParams::MaxPool2d(params) => {
+            w.write_u8(LAYER_MAX_POOL_2D)?;
+            write_pool_params(w, params)
+        }
+        ConcreteLayerParams::AvgPool2d(params) => {
+            w.write_u8(LAYER_AVG_POOL_2D)?;
+            write_pool_params(w, params)
+        }
+        ConcreteLayerParams::Dropout(DropoutLayerParams { rate, seed }) => {
+            w.write_u8(LAYER_DROPOUT)?;
+            w.write_f64(*rate)?;
+            w.write_u64(*seed)
+        }
+        ConcreteLayerParams::BatchNorm(BatchNormLayerParams { momentum, epsilon }) => {
+            w.write_u8(LAYER_BATCH_NORM)?;
+            w.write_f64(*momentum)?;
+            w.write_f64(*epsilon)
+        }
+        ConcreteLayerParams::LayerNorm(LayerNormLayerParams { epsilon }) => {
+            w.write_u8(LAYER_LAYER_NORM)?;
+            w.write_f64(*epsilon)
+        }
+        ConcreteLayerParams::Activation(ActivationLayerParams { activation_fn }) => {
+            w.write_u8(LAYER_ACTIVATION)?;
+            write_activation_fn(w, activation_fn)
+        }
+        ConcreteLayerParams::Custom { name } => {
+            w.write_u8(LAYER_CUSTOM)?;
+            w.write_str(name)
+        }
+    }
+}
+
+fn read_layer_params<R: Read>(r: &mut BinaryReader<R>) -> ModelResult<ConcreteLayerParams> {
+    match r.read_u8()? {
+        LAYER_FULLY_CONNECTED => Ok(ConcreteLayerParams::FullyConnected(DenseLayerParams {
+            size: r.read_usize()?,
+            activation_fn: read_activation_fn(r)?,
+            use_bias: r.read_bool()?,
+        })),
+        LAYER_CONV_2D => Ok(ConcreteLayerParams::Conv2d(Conv2dLayerParams {
+            input_dims: Dim3(r.read_usize()?, r.read_usize()?, r.read_usize()?),
+            filters: r.read_usize()?,
+            kernel_size: r.read_usize()?,
+            stride: r.read_usize()?,
+            padding: r.read_usize()?,
+            activation_fn: read_activation_fn(r)?,
+            use_bias: r.read_bool()?,
+        })),
+        LAYER_MAX_POOL_2D => Ok(ConcreteLayerParams::MaxPool2d(read_pool_params(r)?)),
+        LAYER_AVG_POOL_2D => Ok(ConcreteLayerParams::AvgPool2d(read_pool_params(r)?)),
+        LAYER_DROPOUT => Ok(ConcreteLayerParams::Dropout(DropoutLayerParams {
+            rate: r.read_f64()?,
+            seed: r.read_u64()?,
+        })),
+        LAYER_BATCH_NORM => Ok(ConcreteLayerParams::BatchNorm(BatchNormLayerParams {
+            momentum: r.read_f64()?,
+            epsilon: r.read_f64()?,
+        })),
+        LAYER_LAYER_NORM => Ok(ConcreteLayerParams::LayerNorm(LayerNormLayerParams {
+            epsilon: r.read_f64()?,
+        })),
+        LAYER_ACTIVATION => Ok(ConcreteLayerParams::Activation(ActivationLayerParams {
+            activation_fn: read_activation_fn(r)?,
+        })),
+        LAYER_CUSTOM => Ok(ConcreteLayerParams::Custom { name: r.read_string()? }),
+        tag => Err(ModelError::InvalidFormat(format!("unknown layer type: {tag}"))),
+    }
+}
+
+fn write_pool_params<W: Write>(w: &mut BinaryWriter<W>, params: &Pool2dLayerParams) -> ModelResult<()> {
+    let Pool2dLayerParams {
+        input_dims: Dim3(channels, height, width),
+        size,
+        stride,
+        padding,
+    } = *params;
+    for value in [channels, height, width, size, stride, padding] {
+        w.write_usize(value)?;
+    }
+    Ok(())
+}
+
+fn read_pool_params<R: Read>(r: &mut BinaryReader<R>) -> ModelResult<Pool2dLayerParams> {
+    Ok(Pool2dLayerParams {
+        input_dims: Dim3(r.read_usize()?, r.read_usize()?, r.read_usize()?),
+        size: r.read_usize()?,
+        stride: r.read_usize()?,
+        padding: r.read_usize()?,
+    })
+}
+
+fn write_activation_fn<W: Write>(w: &mut BinaryWriter<W>, activation_fn: &ActivationFn) -> ModelResult<()> {
+    match activation_fn {
+        ActivationFn::Sigmoid => w.write_u8(ACTIVATION_SIGMOID),
+        ActivationFn::ReLU { leak } => {
+            w.write_u8(ACTIVATION_RELU)?;
+            w.write_f64(*leak)
+        }
+        ActivationFn::Softmax => w.write_u8(ACTIVATION_SOFTMAX),
+        ActivationFn::Identity => w.write_u8(ACTIVATION_IDENTITY),
+        ActivationFn::Tanh => w.write_u8(ACTIVATION_TANH),
+        ActivationFn::ELU { alpha } => {
+            w.write_u8(ACTIVATION_ELU)?;
+            w.write_f64(*alpha)
+        }
+        ActivationFn::SELU => w.write_u8(ACTIVATION_SELU),
+        ActivationFn::GELU => w.write_u8(ACTIVATION_GELU),
+        ActivationFn::Swish => w.write_u8(ACTIVATION_SWISH),
+        ActivationFn::Softplus => w.write_u8(ACTIVATION_SOFTPLUS),
+    }
+}
+
+fn read_activation_fn<R: Read>(r: &mut BinaryReader<R>) -> ModelResult<ActivationFn> {
+    match r.read_u8()? {
+        ACTIVATION_SIGMOID => Ok(ActivationFn::Sigmoid),
+        ACTIVATION_RELU => Ok(ActivationFn::ReLU { leak: r.read_f64()? }),
+        ACTIVATION_SOFTMAX => Ok(ActivationFn::Softmax),
+        ACTIVATION_IDENTITY => Ok(ActivationFn::Identity),
+        ACTIVATION_TANH => Ok(ActivationFn::Tanh),
+        ACTIVATION_ELU => Ok(ActivationFn::ELU { alpha: r.read_f64()? }),
+        ACTIVATION_SELU => Ok(ActivationFn::SELU),
+        ACTIVATION_GELU => Ok(ActivationFn::GELU),
+        ACTIVATION_SWISH => Ok(ActivationFn::Swish),
+        ACTIVATION_SOFTPLUS => Ok(ActivationFn::Softplus),
+        tag => Err(ModelError::InvalidFormat(format!("unknown activation function: {tag}"))),
+    }
+}
+
+fn write_tensors<W: Write>(w: &mut BinaryWriter<W>, tensors: &[ParamTensor], float_bits: u8) -> ModelResult<()> {
+    w.write_usize(tensors.len())?;
+    for tensor in tensors.iter() {
+        write_tensor(w, tensor, float_bits)?;
+    }
+    Ok(())
+}
+
+fn read_tensors<R: Read>(r: &mut BinaryReader<R>, float_bits: u8) -> ModelResult<Vec<ParamTensor>> {
+    let len = r.read_usize()?;
+    (0..len).map(|_| read_tensor(r, float_bits)).collect()
+}
+
+fn write_tensor<W: Write>(w: &mut BinaryWriter<W>, tensor: &ParamTensor, float_bits: u8) -> ModelResult<()> {
+    w.write_usize(tensor.dims.len())?;
+    for &dim in tensor.dims.iter() {
+        w.write_usize(dim)?;
+    }
+    w.write_usize(tensor.data.len())?;
+    for &value in tensor.data.iter() {
+        if float_bits == 32 {
+            w.write_f32(value as f32)?;
+        } else {
+            w.write_f64(value)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_tensor<R: Read>(r: &mut BinaryReader<R>, float_bits: u8) -> ModelResult<ParamTensor> {
+    let num_dims = r.read_usize()?;
+    let dims = (0..num_dims).map(|_| r.read_usize()).collect::<ModelResult<Vec<_>>>()?;
+    let len = r.read_usize()?;
+    let dims_len = dims
+        .iter()
+        .try_fold(1usize, |product, &dim| product.checked_mul(dim))
+        .ok_or_else(|| ModelError::InvalidFormat(format!("tensor dims {dims:?} overflow")))?;
+    if dims_len != len {
+        return Err(ModelError::InvalidFormat(format!(
+            "tensor length {len} does not match dims {dims:?}"
+        )));
+    }
+    let data = (0..len)
+        .map(|_| {
+            if float_bits == 32 {
+                r.read_f32().map(f64::from)
+            } else {
+                r.read_f64()
+            }
+        })
+        .collect::<ModelResult<Vec<_>>>()?;
+    Ok(ParamTensor { dims, data })
+}
+
+struct BinaryWriter<W: Write>(W);
+
+impl<W: Write> BinaryWriter<W> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> ModelResult<()> {
+        Ok(self.0.write_all(bytes)?)
+    }
+    fn write_u8(&mut self, value: u8) -> ModelResult<()> {
+        self.write_bytes(&[value])
+    }
+    fn write_bool(&mut self, value: bool) -> ModelResult<()> {
+        self.write_u8(value as u8)
+    }
+    fn write_u32(&mut self, value: u32) -> ModelResult<()> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+    fn write_u64(&mut self, value: u64) -> ModelResult<()> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+    fn write_usize(&mut self, value: usize) -> ModelResult<()> {
+        self.write_u64(value as u64)
+    }
+    fn write_f32(&mut self, value: f32) -> ModelResult<()> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+    fn write_f64(&mut self, value: f64) -> ModelResult<()> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+    fn write_str(&mut self, value: &str) -> ModelResult<()> {
+        self.write_usize(value.len())?;
+        self.write_bytes(value.as_bytes())
+    }
+}
+
+struct BinaryReader<R: Read>(R);
+
+impl<R: Read> BinaryReader<R> {
+    fn read_bytes(&mut self, buf: &mut [u8]) -> ModelResult<()> {
+        Ok(self.0.read_exact(buf)?)
+    }
+    fn read_magic(&mut self, magic: &[u8]) -> ModelResult<()> {
+        let mut buf = vec![0u8; magic.len()];
+        self.read_bytes(&mut buf)?;
+        if buf != magic {
+            return Err(ModelError::InvalidFormat("missing binary header".to_string()));
+        }
+        Ok(())
+    }
+    fn read_u8(&mut self) -> ModelResult<u8> {
+        let mut buf = [0u8; 1];
+        self.read_bytes(&mut buf)?;
+        Ok(buf[0])
+    }
+    fn read_bool(&mut self) -> ModelResult<bool> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(ModelError::InvalidFormat(format!("invalid bool: {value}"))),
+        }
+    }
+    fn read_u32(&mut self) -> ModelResult<u32> {
+        let mut buf = [0u8; 4];
+        self.read_bytes(&mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+    fn read_u64(&mut self) -> ModelResult<u64> {
+        let mut buf = [0u8; 8];
+        self.read_bytes(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+    fn read_usize(&mut self) -> ModelResult<usize> {
+        usize::try_from(self.read_u64()?).map_err(|_| ModelError::InvalidFormat("size overflow".to_string()))
+    }
+    fn read_f32(&mut self) -> ModelResult<f32> {
+        let mut buf = [0u8; 4];
+        self.read_bytes(&mut buf)?;
+        Ok(f32::from_le_bytes(buf))
+    }
+    fn read_f64(&mut self) -> ModelResult<f64> {
+        let mut buf = [0u8; 8];
+        self.read_bytes(&mut buf)?;
+        Ok(f64::from_le_bytes(buf))
+    }
+    fn read_string(&mut self) -> ModelResult<String> {
+        let len = self.read_u64()?;
+        let mut buf = Vec::new();
+        (&mut self.0).take(len).read_to_end(&mut buf)?;
+        if buf.len() as u64 != len {
+            return Err(ModelError::InvalidFormat("truncated string".to_string()));
+        }
+        String::from_utf8(buf).map_err(|_| ModelError::InvalidFormat("invalid utf-8 string".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_tensor, write_tensor, BinaryReader, BinaryWriter};
+    use crate::net::model::{ModelError, ParamTensor};
+
+    #[test]
+    fn test_tensor_float_bits() {
+        let tensor = ParamTensor {
+            dims: vec![2, 2],
+            data: vec![0.5, -1.25, 3.0, 0.1],
+        };
+        for (float_bits, value_size) in [(32, 4), (64, 8)] {
+            let mut w = BinaryWriter(Vec::new());
+            write_tensor(&mut w, &tensor, float_bits).unwrap();
+            assert_eq!(w.0.len(), 4 * 8 + 4 * value_size);
+            let read = read_tensor(&mut BinaryReader(w.0.as_slice()), float_bits).unwrap();
+            assert_eq!(read.dims, tensor.dims);
+            assert_eq!(read.data[..3], tensor.data[..3]);
+            assert_eq!(read.data[3] as f32, 0.1f32);
+        }
+    }
+
+    #[test]
+    fn test_tensor_dims_overflow() {
+        let mut w = BinaryWriter(Vec::new());
+        for value in [2, 1 << 32, 1 << 32, 0] {
+            w.write_u64(value).unwrap();
+        }
+        let result = read_tensor(&mut BinaryReader(w.0.as_slice()), 64);
+        assert!(matches!(result, Err(ModelError::InvalidFormat(_))));
+    }
+}
@@ -0,0 +1,315 @@
+mod binary;
+
+use crate::backend::Backend;
+use crate::dtype::DType;
+use crate::net::layer::ConcreteLayerParams;
+use crate::optimizer::OptimizerState;
+use crate::tensor::{Dims, ITensor, Tensor, TensorBase};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+/// The version of the saved model format written by this version of the crate
+pub const MODEL_FORMAT_VERSION: u32 = 1;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ModelFormat {
+    /// Compact, little-endian binary format
+    Binary,
+    /// Human-readable JSON format
+    #[cfg(feature = "serde")]
+    Json,
+}
+
+#[derive(Debug)]
+pub enum ModelError {
+    Io(std::io::Error),
+    #[cfg(feature = "serde")]
+    Json(serde_json::Error),
+    InvalidFormat(String),
+    UnsupportedVersion(u32),
+    /// The saved parameters do not fit the layers they are loaded into
+    ParamMismatch(String),
+    /// The model contains a layer which can't be created from its saved params
+    UnsupportedLayer(String),
+    /// An op of the backend failed while training the net
+    Backend(Box<dyn std::error::Error + Send + Sync>),
+}
+
+pub type ModelResult<T> = Result<T, ModelError>;
+
+impl Display for ModelError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelError::Io(err) => write!(f, "I/O error: {err}"),
+            #[cfg(feature = "serde")]
+            ModelError::Json(err) => write!(f, "JSON error: {err}"),
+            ModelError::InvalidFormat(msg) => write!(f, "Invalid model format: {msg}"),
+            ModelError::UnsupportedVersion(version) => write!(
+                f,
+                "Unsupported model format version: {version}. Max supported: {MODEL_FORMAT_VERSION}"
+            ),
+            ModelError::ParamMismatch(msg) => write!(f, "Mismatched model parameters: {msg}"),
+            ModelError::UnsupportedLayer(msg) => write!(f, "Unsupported layer: {msg}"),
+            ModelError::Backend(err) => write!(f, "Backend error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+impl From<std::io::Error> for ModelError {
+    fn from(err: std::io::Error) -> Self {
+        ModelError::Io(err)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for ModelError {
+    fn from(err: serde_json::Error) -> Self {
+        ModelError::Json(err)
+    }
+}
+
+/// A backend-independent copy of a parameter tensor, stored as f64 so it can be loaded with any float type
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ParamTensor {
+    pub dims: Vec<usize>,
+    pub data: Vec<f64>,
+}
+
+impl ParamTensor {
+    pub fn read_from<B: Backend, D: Dims>(backend: &B, tensor: &B::Tensor<D>) -> Self {
+        let native = backend.tensor_as_native(tensor);
+        ParamTensor {
+            dims: native.dims().as_vec(),
+            data: native.iter().map(|v| v.to_f64()).collect(),
+        }
+    }
+
+    pub fn write_to<B: Backend, D: Dims>(&self, backend: &B, tensor: &mut B::Tensor<D>) -> ModelResult<()> {
+        let dims = *tensor.dims();
+        if self.dims != dims.as_vec() || self.data.len() != dims.tensor_len() {
+            return Err(ModelError::ParamMismatch(format!(
+                "expected a tensor with dims {dims}, found {:?}",
+                self.dims
+            )));
+        }
+        let native = Tensor::from_vec(self.data.iter().map(|&v| B::Float::from_f64(v)).collect(), dims);
+        backend
+            .try_write_tensor(tensor, &native)
+            .map_err(|err| ModelError::Backend(Box::new(err)))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LayerModel {
+    pub params: ConcreteLayerParams,
+    pub tensors: Vec<ParamTensor>,
+}
+
+/// A saved net: its structure and the values of all of its parameters
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NetModel {
+    pub version: u32,
+    /// the width the binary format stores the parameters with, 32 or 64: the smallest which holds the float
+    /// type of the backend the model was saved from
+    pub float_bits: u8,
+    pub input_size: usize,
+    pub layers: Vec<LayerModel>,
+}
+
+impl NetModel {
+    /// the value of [NetModel::float_bits] for a float type
+    pub fn float_bits_of<F: DType>() -> u8 {
+        if F::BITS <= 32 { 32 } else { 64 }
+    }
+
+    fn check_version(&self) -> ModelResult<()> {
+        check_version(self.version)
+    }
+
+    pub fn write<W: Write>(&self, writer: W, format: ModelFormat) -> ModelResult<()> {
+        match format {
+            ModelFormat::Binary => binary::write_model(writer, self),
+            #[cfg(feature = "serde")]
+            ModelFormat::Json => Ok(serde_json::to_writer_pretty(writer, self)?),
+        }
+    }
+
+    pub fn read<R: Read>(reader: R, format: ModelFormat) -> ModelResult<Self> {
+        let model = match format {
+            ModelFormat::Binary => binary::read_model(reader)?,
+            #[cfg(feature = "serde")]
+            ModelFormat::Json => serde_json::from_reader::<_, NetModel>(reader)?,
+        };
+        model.check_version()?;
+        Ok(model)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P, format: ModelFormat) -> ModelResult<()> {
+        save_file(path.as_ref(), |writer| self.write(writer, format))
+    }
+
+    /// loads a model from a file, detecting whether it is in binary or JSON format
+    pub fn load<P: AsRef<Path>>(path: P) -> ModelResult<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::read(bytes.as_slice(), detect_format(&bytes, binary::MAGIC)?)
+    }
+}
+
+/// The state of the shuffle rng of a trainer
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RngState {
+    pub seed: [u8; 32],
+    pub stream: u64,
+    pub word_pos: u128,
+}
+
+/// Everything needed to resume training exactly where it left off
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Checkpoint {
+    pub version: u32,
+    /// the number of epochs completed
+    pub epoch: usize,
+    pub model: NetModel,
+    pub optimizer: OptimizerState,
+    pub rng: RngState,
+    /// the training state of each layer besides its parameters, e.g. the number of dropout masks sampled
+    pub layer_states: Vec<Vec<u64>>,
+}
+
+impl Checkpoint {
+    pub fn write<W: Write>(&self, writer: W, format: ModelFormat) -> ModelResult<()> {
+        match format {
+            ModelFormat::Binary => binary::write_checkpoint(writer, self),
+            #[cfg(feature = "serde")]
+            ModelFormat::Json => Ok(serde_json::to_writer_pretty(writer, self)?),
+        }
+    }
+
+    pub fn read<R: Read>(reader: R, format: ModelFormat) -> ModelResult<Self> {
+        let checkpoint = match format {
+            ModelFormat::Binary => binary::read_checkpoint(reader)?,
+            #[cfg(feature = "serde")]
+            ModelFormat::Json => serde_json::from_reader::<_, Checkpoint>(reader)?,
+        };
+        check_version(checkpoint.version)?;
+        checkpoint.model.check_version()?;
+        Ok(checkpoint)
+    }
+
+    /// saves the checkpoint, replacing any existing file only once it has been completely written
+    pub fn save<P: AsRef<Path>>(&self, path: P, format: ModelFormat) -> ModelResult<()> {
+        save_file(path.as_ref(), |writer| self.write(writer, format))
+    }
+
+    /// loads a checkpoint from a file, detecting whether it is in binary or JSON format
+    pub fn load<P: AsRef<Path>>(path: P) -> ModelResult<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::read(bytes.as_slice(), detect_format(&bytes, binary::CHECKPOINT_MAGIC)?)
+    }
+}
+
+fn check_version(version: u32) -> ModelResult<()> {
+    if version == 0 || version > MODEL_FORMAT_VERSION {
+        Err(ModelError::UnsupportedVersion(version))
+    } else {
+        Ok(())
+    }
+}
+
+/// writes to a temporary file next to `path`, then renames it so a partially written file is never left behind
+fn save_file<F>(path: &Path, write: F) -> ModelResult<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> ModelResult<()>,
+{
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+    let mut writer = BufWriter::new(File::create(&tmp_path)?);
+    write(&mut writer)?;
+    writer.flush()?;
+    drop(writer);
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn detect_format(bytes: &[u8], magic: &[u8]) -> ModelResult<ModelFormat> {
+    if bytes.starts_with(magic) {
+        return Ok(ModelFormat::Binary);
+    }
+    #[cfg(feature = "serde")]
+    if bytes.trim_ascii_start().starts_with(b"{") {
+        return Ok(ModelFormat::Json);
+    }
+    Err(ModelError::InvalidFormat("unrecognized file format".to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ModelFormat, NetModel};
+    use crate::activation::ActivationFn;
+    use crate::backend::CpuBackend;
+    use crate::net::initializer::RandomNetInitializer;
+    use crate::net::layer::DenseLayerParams;
+    use crate::net::{Net, NetBuilder};
+    use crate::tensor::{Tensor2, TensorBase};
+    use std::iter::zip;
+
+    fn create_net() -> Net<CpuBackend<f32>> {
+        NetBuilder::new(CpuBackend::<f32>::new(4), 3)
+            .with_initializer(RandomNetInitializer::seed_from_u64(0x1234))
+            .with_layer(DenseLayerParams {
+                size: 5,
+                activation_fn: ActivationFn::ReLU { leak: 0.1 },
+                use_bias: true,
+            })
+            .with_layer(DenseLayerParams {
+                size: 2,
+                activation_fn: ActivationFn::Softmax,
+                use_bias: false,
+            })
+            .build()
+            .unwrap()
+    }
+
+    fn check_round_trip(format: ModelFormat) {
+        let mut net = create_net();
+        let mut bytes = Vec::new();
+        net.to_model().write(&mut bytes, format).unwrap();
+        let model = NetModel::read(bytes.as_slice(), format).unwrap();
+        assert_eq!(model, net.to_model());
+        assert_eq!(model.float_bits, 32);
+
+        // load the model into a backend with a different float type
+        let mut loaded = Net::from_model(CpuBackend::<f64>::new(4), &model).unwrap();
+        assert_eq!(loaded.layer_params(), net.layer_params());
+        let input = Tensor2::from_vec_2d(vec![[0.5, -1.0, 2.0], [1.5, 0.25, -0.75]]);
+        let input64 = Tensor2::from_vec_2d(vec![[0.5, -1.0, 2.0], [1.5, 0.25, -0.75]]);
+        let expected = net.predict(input.view()).unwrap().clone();
+        let actual = loaded.predict(input64.view()).unwrap();
+        for (&e, &a) in zip(expected.iter(), actual.iter()) {
+            assert!((e as f64 - a).abs() < 1e-5, "{e} != {a}");
+        }
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        check_round_trip(ModelFormat::Binary);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_round_trip() {
+        check_round_trip(ModelFormat::Json);
+    }
+}
@@ -0,0 +1,403 @@
+use crate::backend::Backend;
+use crate::loss::LossFn;
+use crate::net::model::{Checkpoint, ModelError, ModelFormat, ModelResult, RngState, MODEL_FORMAT_VERSION};
+use crate::net::Net;
+use crate::optimizer::{Optimizer, OptimizerFn};
+use crate::scoring::{NoOpScorer, Scorer};
+use crate::tensor::{Dim1, Dim2, ITensor, TensorBase, TensorView2};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::fmt::{Debug, Formatter};
+use std::iter::zip;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrainConfig {
+    pub loss: LossFn,
+    pub optimizer: OptimizerFn,
+    /// number of rows per batch, must not exceed the max batch size of the backend.
+    /// Defaults to the max batch size when `None`.
+    pub batch_size: Option<usize>,
+    /// total number of epochs to train for
+    pub num_epochs: usize,
+    /// whether to shuffle the order of the batches at the start of each epoch
+    pub shuffle: bool,
+    /// seed for the shuffle rng
+    pub seed: u64,
+    /// periodically saves a [Checkpoint] during training, see [Trainer::resume_from]
+    pub checkpoint: Option<CheckpointConfig>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CheckpointConfig {
+    pub path: PathBuf,
+    /// a checkpoint is written after every this many epochs, and after the last epoch
+    pub every_n_epochs: usize,
+    pub format: ModelFormat,
+}
+
+impl CheckpointConfig {
+    pub fn new<P: Into<PathBuf>>(path: P, every_n_epochs: usize) -> Self {
+        CheckpointConfig {
+            path: path.into(),
+            every_n_epochs,
+            format: ModelFormat::Binary,
+        }
+    }
+}
+
+impl Default for TrainConfig {
+    fn default() -> Self {
+        TrainConfig {
+            loss: LossFn::default(),
+            optimizer: OptimizerFn::default(),
+            batch_size: None,
+            num_epochs: 1,
+            shuffle: true,
+            seed: 0,
+            checkpoint: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EpochStats {
+    pub epoch: usize,
+    /// mean loss over all rows in the epoch
+    pub loss: f64,
+    /// metrics reported by the scorer at the end of the epoch
+    pub metrics: Vec<(String, f64)>,
+    pub duration: Duration,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrainingHistory {
+    pub epochs: Vec<EpochStats>,
+}
+
+impl TrainingHistory {
+    pub fn last(&self) -> Option<&EpochStats> {
+        self.epochs.last()
+    }
+
+    pub fn total_duration(&self) -> Duration {
+        self.epochs.iter().map(|e| e.duration).sum()
+    }
+}
+
+/// Trains a [Net] according to a [TrainConfig], keeping the optimizer state, scorer and shuffle rng
+/// across calls to [Trainer::train].
+pub struct Trainer<B: Backend> {
+    config: TrainConfig,
+    optimizer: Optimizer<B>,
+    scorer: Box<dyn Scorer<B>>,
+    rng: ChaCha8Rng,
+    epoch: usize,
+}
+
+impl<B: Backend> Trainer<B> {
+    pub fn new(config: TrainConfig) -> Self {
+        Trainer {
+            optimizer: Optimizer::new(config.optimizer),
+            scorer: Box::new(NoOpScorer),
+            rng: ChaCha8Rng::seed_from_u64(config.seed),
+            epoch: 0,
+            config,
+        }
+    }
+
+    pub fn with_scorer<S>(mut self, scorer: S) -> Self
+    where
+        S: 'static + Scorer<B>,
+    {
+        self.scorer = Box::new(scorer);
+        self
+    }
+
+    #[inline]
+    pub fn config(&self) -> &TrainConfig {
+        &self.config
+    }
+
+    #[inline]
+    pub fn optimizer(&self) -> &Optimizer<B> {
+        &self.optimizer
+    }
+
+    #[inline]
+    pub fn scorer(&self) -> &dyn Scorer<B> {
+        self.scorer.as_ref()
+    }
+
+    /// the number of epochs completed so far
+    #[inline]
+    pub fn epoch(&self) -> usize {
+        self.epoch
+    }
+
+    /// restores the net parameters and training state of its layers, the optimizer state, epoch counter and
+    /// shuffle rng from a checkpoint, so that continuing with [Trainer::train] gives exactly the same result as
+    /// an uninterrupted run
+    pub fn resume_from<P: AsRef<Path>>(&mut self, net: &mut Net<B>, path: P) -> ModelResult<()> {
+        let checkpoint = Checkpoint::load(path)?;
+        net.import_params(&checkpoint.model)?;
+        net.import_train_state(&checkpoint.layer_states)?;
+        self.optimizer.import_state(&net.raw.backend, &checkpoint.optimizer)?;
+        let RngState { seed, stream, word_pos } = checkpoint.rng;
+        self.rng = ChaCha8Rng::from_seed(seed);
+        self.rng.set_stream(stream);
+        self.rng.set_word_pos(word_pos);
+        self.epoch = checkpoint.epoch;
+        Ok(())
+    }
+
+    pub fn checkpoint(&self, net: &Net<B>) -> Checkpoint {
+        Checkpoint {
+            version: MODEL_FORMAT_VERSION,
+            epoch: self.epoch,
+            model: net.to_model(),
+            optimizer: self.optimizer.export_state(&net.raw.backend),
+            rng: RngState {
+                seed: self.rng.get_seed(),
+                stream: self.rng.get_stream(),
+                word_pos: self.rng.get_word_pos(),
+            },
+            layer_states: net.export_train_state(),
+        }
+    }
+
+    /// trains the net for the remaining epochs, up to `num_epochs` in total
+    pub fn train(
+        &mut self,
+        net: &mut Net<B>,
+        input: TensorView2<B::Float>,
+        expected: TensorView2<B::Float>,
+    ) -> ModelResult<TrainingHistory> {
+        let input_size = net.input_size();
+        let output_size = net.output_size();
+        assert_eq!(
+            input.dims().rows(),
+            expected.dims().rows(),
+            "Mismatched number of rows in input and expected"
+        );
+        assert_eq!(input.dims().cols(), input_size, "Mismatched number of columns in input");
+        assert_eq!(
+            expected.dims().cols(),
+            output_size,
+            "Mismatched number of columns in expected"
+        );
+        let max_batch_size = net.max_batch_size();
+        let batch_size = self.config.batch_size.unwrap_or(max_batch_size);
+        assert!(
+            batch_size > 0 && batch_size <= max_batch_size,
+            "Invalid batch size: {batch_size}. Max allowed: {max_batch_size}."
+        );
+        let num_batches = input.dims().rows().div_ceil(batch_size);
+        let backend = &net.raw.backend;
+        let backend_err = |err: B::Error| ModelError::Backend(Box::new(err));
+
+        // allocate buffers for the input and expected data
+        let mut buffers: Vec<_> = (0..num_batches)
+            .map(|_| {
+                Ok((
+                    backend.try_new_input_adaption_buff(Dim1(input_size))?,
+                    backend.try_new_input_adaption_buff(Dim1(output_size))?,
+                ))
+            })
+            .collect::<Result<_, B::Error>>()
+            .map_err(backend_err)?;
+
+        let mut batches = Vec::with_capacity(num_batches);
+
+        // convert input to backend format
+        for ((input_batch, expected_batch), (input_buff, expected_buff)) in zip(
+            zip(
+                input.iter_major_axis_chunks(batch_size),
+                expected.iter_major_axis_chunks(batch_size),
+            ),
+            buffers.iter_mut(),
+        ) {
+            batches.push((
+                backend.try_adapt_input(input_buff, input_batch).map_err(backend_err)?,
+                backend.try_adapt_input(expected_buff, expected_batch).map_err(backend_err)?,
+            ));
+        }
+
+        let mut history = TrainingHistory::default();
+        let mut order: Vec<usize> = (0..num_batches).collect();
+        while self.epoch < self.config.num_epochs {
+            // shuffle a fresh permutation each epoch so the order only depends on the rng state
+            if self.config.shuffle {
+                order.sort_unstable();
+                order.shuffle(&mut self.rng);
+            }
+            let stats = self
+                .train_epoch(net, order.iter().map(|&i| &batches[i]))
+                .map_err(backend_err)?;
+            history.epochs.push(stats);
+            if let Some(checkpoint) = &self.config.checkpoint {
+                let every = checkpoint.every_n_epochs.max(1);
+                if self.epoch.is_multiple_of(every) || self.epoch == self.config.num_epochs {
+                    self.checkpoint(net).save(&checkpoint.path, checkpoint.format)?;
+                }
+            }
+        }
+        Ok(history)
+    }
+
+    fn train_epoch<'a, 'b: 'a, I>(&mut self, net: &mut Net<B>, batches: I) -> Result<EpochStats, B::Error>
+    where
+        I: Iterator<Item = &'a (B::TensorRef<'b, Dim2>, B::TensorRef<'b, Dim2>)>,
+        B: 'b,
+    {
+        let start = Instant::now();
+        let raw = &mut net.raw;
+        self.scorer.reset(&raw.backend);
+        let mut total_rows = 0;
+        for (input, expected) in batches {
+            let num_rows = input.dims().rows();
+            debug_assert_eq!(num_rows, expected.dims().rows());
+            raw.forward(input.clone(), true);
+            raw.backprop(input.clone(), expected.clone(), &self.config.loss);
+            raw.update_params(&mut self.optimizer);
+            self.scorer
+                .process_batch(&raw.backend, &raw.last_output, expected.clone());
+            raw.accum_loss()?;
+            raw.backend.flush();
+            total_rows += num_rows;
+        }
+        raw.backend.sync();
+        raw.backend.take_error()?;
+        let total_loss = raw.take_loss()?;
+        let stats = EpochStats {
+            epoch: self.epoch,
+            loss: total_loss / total_rows as f64,
+            metrics: self.scorer.metrics(&raw.backend),
+            duration: start.elapsed(),
+        };
+        self.epoch += 1;
+        Ok(stats)
+    }
+}
+
+impl<B: Backend> Debug for Trainer<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Trainer")
+            .field("config", &self.config)
+            .field("optimizer", &self.optimizer)
+            .field("epoch", &self.epoch)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CheckpointConfig, TrainConfig, Trainer};
+    use crate::activation::ActivationFn;
+    use crate::backend::CpuBackend;
+    use crate::loss::LossFn;
+    use crate::net::initializer::RandomNetInitializer;
+    use crate::net::layer::DenseLayerParams;
+    use crate::net::NetBuilder;
+    use crate::optimizer::OptimizerFn;
+    use crate::tensor::{Tensor2, TensorBase};
+
+    #[test]
+    fn test_trainer_history() {
+        let mut net = NetBuilder::new(CpuBackend::<f64>::new(4), 2)
+            .with_initializer(RandomNetInitializer::seed_from_u64(0x1234))
+            .with_layer(DenseLayerParams {
+                size: 8,
+                activation_fn: ActivationFn::Sigmoid,
+                use_bias: true,
+            })
+            .with_layer(DenseLayerParams {
+                size: 1,
+                activation_fn: ActivationFn::Sigmoid,
+                use_bias: true,
+            })
+            .build()
+            .unwrap();
+        let input = Tensor2::from_vec_2d(vec![[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0]]);
+        let expected = Tensor2::from_vec_2d(vec![[0.0], [1.0], [1.0], [0.0]]);
+        let config = TrainConfig {
+            loss: LossFn::BinaryCrossEntropy,
+            optimizer: OptimizerFn::adam(0.05),
+            batch_size: Some(2),
+            num_epochs: 200,
+            shuffle: true,
+            seed: 0x5678,
+            checkpoint: None,
+        };
+        let mut trainer = Trainer::new(config);
+        let history = trainer.train(&mut net, input.view(), expected.view()).unwrap();
+        assert_eq!(history.epochs.len(), 200);
+        assert_eq!(trainer.epoch(), 200);
+        let first_loss = history.epochs[0].loss;
+        let last_loss = history.last().unwrap().loss;
+        assert!(last_loss < first_loss * 0.5, "loss did not decrease: {first_loss} -> {last_loss}");
+
+        // training again does nothing once all epochs have completed
+        assert!(trainer.train(&mut net, input.view(), expected.view()).unwrap().epochs.is_empty());
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        let create_net = || {
+            NetBuilder::new(CpuBackend::<f64>::new(2), 2)
+                .with_initializer(RandomNetInitializer::seed_from_u64(0x1234))
+                .with_layer(DenseLayerParams {
+                    size: 4,
+                    activation_fn: ActivationFn::ReLU { leak: 0.1 },
+                    use_bias: true,
+                })
+                .with_layer(DenseLayerParams {
+                    size: 1,
+                    activation_fn: ActivationFn::Sigmoid,
+                    use_bias: true,
+                })
+                .build()
+                .unwrap()
+        };
+        let input = Tensor2::from_vec_2d(vec![[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0], [0.5, 0.5]]);
+        let expected = Tensor2::from_vec_2d(vec![[0.0], [1.0], [1.0], [0.0], [0.5]]);
+        let path = std::env::temp_dir().join(format!("rcann_test_resume_{}.ckpt", std::process::id()));
+        let config = TrainConfig {
+            loss: LossFn::BinaryCrossEntropy,
+            optimizer: OptimizerFn::nesterov(0.1, 0.9),
+            batch_size: Some(1),
+            num_epochs: 8,
+            shuffle: true,
+            seed: 0x5678,
+            checkpoint: None,
+        };
+
+        let mut net = create_net();
+        Trainer::new(config.clone())
+            .train(&mut net, input.view(), expected.view())
+            .unwrap();
+
+        // train half way, writing a checkpoint at the end
+        let mut interrupted = create_net();
+        let mut first_half = Trainer::new(TrainConfig {
+            num_epochs: 4,
+            checkpoint: Some(CheckpointConfig::new(&path, 3)),
+            ..config.clone()
+        });
+        first_half
+            .train(&mut interrupted, input.view(), expected.view())
+            .unwrap();
+
+        // resume into a freshly built net and trainer
+        let mut resumed = create_net();
+        let mut second_half = Trainer::new(config);
+        second_half.resume_from(&mut resumed, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(second_half.epoch(), 4);
+        let history = second_half.train(&mut resumed, input.view(), expected.view()).unwrap();
+        assert_eq!(history.epochs.len(), 4);
+        assert_eq!(resumed.to_model(), net.to_model());
+    }
+}
@@ -0,0 +1,431 @@
+use crate::backend::Backend;
+use crate::dtype::DType;
+use crate::net::model::{ModelError, ModelResult, ParamTensor};
+use crate::tensor::{Dim1, Dim2, Dims, ITensor};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Formatter};
+use std::mem::discriminant;
+
+/// The update rule used to apply gradients to the parameters of a net
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OptimizerFn {
+    /// Stochastic gradient descent with optional (Nesterov) momentum
+    SGD {
+        learn_rate: f64,
+        momentum: f64,
+        nesterov: bool,
+    },
+    Adagrad {
+        learn_rate: f64,
+        epsilon: f64,
+    },
+    RMSProp {
+        learn_rate: f64,
+        rho: f64,
+        epsilon: f64,
+    },
+    Adam {
+        learn_rate: f64,
+        beta1: f64,
+        beta2: f64,
+        epsilon: f64,
+    },
+    /// Adam with decoupled weight decay
+    AdamW {
+        learn_rate: f64,
+        beta1: f64,
+        beta2: f64,
+        epsilon: f64,
+        weight_decay: f64,
+    },
+}
+
+impl OptimizerFn {
+    pub fn sgd(learn_rate: f64, momentum: f64) -> Self {
+        OptimizerFn::SGD {
+            learn_rate,
+            momentum,
+            nesterov: false,
+        }
+    }
+
+    pub fn nesterov(learn_rate: f64, momentum: f64) -> Self {
+        OptimizerFn::SGD {
+            learn_rate,
+            momentum,
+            nesterov: true,
+        }
+    }
+
+    pub fn adagrad(learn_rate: f64) -> Self {
+        OptimizerFn::Adagrad {
+            learn_rate,
+            epsilon: 1e-8,
+        }
+    }
+
+    pub fn rms_prop(learn_rate: f64) -> Self {
+        OptimizerFn::RMSProp {
+            learn_rate,
+            rho: 0.9,
+            epsilon: 1e-8,
+        }
+    }
+
+    pub fn adam(learn_rate: f64) -> Self {
+        OptimizerFn::Adam {
+            learn_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+        }
+    }
+
+    pub fn adam_w(learn_rate: f64, weight_decay: f64) -> Self {
+        OptimizerFn::AdamW {
+            learn_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            weight_decay,
+        }
+    }
+
+    /// the number of state tensors kept for each parameter tensor
+    fn num_states(&self) -> usize {
+        match self {
+            OptimizerFn::SGD { .. } | OptimizerFn::Adagrad { .. } | OptimizerFn::RMSProp { .. } => 1,
+            OptimizerFn::Adam { .. } | OptimizerFn::AdamW { .. } => 2,
+        }
+    }
+}
+
+impl Default for OptimizerFn {
+    fn default() -> Self {
+        OptimizerFn::sgd(0.1, 0.1)
+    }
+}
+
+/// Applies an [OptimizerFn] to the parameters of a net, and owns the per-parameter state (e.g. momentum) on the backend.
+///
+/// Layers hand their parameters and gradients to [Optimizer::update] in the same order at every step, which
+/// is how each parameter tensor is matched up with its state.
+pub struct Optimizer<B: Backend> {
+    optimizer_fn: OptimizerFn,
+    step: usize,
+    dim1: ParamStates<B, Dim1>,
+    dim2: ParamStates<B, Dim2>,
+}
+
+impl<B: Backend> Optimizer<B> {
+    pub fn new(optimizer_fn: OptimizerFn) -> Self {
+        Optimizer {
+            optimizer_fn,
+            step: 0,
+            dim1: ParamStates::new(),
+            dim2: ParamStates::new(),
+        }
+    }
+
+    #[inline]
+    pub fn optimizer_fn(&self) -> &OptimizerFn {
+        &self.optimizer_fn
+    }
+
+    /// the number of steps which have been started so far
+    #[inline]
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    /// starts a new optimization step. Must be called once per batch before updating any parameters.
+    pub fn begin_step(&mut self) {
+        self.step += 1;
+        self.dim1.cursor = 0;
+        self.dim2.cursor = 0;
+    }
+
+    /// applies the given gradient to the given parameters
+    pub fn update<D: ParamDims>(&mut self, backend: &B, params: &mut B::Tensor<D>, grad: &B::Tensor<D>) {
+        assert!(self.step > 0, "begin_step must be called before updating parameters");
+        assert_eq!(params.dims(), grad.dims(), "Mismatched parameter and gradient dimensions");
+        let optimizer_fn = self.optimizer_fn;
+        let step = self.step as i32;
+        let state = D::param_states(self).next(backend, *params.dims(), optimizer_fn.num_states());
+        let f = B::Float::from_f64;
+        match optimizer_fn {
+            OptimizerFn::SGD {
+                learn_rate,
+                momentum,
+                nesterov,
+            } => {
+                let velocity = &mut state[0];
+                backend.add_assign(B::Float::ONE, grad, f(momentum), velocity);
+                if nesterov {
+                    backend.add_assign(f(-learn_rate), grad, B::Float::ONE, params);
+                    backend.add_assign(f(-learn_rate * momentum), velocity, B::Float::ONE, params);
+                } else {
+                    backend.add_assign(f(-learn_rate), velocity, B::Float::ONE, params);
+                }
+            }
+            OptimizerFn::Adagrad { learn_rate, epsilon } => {
+                backend.adaptive_update(
+                    f(learn_rate),
+                    B::Float::ONE,
+                    B::Float::ONE,
+                    f(epsilon),
+                    grad,
+                    &mut state[0],
+                    params,
+                );
+            }
+            OptimizerFn::RMSProp {
+                learn_rate,
+                rho,
+                epsilon,
+            } => {
+                backend.adaptive_update(
+                    f(learn_rate),
+                    f(rho),
+                    f(1.0 - rho),
+                    f(epsilon),
+                    grad,
+                    &mut state[0],
+                    params,
+                );
+            }
+            OptimizerFn::Adam {
+                learn_rate,
+                beta1,
+                beta2,
+                epsilon,
+            } => {
+                adam_update(backend, step, learn_rate, beta1, beta2, epsilon, 0.0, grad, state, params);
+            }
+            OptimizerFn::AdamW {
+                learn_rate,
+                beta1,
+                beta2,
+                epsilon,
+                weight_decay,
+            } => {
+                let decay = learn_rate * weight_decay;
+                adam_update(backend, step, learn_rate, beta1, beta2, epsilon, decay, grad, state, params);
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn adam_update<B: Backend, D: Dims>(
+    backend: &B,
+    step: i32,
+    learn_rate: f64,
+    beta1: f64,
+    beta2: f64,
+    epsilon: f64,
+    decay: f64,
+    grad: &B::Tensor<D>,
+    state: &mut [B::Tensor<D>],
+    params: &mut B::Tensor<D>,
+) {
+    // fold the bias correction of both moments into the step size and epsilon
+    let correction1 = 1.0 - beta1.powi(step);
+    let correction2 = (1.0 - beta2.powi(step)).sqrt();
+    let step_size = learn_rate * correction2 / correction1;
+    let [m, v] = state else {
+        panic!("Expected two state tensors for Adam");
+    };
+    let f = B::Float::from_f64;
+    backend.adam_update(
+        f(step_size),
+        f(beta1),
+        f(beta2),
+        f(epsilon * correction2),
+        f(decay),
+        grad,
+        m,
+        v,
+        params,
+    );
+}
+
+/// A backend-independent copy of the state of an [Optimizer], e.g. for checkpoints
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OptimizerState {
+    pub optimizer_fn: OptimizerFn,
+    pub step: usize,
+    pub dim1: Vec<Vec<ParamTensor>>,
+    pub dim2: Vec<Vec<ParamTensor>>,
+}
+
+impl<B: Backend> Optimizer<B> {
+    pub fn export_state(&self, backend: &B) -> OptimizerState {
+        OptimizerState {
+            optimizer_fn: self.optimizer_fn,
+            step: self.step,
+            dim1: self.dim1.export(backend),
+            dim2: self.dim2.export(backend),
+        }
+    }
+
+    /// restores state exported by `export_state`. The hyperparameters of this optimizer are kept, but
+    /// the saved state must be for the same kind of optimizer.
+    pub fn import_state(&mut self, backend: &B, state: &OptimizerState) -> ModelResult<()> {
+        if discriminant(&self.optimizer_fn) != discriminant(&state.optimizer_fn) {
+            return Err(ModelError::ParamMismatch(format!(
+                "cannot restore the state of {:?} into {:?}",
+                state.optimizer_fn, self.optimizer_fn
+            )));
+        }
+        let num_states = self.optimizer_fn.num_states();
+        self.dim1.import(backend, &state.dim1, num_states)?;
+        self.dim2.import(backend, &state.dim2, num_states)?;
+        self.step = state.step;
+        Ok(())
+    }
+}
+
+impl<B: Backend> Debug for Optimizer<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Optimizer")
+            .field("optimizer_fn", &self.optimizer_fn)
+            .field("step", &self.step)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The optimizer state for all parameter tensors of a given dimensionality
+pub struct ParamStates<B: Backend, D: Dims> {
+    states: Vec<Box<[B::Tensor<D>]>>,
+    cursor: usize,
+}
+
+impl<B: Backend, D: Dims> ParamStates<B, D> {
+    fn new() -> Self {
+        ParamStates {
+            states: Vec::new(),
+            cursor: 0,
+        }
+    }
+
+    fn export(&self, backend: &B) -> Vec<Vec<ParamTensor>> {
+        self.states
+            .iter()
+            .map(|state| state.iter().map(|t| ParamTensor::read_from::<B, D>(backend, t)).collect())
+            .collect()
+    }
+
+    fn import(&mut self, backend: &B, states: &[Vec<ParamTensor>], num_states: usize) -> ModelResult<()>
+    where
+        D: ParamDims,
+    {
+        let mut imported = Vec::with_capacity(states.len());
+        for state in states.iter() {
+            if state.len() != num_states {
+                return Err(ModelError::ParamMismatch(format!(
+                    "expected {num_states} optimizer state tensors, found {}",
+                    state.len()
+                )));
+            }
+            let tensors = state
+                .iter()
+                .map(|saved| {
+                    let dims = D::from_slice(&saved.dims).ok_or_else(|| {
+                        ModelError::ParamMismatch(format!("invalid optimizer state dims: {:?}", saved.dims))
+                    })?;
+                    let mut tensor = backend.new_tensor_exact(dims);
+                    saved.write_to::<B, D>(backend, &mut tensor)?;
+                    Ok(tensor)
+                })
+                .collect::<ModelResult<Box<[_]>>>()?;
+            imported.push(tensors);
+        }
+        self.states = imported;
+        self.cursor = 0;
+        Ok(())
+    }
+
+    /// gets the state for the next parameter tensor, allocating it on first use
+    fn next(&mut self, backend: &B, dims: D, num_states: usize) -> &mut [B::Tensor<D>] {
+        let idx = self.cursor;
+        self.cursor += 1;
+        if idx == self.states.len() {
+            self.states
+                .push((0..num_states).map(|_| backend.new_tensor_exact(dims)).collect());
+        }
+        let state = &mut self.states[idx];
+        assert_eq!(state[0].dims(), &dims, "Parameters must be updated in the same order at every step");
+        state
+    }
+}
+
+/// Dimensions of parameter tensors which can be updated by an [Optimizer]
+pub trait ParamDims: Dims {
+    fn param_states<B: Backend>(optimizer: &mut Optimizer<B>) -> &mut ParamStates<B, Self>;
+    fn from_slice(dims: &[usize]) -> Option<Self>;
+}
+
+impl ParamDims for Dim1 {
+    #[inline]
+    fn param_states<B: Backend>(optimizer: &mut Optimizer<B>) -> &mut ParamStates<B, Self> {
+        &mut optimizer.dim1
+    }
+    fn from_slice(dims: &[usize]) -> Option<Self> {
+        match dims {
+            &[len] => Some(Dim1(len)),
+            _ => None,
+        }
+    }
+}
+
+impl ParamDims for Dim2 {
+    #[inline]
+    fn param_states<B: Backend>(optimizer: &mut Optimizer<B>) -> &mut ParamStates<B, Self> {
+        &mut optimizer.dim2
+    }
+    fn from_slice(dims: &[usize]) -> Option<Self> {
+        match dims {
+            &[rows, cols] => Some(Dim2(rows, cols)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Optimizer, OptimizerFn};
+    use crate::backend::CpuBackend;
+    use crate::tensor::{Dim1, Tensor1, TensorBase, TensorBaseMut};
+
+    /// minimizes `sum(p^2)` and checks that every parameter moves towards zero
+    fn check_converges(optimizer_fn: OptimizerFn) {
+        let backend = CpuBackend::<f64>::new(0);
+        let mut optimizer = Optimizer::new(optimizer_fn);
+        let mut params = Tensor1::from_vec(vec![1.0, -2.0, 0.5], Dim1(3));
+        let mut grad = Tensor1::zeroed(Dim1(3));
+        for _ in 0..200 {
+            for (g, &p) in grad.iter_mut().zip(params.iter()) {
+                *g = 2.0 * p;
+            }
+            optimizer.begin_step();
+            optimizer.update(&backend, &mut params, &grad);
+        }
+        for &p in params.iter() {
+            assert!(p.abs() < 0.1, "{optimizer_fn:?} did not converge: {p}");
+        }
+    }
+
+    #[test]
+    fn test_optimizers_converge() {
+        check_converges(OptimizerFn::sgd(0.1, 0.5));
+        check_converges(OptimizerFn::nesterov(0.1, 0.5));
+        check_converges(OptimizerFn::adagrad(0.5));
+        check_converges(OptimizerFn::rms_prop(0.05));
+        check_converges(OptimizerFn::adam(0.05));
+        check_converges(OptimizerFn::adam_w(0.05, 0.01));
+    }
+}
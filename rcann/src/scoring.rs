@@ -1,11 +1,19 @@
 use crate::backend::Backend;
 use crate::dtype::DType;
 use crate::net::Net;
-use crate::tensor::{Dim2, ITensor, TensorBase, TensorBaseMut};
+use crate::tensor::{Dim2, ITensor, Tensor2, TensorBase, TensorBaseMut};
 use std::fmt::Debug;
 
 pub trait Scorer<B: Backend> {
     fn process_batch(&mut self, backend: &B, output: &B::Tensor<Dim2>, expected: B::TensorRef<'_, Dim2>);
+
+    /// clears the state accumulated by `process_batch`, e.g. at the start of each training epoch
+    fn reset(&mut self, _backend: &B) {}
+
+    /// named metrics computed from the batches processed since the last reset
+    fn metrics(&self, _backend: &B) -> Vec<(String, f64)> {
+        Vec::new()
+    }
 }
 
 pub struct NoOpScorer;
@@ -52,4 +60,21 @@ impl<B: Backend> Scorer<B> for MulticlassScorer<B> {
         self.count += output.dims().rows();
         backend.accum_confusion_matrix_multiclass(&mut self.matrix, output, expected);
     }
+
+    fn reset(&mut self, backend: &B) {
+        let zeroed = Tensor2::zeroed(*self.matrix.dims());
+        backend.write_tensor(&mut self.matrix, &zeroed);
+        self.count = 0;
+    }
+
+    fn metrics(&self, backend: &B) -> Vec<(String, f64)> {
+        let matrix = backend.tensor_as_native(&self.matrix);
+        let total_correct: usize = (0..matrix.dims().rows()).map(|i| matrix[[i, i]].to_usize()).sum();
+        let accuracy = if self.count == 0 {
+            0.0
+        } else {
+            total_correct as f64 / self.count as f64
+        };
+        vec![("accuracy".to_string(), accuracy)]
+    }
 }